@@ -0,0 +1,525 @@
+use crate::car::components::{CarControls, Velocity};
+use crate::car::systems::{braking_deceleration, car_physics_system, turn_rate};
+use crate::course::Course;
+use crate::course::mesh;
+use crate::events::{DrsToggled, GearChanged};
+use crate::game::OVERHEAT_TEMPERATURE;
+use crate::resources::{CarProgress, CarState, CarStatus, GameSession};
+use crate::settings::CycleSetting;
+use crate::states::{InGame, Racing};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Spacing of the racing line's points along the course (m).
+const LINE_STEP: f32 = 10.0;
+/// Relaxation passes pulling the racing line straight between the track edges.
+const LINE_PASSES: usize = 2000;
+/// How far past the smoothest spot each pass moves a point, to settle the line sooner.
+const LINE_RELAXATION: f32 = 1.7;
+/// Room the racing line leaves to the edge of the road (m).
+const LINE_EDGE_MARGIN: f32 = 1.5;
+/// Radius counted as a straight (m).
+const STRAIGHT_RADIUS: f32 = 10_000.0;
+
+/// How far ahead drivers look for corners to brake for (m).
+const BRAKING_LOOKAHEAD: f32 = 200.0;
+/// Drivers only brake once they're this much faster than they want to be (m/s).
+const BRAKE_MARGIN: f32 = 1.0;
+/// Heading error (radians) at which drivers steer at full lock.
+const FULL_LOCK_ANGLE: f32 = 0.3;
+/// Corners at least this wide still count as straight for opening DRS (m).
+const DRS_RADIUS: f32 = 400.0;
+/// Straight ahead needed before a fully aggressive driver opens DRS (m).
+const DRS_LOOKAHEAD: f32 = 150.0;
+/// Share of the race distance covered before fuel use is judged.
+const FUEL_JUDGE_PROGRESS: f32 = 0.05;
+/// Fuel kept in hand over what the rest of the race needs.
+const FUEL_MARGIN: f32 = 1.1;
+/// Share of its usual speed a driver drives at while saving fuel or cooling the engine.
+const SAVING_PACE: f32 = 0.9;
+/// Degrees below meltdown at which drivers start to nurse the engine.
+const HEAT_MARGIN: f32 = 20.0;
+
+/// How good the computer drivers are, picked on the settings screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+/// What a difficulty means behind the wheel.
+#[derive(Debug, Clone, Copy)]
+pub struct DriverSkill {
+    pub reaction_time: f32, // Delay between seeing the track and the inputs (s)
+    pub line_error: f32,    // How far it wanders off the racing line (m)
+    pub aggression: f32,    // Share of the car's limits it uses (0 to 1)
+}
+
+impl CycleSetting for AiDifficulty {
+    const NAME: &'static str = "AI Difficulty";
+
+    fn next(&self) -> Self {
+        match self {
+            AiDifficulty::Easy => AiDifficulty::Medium,
+            AiDifficulty::Medium => AiDifficulty::Hard,
+            AiDifficulty::Hard => AiDifficulty::Easy,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            AiDifficulty::Easy => "Easy",
+            AiDifficulty::Medium => "Medium",
+            AiDifficulty::Hard => "Hard",
+        }
+        .to_string()
+    }
+}
+
+impl AiDifficulty {
+    pub fn skill(&self) -> DriverSkill {
+        match self {
+            AiDifficulty::Easy => DriverSkill {
+                reaction_time: 0.35,
+                line_error: 2.5,
+                aggression: 0.6,
+            },
+            AiDifficulty::Medium => DriverSkill {
+                reaction_time: 0.2,
+                line_error: 1.2,
+                aggression: 0.8,
+            },
+            AiDifficulty::Hard => DriverSkill {
+                reaction_time: 0.08,
+                line_error: 0.3,
+                aggression: 1.0,
+            },
+        }
+    }
+}
+
+/// Racing line of a course: the path that straightens the corners out as much as the road
+/// allows, going wide into a corner, clipping the apex and running wide again on the exit.
+#[derive(Resource, Debug, Clone)]
+pub struct RacingLine {
+    start: f32,   // Distance along the course of the first point (m)
+    step: f32,    // Spacing of the points (m)
+    closed: bool, // The line loops around a circuit
+    points: Vec<LinePoint>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LinePoint {
+    lateral: f32, // Offset from the centreline (m)
+    radius: f32,  // Radius of the line's turn here (m)
+}
+
+impl RacingLine {
+    /// Works out the racing line by repeatedly moving every point to where it bends the
+    /// line least between its neighbours, keeping it on the road.
+    pub fn new(course: &Course) -> Self {
+        let closed = course.is_circuit();
+        let start = mesh::geometry_start(course);
+        // Around a circuit the points are spread evenly over a lap, so the line closes up
+        let span = course.length() - start;
+        let (count, step) = if closed {
+            let count = (span / LINE_STEP).round().max(5.0) as usize;
+            (count, span / count as f32)
+        } else {
+            ((span / LINE_STEP).ceil() as usize + 1, LINE_STEP)
+        };
+        let frames: Vec<_> = (0..count)
+            .map(|i| course.frame_at(start + i as f32 * step))
+            .collect();
+        let limits: Vec<f32> = frames
+            .iter()
+            .map(|frame| (frame.width / 2.0 - LINE_EDGE_MARGIN).max(0.0))
+            .collect();
+
+        // Index of the point `offset` places away, if the line goes that far
+        let neighbour = |i: usize, offset: isize| -> Option<usize> {
+            let j = i as isize + offset;
+            if closed {
+                Some(j.rem_euclid(count as isize) as usize)
+            } else {
+                (0..count as isize).contains(&j).then_some(j as usize)
+            }
+        };
+        let mut laterals = vec![0.0; count];
+        let point = |laterals: &[f32], j: usize| frames[j].point(laterals[j]);
+        for _ in 0..LINE_PASSES {
+            for i in 0..count {
+                let (Some(a), Some(b), Some(c), Some(d)) = (
+                    neighbour(i, -2),
+                    neighbour(i, -1),
+                    neighbour(i, 1),
+                    neighbour(i, 2),
+                ) else {
+                    continue;
+                };
+                // Minimises the squared second differences around the point
+                let smooth = ((point(&laterals, b) + point(&laterals, c)) * 4.0
+                    - point(&laterals, a)
+                    - point(&laterals, d))
+                    / 6.0;
+                let settled = (smooth - frames[i].position).dot(frames[i].right);
+                laterals[i] = (laterals[i] + (settled - laterals[i]) * LINE_RELAXATION)
+                    .clamp(-limits[i], limits[i]);
+            }
+        }
+
+        let points = (0..count)
+            .map(|i| LinePoint {
+                lateral: laterals[i],
+                radius: match (neighbour(i, -1), neighbour(i, 1)) {
+                    (Some(before), Some(after)) => turn_radius(
+                        point(&laterals, before),
+                        point(&laterals, i),
+                        point(&laterals, after),
+                    ),
+                    _ => STRAIGHT_RADIUS,
+                },
+            })
+            .collect();
+        Self {
+            start,
+            step,
+            closed,
+            points,
+        }
+    }
+
+    /// The two points either side of a distance and how far it is between them.
+    fn around(&self, distance: f32) -> (LinePoint, LinePoint, f32) {
+        let count = self.points.len();
+        let along = (distance - self.start) / self.step;
+        let (index, t) = if self.closed {
+            let along = along.rem_euclid(count as f32);
+            (along.floor() as usize % count, along.fract())
+        } else {
+            let along = along.clamp(0.0, (count - 1) as f32);
+            let index = (along.floor() as usize).min(count.saturating_sub(2));
+            (index, along - index as f32)
+        };
+        let next = if self.closed {
+            (index + 1) % count
+        } else {
+            (index + 1).min(count - 1)
+        };
+        (self.points[index], self.points[next], t)
+    }
+
+    /// Offset of the racing line from the centreline at a distance along the course (m).
+    pub fn lateral_at(&self, distance: f32) -> f32 {
+        let (a, b, t) = self.around(distance);
+        a.lateral + (b.lateral - a.lateral) * t
+    }
+
+    /// Radius of the racing line's turn at a distance along the course (m).
+    pub fn radius_at(&self, distance: f32) -> f32 {
+        let (a, b, t) = self.around(distance);
+        a.radius + (b.radius - a.radius) * t
+    }
+}
+
+/// Radius of the circle through three points seen from above.
+fn turn_radius(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let (a, b, c) = (a.xz(), b.xz(), c.xz());
+    let twice_area = (b - a).perp_dot(c - a).abs();
+    if twice_area < f32::EPSILON {
+        return STRAIGHT_RADIUS;
+    }
+    (a.distance(b) * b.distance(c) * c.distance(a) / (2.0 * twice_area)).min(STRAIGHT_RADIUS)
+}
+
+/// Fastest a car can go at a distance along the racing line and still brake in time for
+/// every corner within `BRAKING_LOOKAHEAD` metres (m/s). Corners are taken as fast as the
+/// car can turn (`turn_rate`, radians per second) and braked for at `deceleration`.
+pub fn target_speed(
+    line: &RacingLine,
+    distance: f32,
+    top_speed: f32,
+    turn_rate: f32,
+    deceleration: f32,
+) -> f32 {
+    (0..=(BRAKING_LOOKAHEAD / LINE_STEP) as usize)
+        .map(|i| i as f32 * LINE_STEP)
+        .map(|ahead| {
+            let corner_speed = turn_rate * line.radius_at(distance + ahead);
+            (corner_speed * corner_speed + 2.0 * deceleration * ahead).sqrt()
+        })
+        .fold(top_speed, f32::min)
+}
+
+/// Inputs an AI driver has decided on, waiting for its reaction time to pass.
+#[derive(Debug, Clone, Copy)]
+struct DriverInputs {
+    controls: CarControls,
+    gear: i32,
+    drs: bool,
+}
+
+/// A car driven by the computer. It drives with the same controls the player has: throttle,
+/// brake and steering, the gears and DRS.
+#[derive(Component, Debug)]
+pub struct AiDriver {
+    pace: f32,                              // Share of its car's top speed it drives at
+    seed: f32,                              // Sets how its line error wanders
+    start_fuel: Option<f32>,                // Fuel it started the race with
+    pending: VecDeque<(f32, DriverInputs)>, // Inputs and when they reach the car
+}
+
+impl AiDriver {
+    pub fn new(pace: f32, seed: f32) -> Self {
+        Self {
+            pace,
+            seed,
+            start_fuel: None,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// Plugin that lets the computer drive cars carrying an `AiDriver`.
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AiDifficulty>()
+            .add_systems(
+                Update,
+                ai_driver_system
+                    .before(car_physics_system)
+                    .run_if(in_state(Racing).and(resource_exists::<RacingLine>)),
+            )
+            .add_systems(OnExit(InGame), |mut commands: Commands| {
+                commands.remove_resource::<RacingLine>();
+            });
+    }
+}
+
+/// Drives every AI car round the racing line: on throttle until a corner ahead needs it to
+/// brake, steering for a point ahead on the line (give or take its line error) and
+/// shifting with its speed. It eases off when the fuel won't last or the engine runs hot,
+/// and slows to a stop once its run is over. What it decides reaches the car only after
+/// its reaction time.
+#[allow(clippy::type_complexity)]
+fn ai_driver_system(
+    time: Res<Time>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    line: Res<RacingLine>,
+    difficulty: Res<AiDifficulty>,
+    mut drivers: Query<(
+        Entity,
+        &mut AiDriver,
+        &Transform,
+        &Velocity,
+        &CarStatus,
+        &CarProgress,
+        &mut CarControls,
+        &mut CarState,
+    )>,
+    (mut gear_changes, mut drs_toggles): (MessageWriter<GearChanged>, MessageWriter<DrsToggled>),
+) {
+    if session.is_game_over {
+        return;
+    }
+    let skill = difficulty.skill();
+    let now = time.elapsed_secs();
+
+    for (car, mut driver, transform, velocity, car_status, progress, mut controls, mut state) in
+        &mut drivers
+    {
+        let distance = progress.distance;
+        let speed = velocity.0.length();
+        let top_speed = car_status.max_speed / 3.6;
+
+        // Fuel: compare what's left with what the race so far says the rest needs
+        let start_fuel = *driver.start_fuel.get_or_insert(state.fuel);
+        let race_share = course.progress(
+            distance,
+            progress.timing.laps.len() as u32,
+            progress.timing.next_gate,
+        );
+        let saving_fuel = race_share > FUEL_JUDGE_PROGRESS
+            && state.fuel
+                < (start_fuel - state.fuel) / race_share * (1.0 - race_share) * FUEL_MARGIN;
+        let running_hot = state.temp > OVERHEAT_TEMPERATURE - HEAT_MARGIN;
+        let easing_off = saving_fuel || running_hot;
+
+        let target = if progress.ended.is_some() {
+            0.0
+        } else {
+            let corner_share = 0.75 + 0.25 * skill.aggression;
+            let brake_share = 0.6 + 0.4 * skill.aggression;
+            let speed = target_speed(
+                &line,
+                distance,
+                top_speed * driver.pace,
+                turn_rate(car_status) * corner_share,
+                braking_deceleration(car_status) * brake_share,
+            );
+            if easing_off {
+                speed * SAVING_PACE
+            } else {
+                speed
+            }
+        };
+
+        // Steer for a point ahead on the line, further ahead the faster the car goes and
+        // the slower the driver reacts
+        let aim_distance = distance + (speed * (0.8 + skill.reaction_time)).max(10.0);
+        let wander = (aim_distance / 97.0 + driver.seed).sin();
+        let aim = course
+            .frame_at(aim_distance)
+            .point(line.lateral_at(aim_distance) + wander * skill.line_error);
+        let wanted = (aim - transform.translation)
+            .with_y(0.0)
+            .normalize_or_zero();
+        let forward = transform.forward().with_y(0.0).normalize_or_zero();
+        let turn = forward.cross(wanted).y.atan2(forward.dot(wanted));
+
+        // Highest gear that still pulls at this speed
+        let speed_ratio = if top_speed > 0.0 {
+            speed / top_speed
+        } else {
+            0.0
+        };
+        let gear = ((speed_ratio * 6.0).floor() as i32 + 1).clamp(1, 6);
+
+        // DRS on a long enough straight, unless nursing the car
+        let drs_lookahead = DRS_LOOKAHEAD * (2.0 - skill.aggression);
+        let straight_ahead = (0..=(drs_lookahead / LINE_STEP) as usize)
+            .all(|i| line.radius_at(distance + i as f32 * LINE_STEP) >= DRS_RADIUS);
+        let drs = straight_ahead && !easing_off && progress.ended.is_none();
+
+        driver.pending.push_back((
+            now + skill.reaction_time,
+            DriverInputs {
+                controls: CarControls {
+                    throttle: speed < target,
+                    brake: speed > target + BRAKE_MARGIN,
+                    steer: -(turn / FULL_LOCK_ANGLE).clamp(-1.0, 1.0),
+                },
+                gear,
+                drs,
+            },
+        ));
+        while driver.pending.front().is_some_and(|(due, _)| *due <= now) {
+            let (_, inputs) = driver.pending.pop_front().unwrap();
+            *controls = inputs.controls;
+            if state.gear != inputs.gear {
+                state.gear = inputs.gear;
+                gear_changes.write(GearChanged {
+                    car,
+                    gear: inputs.gear,
+                });
+            }
+            if state.drs_enabled != inputs.drs {
+                state.drs_enabled = inputs.drs;
+                drs_toggles.write(DrsToggled {
+                    car,
+                    enabled: inputs.drs,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::car::car_bundle;
+    use crate::course::file::CourseFile;
+    use crate::events::PenaltyApplied;
+    use crate::race::opponent_spec;
+    use crate::resources::BaseCarStatus;
+    use crate::weather::{TrackConditions, Weather};
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+    use std::time::Duration;
+
+    /// How far an AI car gets on course_1 in ten seconds from the grid.
+    fn distance_covered(difficulty: AiDifficulty) -> f32 {
+        let course =
+            CourseFile::parse(include_bytes!("../assets/courses/course_1.course.ron")).unwrap();
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 30.0,
+            )))
+            .add_message::<GearChanged>()
+            .add_message::<DrsToggled>()
+            .add_message::<PenaltyApplied>()
+            .insert_resource(TrackConditions::new(&course, Weather::Dry, false))
+            .insert_resource(RacingLine::new(&course))
+            .insert_resource(difficulty)
+            .init_resource::<GameSession>()
+            .add_systems(Update, (ai_driver_system, car_physics_system).chain());
+        let transform = course.grid_transform(&course.start_grid.slots[0]);
+        let start = course.project(transform.translation).distance;
+        let car = app
+            .world_mut()
+            .spawn((
+                car_bundle(
+                    opponent_spec(0, &BaseCarStatus::default()),
+                    transform,
+                    start,
+                ),
+                AiDriver::new(0.9, 0.0),
+            ))
+            .id();
+        app.insert_resource(course);
+        for _ in 0..300 {
+            app.update();
+        }
+        app.world().get::<CarProgress>(car).unwrap().distance - start
+    }
+
+    #[test]
+    fn drivers_get_round_the_course() {
+        let easy = distance_covered(AiDifficulty::Easy);
+        let hard = distance_covered(AiDifficulty::Hard);
+        assert!(easy > 30.0, "easy driver only covered {easy} m");
+        assert!(hard > easy, "hard {hard} m, easy {easy} m");
+    }
+
+    #[test]
+    fn racing_line_straightens_the_corners() {
+        let course =
+            CourseFile::parse(include_bytes!("../assets/courses/course_2.course.ron")).unwrap();
+        let line = RacingLine::new(&course);
+        // Total squared curvature along a lap
+        let bend = |radius: &dyn Fn(f32) -> f32| -> f32 {
+            (0..(course.length() / LINE_STEP) as usize)
+                .map(|i| radius(i as f32 * LINE_STEP).powi(-2))
+                .sum()
+        };
+        let centreline = bend(&|distance| {
+            turn_radius(
+                course.frame_at(distance - LINE_STEP).position,
+                course.frame_at(distance).position,
+                course.frame_at(distance + LINE_STEP).position,
+            )
+        });
+        assert!(bend(&|distance| line.radius_at(distance)) < centreline * 0.9);
+        for point in &line.points {
+            assert!(point.lateral.abs() <= course.half_width());
+        }
+    }
+
+    #[test]
+    fn drivers_brake_for_corners() {
+        let circuit =
+            CourseFile::parse(include_bytes!("../assets/courses/circuit_1.course.ron")).unwrap();
+        let line = RacingLine::new(&circuit);
+        let top_speed = 100.0;
+        let speed_at = |distance| target_speed(&line, distance, top_speed, 0.3, 12.0);
+        // Middle of the start straight, braking for the first turn, then in the turn
+        assert_eq!(speed_at(20.0), top_speed);
+        assert!(speed_at(200.0) < top_speed);
+        assert!(speed_at(400.0) < top_speed / 2.0);
+    }
+}
@@ -92,6 +92,7 @@ fn cleanup_calc_info(mut commands: Commands, query: Query<Entity, With<CalcUi>>)
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn interact_calc(
     mut query: Query<
         (&Interaction, &mut BackgroundColor),
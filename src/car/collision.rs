@@ -0,0 +1,323 @@
+use crate::car::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Radius of the sphere used to approximate the car body against obstacles.
+pub const CAR_COLLISION_RADIUS: f32 = 1.5;
+/// Impacts slower than this (m/s along the normal) only scrape and cause no damage.
+const DAMAGE_SPEED_THRESHOLD: f32 = 3.0;
+/// Damage added per m/s of impact speed above the threshold.
+const DAMAGE_PER_IMPACT_SPEED: f32 = 0.02;
+/// Fraction of the tangential speed lost per impact (scraping along the wall).
+const SCRAPE_SPEED_LOSS: f32 = 0.15;
+/// Seconds after an impact during which the car slides freely instead of
+/// following its heading, so the bounce isn't thrown away by the physics step.
+const IMPACT_SLIP_TIME: f32 = 0.4;
+/// How much of the closing speed survives a car-to-car bump.
+const CAR_RESTITUTION: f32 = 0.3;
+
+/// Resolves collisions between every car and every `Obstacle` in the world.
+/// A car's movement this frame is swept against each nearby obstacle so fast cars
+/// can't tunnel through thin barriers. On contact the car is pushed out, its velocity
+/// gets an impulse along the contact normal and any hard impact is accumulated as
+/// `CarDamage` on the car.
+pub fn car_collision_system(
+    time: Res<Time>,
+    session: Res<GameSession>,
+    obstacles: Query<(&Transform, &Obstacle), Without<CarState>>,
+    mut cars: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &mut CollisionState,
+        &mut CarState,
+    )>,
+) {
+    if session.is_game_over {
+        return;
+    }
+
+    for (mut transform, mut velocity, mut collision, mut car) in &mut cars {
+        collision.slip_timer = (collision.slip_timer - time.delta_secs()).max(0.0);
+
+        let start = collision.previous_translation;
+        let end = transform.translation;
+        let travel = (end - start).length();
+
+        for (obstacle_transform, obstacle) in &obstacles {
+            // Cheap reject: skip obstacles that can't be reached by the sweep this frame
+            let reach = obstacle.half_extents.length() + CAR_COLLISION_RADIUS + travel;
+            if (end - obstacle_transform.translation).length_squared() > reach * reach {
+                continue;
+            }
+
+            let Some((position, normal, penetration)) =
+                swept_contact(start, transform.translation, obstacle_transform, obstacle)
+            else {
+                continue;
+            };
+
+            // Move the car back to where it first touched, out of the obstacle, and let the
+            // rest of this frame's movement slide along the surface
+            let remaining = transform.translation - position;
+            let slide = remaining - normal * remaining.dot(normal).min(0.0);
+            transform.translation = position + normal * penetration + slide;
+
+            // Impulse response along the contact normal
+            let normal_speed = velocity.0.dot(normal);
+            if normal_speed >= 0.0 {
+                continue; // Already separating
+            }
+            let impact_speed = -normal_speed;
+            let normal_velocity = normal * normal_speed;
+            let tangent_velocity = velocity.0 - normal_velocity;
+            velocity.0 = tangent_velocity * (1.0 - SCRAPE_SPEED_LOSS)
+                - normal_velocity * obstacle.restitution;
+            collision.slip_timer = IMPACT_SLIP_TIME;
+
+            // Glancing hits turn the car along the wall; head-on hits leave the heading alone
+            let forward = *transform.forward();
+            let along_wall = (forward - normal * forward.dot(normal).min(0.0))
+                .with_y(0.0)
+                .normalize_or_zero();
+            if along_wall != Vec3::ZERO {
+                let target = transform.translation + along_wall;
+                transform.look_at(target, Vec3::Y);
+            }
+
+            apply_impact_damage(&mut car.damage, impact_speed, forward, normal);
+        }
+
+        collision.previous_translation = transform.translation;
+    }
+}
+
+/// Resolves contact between cars. Touching cars are pushed apart and, being about equally
+/// heavy, swap the parts of their velocities along the line between them, minus what the
+/// bump absorbs. A hard bump damages both cars.
+pub fn car_contact_system(
+    session: Res<GameSession>,
+    mut cars: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &mut CollisionState,
+        &mut CarState,
+    )>,
+) {
+    if session.is_game_over {
+        return;
+    }
+
+    let mut pairs = cars.iter_combinations_mut();
+    while let Some([a, b]) = pairs.fetch_next() {
+        let (mut a_transform, mut a_velocity, mut a_collision, mut a_car) = a;
+        let (mut b_transform, mut b_velocity, mut b_collision, mut b_car) = b;
+
+        let Some((normal, penetration)) =
+            car_contact(a_transform.translation, b_transform.translation)
+        else {
+            continue;
+        };
+        a_transform.translation -= normal * penetration / 2.0;
+        b_transform.translation += normal * penetration / 2.0;
+
+        // Speed at which they close in on each other along the normal
+        let closing = (a_velocity.0 - b_velocity.0).dot(normal);
+        if closing <= 0.0 {
+            continue; // Already separating
+        }
+        let impulse = normal * closing * (1.0 + CAR_RESTITUTION) / 2.0;
+        a_velocity.0 -= impulse;
+        b_velocity.0 += impulse;
+        a_collision.slip_timer = IMPACT_SLIP_TIME;
+        b_collision.slip_timer = IMPACT_SLIP_TIME;
+
+        let a_forward = *a_transform.forward();
+        let b_forward = *b_transform.forward();
+        apply_impact_damage(&mut a_car.damage, closing, a_forward, -normal);
+        apply_impact_damage(&mut b_car.damage, closing, b_forward, normal);
+    }
+}
+
+/// Returns the horizontal contact normal (pointing from `a` to `b`) and penetration depth
+/// of two car spheres, or `None` if they don't touch.
+pub fn car_contact(a: Vec3, b: Vec3) -> Option<(Vec3, f32)> {
+    let offset = (b - a).with_y(0.0);
+    let distance = offset.length();
+    if distance >= CAR_COLLISION_RADIUS * 2.0 {
+        return None;
+    }
+    // Cars exactly on top of each other are pushed apart sideways
+    let normal = if distance > f32::EPSILON {
+        offset / distance
+    } else {
+        Vec3::X
+    };
+    Some((normal, CAR_COLLISION_RADIUS * 2.0 - distance))
+}
+
+/// Adds the damage of a single impact. Head-on hits hurt the engine, side swipes hurt
+/// the steering, and every hard hit costs some aero.
+pub fn apply_impact_damage(damage: &mut CarDamage, impact_speed: f32, forward: Vec3, normal: Vec3) {
+    if impact_speed <= DAMAGE_SPEED_THRESHOLD {
+        return;
+    }
+    let amount = (impact_speed - DAMAGE_SPEED_THRESHOLD) * DAMAGE_PER_IMPACT_SPEED;
+    let frontal = (-forward.dot(normal)).clamp(0.0, 1.0);
+    damage.engine = (damage.engine + amount * frontal).min(1.0);
+    damage.steering = (damage.steering + amount * (1.0 - frontal)).min(1.0);
+    damage.aero = (damage.aero + amount * 0.5).min(1.0);
+    damage.impact += amount;
+}
+
+/// Sweeps the car sphere from `start` to `end` and returns the first touching position
+/// together with the contact normal and penetration depth at that position.
+fn swept_contact(
+    start: Vec3,
+    end: Vec3,
+    box_transform: &Transform,
+    obstacle: &Obstacle,
+) -> Option<(Vec3, Vec3, f32)> {
+    // Step at half the car radius so no step can skip over the sphere's reach
+    let steps = ((end - start).length() / (CAR_COLLISION_RADIUS * 0.5))
+        .ceil()
+        .max(1.0) as usize;
+    (1..=steps).find_map(|i| {
+        let position = start.lerp(end, i as f32 / steps as f32);
+        sphere_box_contact(position, box_transform, obstacle)
+            .map(|(normal, penetration)| (position, normal, penetration))
+    })
+}
+
+/// Returns the horizontal contact normal (pointing out of the box) and penetration depth
+/// of the car sphere against an oriented box, or `None` if they don't touch.
+pub fn sphere_box_contact(
+    center: Vec3,
+    box_transform: &Transform,
+    obstacle: &Obstacle,
+) -> Option<(Vec3, f32)> {
+    let inverse_rotation = box_transform.rotation.inverse();
+    let local = inverse_rotation * (center - box_transform.translation);
+    let half = obstacle.half_extents;
+
+    // Ignore obstacles the car is fully above or below
+    if local.y.abs() > half.y + CAR_COLLISION_RADIUS {
+        return None;
+    }
+
+    let closest = local.clamp(-half, half);
+    let offset = Vec3::new(local.x - closest.x, 0.0, local.z - closest.z);
+    let distance = offset.length();
+
+    let (local_normal, penetration) = if distance > f32::EPSILON {
+        if distance >= CAR_COLLISION_RADIUS {
+            return None;
+        }
+        (offset / distance, CAR_COLLISION_RADIUS - distance)
+    } else {
+        // Centre is inside the box: leave through the nearest horizontal face
+        let depth_x = half.x - local.x.abs();
+        let depth_z = half.z - local.z.abs();
+        if depth_x < depth_z {
+            (Vec3::X * local.x.signum(), depth_x + CAR_COLLISION_RADIUS)
+        } else {
+            (Vec3::Z * local.z.signum(), depth_z + CAR_COLLISION_RADIUS)
+        }
+    };
+
+    let normal = (box_transform.rotation * local_normal)
+        .with_y(0.0)
+        .normalize_or_zero();
+    if normal == Vec3::ZERO {
+        return None;
+    }
+    Some((normal, penetration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn barrier() -> Obstacle {
+        Obstacle::new(Vec3::new(2.0, 1.5, 10.0))
+    }
+
+    #[test]
+    fn no_contact_when_far_away() {
+        let transform = Transform::from_xyz(0.0, 0.0, 0.0);
+        assert!(sphere_box_contact(Vec3::new(5.0, 0.0, 0.0), &transform, &barrier()).is_none());
+    }
+
+    #[test]
+    fn edge_contact_pushes_out_along_face() {
+        let transform = Transform::from_xyz(0.0, 0.0, 0.0);
+        let (normal, penetration) =
+            sphere_box_contact(Vec3::new(2.0, 0.0, 0.0), &transform, &barrier()).unwrap();
+        assert!(normal.abs_diff_eq(Vec3::X, 1e-5));
+        assert!((penetration - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn centre_inside_leaves_through_nearest_face() {
+        let transform = Transform::from_xyz(0.0, 0.0, 0.0);
+        let (normal, penetration) =
+            sphere_box_contact(Vec3::new(-0.5, 0.0, 1.0), &transform, &barrier()).unwrap();
+        assert!(normal.abs_diff_eq(Vec3::NEG_X, 1e-5));
+        assert!((penetration - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rotated_box_rotates_normal() {
+        let transform =
+            Transform::from_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        // The long axis now runs along X, so the near face is at z = -1
+        let (normal, _) =
+            sphere_box_contact(Vec3::new(3.0, 0.0, -2.0), &transform, &barrier()).unwrap();
+        assert!(normal.abs_diff_eq(Vec3::NEG_Z, 1e-5));
+        assert!(sphere_box_contact(Vec3::new(3.0, 0.0, -3.0), &transform, &barrier()).is_none());
+    }
+
+    #[test]
+    fn sweep_catches_fast_movement_through_barrier() {
+        let transform = Transform::from_xyz(0.0, 0.0, 0.0);
+        let (position, normal, _) = swept_contact(
+            Vec3::new(-10.0, 0.0, 0.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            &transform,
+            &barrier(),
+        )
+        .unwrap();
+        assert!(position.x < 0.0);
+        assert!(normal.abs_diff_eq(Vec3::NEG_X, 1e-5));
+    }
+
+    #[test]
+    fn frontal_hit_damages_engine_side_hit_damages_steering() {
+        let mut frontal = CarDamage::default();
+        apply_impact_damage(&mut frontal, 20.0, Vec3::NEG_Z, Vec3::Z);
+        assert!(frontal.engine > 0.0);
+        assert_eq!(frontal.steering, 0.0);
+
+        let mut side = CarDamage::default();
+        apply_impact_damage(&mut side, 20.0, Vec3::NEG_Z, Vec3::X);
+        assert_eq!(side.engine, 0.0);
+        assert!(side.steering > 0.0);
+
+        // Both hits were equally hard, so they count the same towards a wreck
+        assert_eq!(frontal.impact, side.impact);
+    }
+
+    #[test]
+    fn touching_cars_are_pushed_apart() {
+        let (normal, penetration) = car_contact(Vec3::ZERO, Vec3::new(0.0, 0.5, 2.0)).unwrap();
+        assert!(normal.abs_diff_eq(Vec3::Z, 1e-5));
+        assert!((penetration - 1.0).abs() < 1e-5);
+        assert!(car_contact(Vec3::ZERO, Vec3::new(3.5, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn slow_contact_causes_no_damage() {
+        let mut damage = CarDamage::default();
+        apply_impact_damage(&mut damage, DAMAGE_SPEED_THRESHOLD, Vec3::NEG_Z, Vec3::Z);
+        assert_eq!(damage.impact, 0.0);
+    }
+}
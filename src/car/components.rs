@@ -1,10 +1,67 @@
 use bevy::prelude::*;
 
+/// Wheel positions relative to the car's centre (left/right, front/rear).
+pub const WHEEL_OFFSETS: [Vec3; 4] = [
+    Vec3::new(-0.9, -0.5, -1.6),
+    Vec3::new(0.9, -0.5, -1.6),
+    Vec3::new(-0.9, -0.5, 1.6),
+    Vec3::new(0.9, -0.5, 1.6),
+];
+
 /// Marker component for the player's car.
 #[derive(Component)]
 pub struct PlayerCar;
 
+/// What the driver of a car is asking for this frame, from the keyboard for the player
+/// or from the computer for opponents. Gear and DRS are changed on the `CarState`.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct CarControls {
+    pub throttle: bool,
+    pub brake: bool,
+    pub steer: f32, // -1.0 = full left, 1.0 = full right
+}
+
+/// Where a car's hardware readings (its `PcStatus`) come from.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareSource {
+    Local,     // This PC's sensors, refreshed while driving
+    Synthetic, // Made-up readings from its `SyntheticHardware`
+}
+
 /// Component to store the current velocity of an entity.
 /// In this game, velocity is primarily use for forward movement and gravity.
 #[derive(Component, Default, Debug)]
 pub struct Velocity(pub Vec3);
+
+/// Solid box that cars collide with (barriers, walls, other track obstacles).
+/// The box is centred on the entity's Transform and rotated with it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Obstacle {
+    pub half_extents: Vec3, // Half size of the box in local space
+    pub restitution: f32,   // 0.0 = dead stop along the normal, 1.0 = perfect bounce
+}
+
+impl Obstacle {
+    pub fn new(size: Vec3) -> Self {
+        Self {
+            half_extents: size / 2.0,
+            restitution: 0.3,
+        }
+    }
+}
+
+/// Per-car collision bookkeeping used to sweep movement and to let the car slide after impacts.
+#[derive(Component, Default, Debug)]
+pub struct CollisionState {
+    pub previous_translation: Vec3, // Position at the end of the last collision pass
+    pub slip_timer: f32, // Seconds left during which velocity isn't re-aligned to the heading
+}
+
+impl CollisionState {
+    pub fn at(translation: Vec3) -> Self {
+        Self {
+            previous_translation: translation,
+            slip_timer: 0.0,
+        }
+    }
+}
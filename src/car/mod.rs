@@ -1,22 +1,68 @@
 // Car Module Definition
-// This module handles everything related to the player's car, including
-// physics, input, and component definitions.
+// This module handles everything related to the cars on track, including
+// physics, input, and component definitions. Every car, the player's or an
+// opponent's, is an entity carrying its own stats, hardware and progress.
+pub mod collision;
 pub mod components;
 pub mod systems;
 
-use crate::states::AppState;
+use crate::resources::{BaseCarStatus, CarProgress, CarState, CarStatus, PcStatus};
+use crate::states::{PauseState, Racing};
+use crate::track_limits::TrackLimitState;
 use bevy::prelude::*;
+use collision::*;
+use components::*;
 use systems::*;
 
-/// Plugin that handles the car's behavior during the game.
-/// It registers the input and physics systems to run only when the game is active.
+/// Plugin that handles the cars' behavior during the game.
+/// It registers the input and physics systems to run only when the game is active, the
+/// physics only once the race has started.
 pub struct CarPlugin;
 
 impl Plugin for CarPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (car_input_system, car_physics_system).run_if(in_state(AppState::TimeAttackGame)),
+            (
+                // The player can rev and pick a gear on the grid
+                car_input_system.run_if(in_state(PauseState::Running)),
+                (
+                    car_reset_system,
+                    car_physics_system,
+                    car_contact_system,
+                    car_collision_system,
+                )
+                    .chain()
+                    .run_if(in_state(Racing)),
+            )
+                .chain(),
         );
     }
 }
+
+/// What sets one car apart from another: its design, its stats and the hardware behind them.
+#[derive(Debug, Clone)]
+pub struct CarSpec {
+    pub base: BaseCarStatus,
+    pub status: CarStatus,
+    pub hardware: PcStatus,
+    pub source: HardwareSource,
+}
+
+/// Components of a car built to `spec`, standing at `transform` a distance along the
+/// track with a full tank.
+pub fn car_bundle(spec: CarSpec, transform: Transform, distance: f32) -> impl Bundle {
+    (
+        transform,
+        Velocity::default(),
+        CollisionState::at(transform.translation),
+        CarControls::default(),
+        CarState::new(spec.status.fuel_capacity),
+        CarProgress::at(distance),
+        TrackLimitState::default(),
+        spec.base,
+        spec.status,
+        spec.hardware,
+        spec.source,
+    )
+}
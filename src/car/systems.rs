@@ -1,44 +1,179 @@
 use crate::car::components::*;
+use crate::course::Course;
+use crate::course::surface::WheelContact;
+use crate::events::{DrsToggled, GearChanged, PenaltyApplied};
 use crate::resources::*;
-use crate::states::AppState;
+use crate::weather::TrackConditions;
 use bevy::prelude::*;
 
-/// System that handles user input for gear shifting and DRS.
+/// Samples the course surface and how wet it is under each wheel of the car.
+fn wheel_contact(
+    transform: &Transform,
+    course: &Course,
+    conditions: &TrackConditions,
+) -> WheelContact {
+    let wheels = WHEEL_OFFSETS.map(|offset| course.project(transform.transform_point(offset)));
+    let surfaces = wheels.map(|wheel| course.surface_at(&wheel));
+    let mut contact = WheelContact::from_surfaces(&surfaces);
+
+    let count = wheels.len() as f32;
+    contact.grip *= wheels
+        .iter()
+        .map(|w| conditions.grip_at(w.distance, w.lateral))
+        .sum::<f32>()
+        / count;
+    contact.braking *= wheels
+        .iter()
+        .map(|w| conditions.braking_at(w.distance, w.lateral))
+        .sum::<f32>()
+        / count;
+    contact
+}
+
+/// Speed (km/h) below which the gearbox allows shifting into or out of reverse.
+const REVERSE_SHIFT_SPEED: f32 = 5.0;
+/// Seconds added to the play time when the car is reset to the track.
+const RESET_TIME_PENALTY: f32 = 5.0;
+/// Turn rate per point of handling at full lock (radians per second).
+const BEVY_STEERING_SENSITIVITY: f32 = 1.2;
+
+/// Fastest a car turns at full lock (radians per second). It comes from the car's
+/// handling, and so from its grip.
+pub fn turn_rate(car_status: &CarStatus) -> f32 {
+    car_status.handling * BEVY_STEERING_SENSITIVITY
+}
+
+/// Deceleration the car's brakes give it on asphalt (m/s^2).
+pub fn braking_deceleration(car_status: &CarStatus) -> f32 {
+    car_status.braking / car_status.weight
+}
+
+/// System that handles user input: throttle, brake and steering, gear shifting and DRS.
 /// Manual gear shifting is a core requirement for tuning performance.
-pub fn car_input_system(input: Res<ButtonInput<KeyCode>>, mut session: ResMut<GameSession>) {
+pub fn car_input_system(
+    input: Res<ButtonInput<KeyCode>>,
+    session: Res<GameSession>,
+    mut query: Query<(Entity, &mut CarControls, &mut CarState), With<PlayerCar>>,
+    mut gear_changes: MessageWriter<GearChanged>,
+    mut drs_toggles: MessageWriter<DrsToggled>,
+) {
     if session.is_game_over {
         return;
     }
+    let Ok((car, mut controls, mut state)) = query.single_mut() else {
+        return;
+    };
+    let (gear, drs) = (state.gear, state.drs_enabled);
+
+    controls.throttle = input.pressed(KeyCode::KeyW);
+    controls.brake = !controls.throttle && input.pressed(KeyCode::KeyS);
+    controls.steer = 0.0;
+    if input.pressed(KeyCode::KeyA) {
+        controls.steer = -1.0;
+    }
+    if input.pressed(KeyCode::KeyD) {
+        controls.steer = 1.0;
+    }
 
     // Gear Shifting (Manual as per Spec 108)
-    if input.just_pressed(KeyCode::ArrowRight) {
-        session.current_gear = (session.current_gear + 1).min(6);
+    // Reverse sits below 1st and can only be engaged or left while (nearly) stopped.
+    let nearly_stopped = state.speed < REVERSE_SHIFT_SPEED;
+    if input.just_pressed(KeyCode::ArrowRight) && (state.gear != REVERSE_GEAR || nearly_stopped) {
+        state.gear = (state.gear + 1).min(6);
     }
-    if input.just_pressed(KeyCode::ArrowLeft) {
-        session.current_gear = (session.current_gear - 1).max(1);
+    if input.just_pressed(KeyCode::ArrowLeft) && (state.gear > 1 || nearly_stopped) {
+        state.gear = (state.gear - 1).max(REVERSE_GEAR);
     }
 
     // DRS Logic (Arrow Up/Down)
     if input.just_pressed(KeyCode::ArrowUp) || input.just_pressed(KeyCode::KeyE) {
-        session.drs_enabled = true;
+        state.drs_enabled = true;
     }
     if input.just_pressed(KeyCode::ArrowDown) || input.just_pressed(KeyCode::KeyQ) {
-        session.drs_enabled = false;
+        state.drs_enabled = false;
+    }
+
+    if state.gear != gear {
+        gear_changes.write(GearChanged {
+            car,
+            gear: state.gear,
+        });
     }
+    if state.drs_enabled != drs {
+        drs_toggles.write(DrsToggled {
+            car,
+            enabled: state.drs_enabled,
+        });
+    }
+}
+
+/// Recovery action (R): puts the car back on the centreline at the last checkpoint passed,
+/// stopped and in 1st gear, in exchange for a time penalty.
+#[allow(clippy::type_complexity)]
+pub fn car_reset_system(
+    input: Res<ButtonInput<KeyCode>>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &mut CollisionState,
+            &mut CarState,
+            &mut CarProgress,
+        ),
+        With<PlayerCar>,
+    >,
+    mut penalties: MessageWriter<PenaltyApplied>,
+) {
+    if session.is_game_over || !input.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+
+    let Ok((car, mut transform, mut velocity, mut collision, mut state, mut progress)) =
+        query.single_mut()
+    else {
+        return;
+    };
+
+    let first_lap = progress.timing.laps.is_empty();
+    let checkpoint = course.restart_distance(first_lap, progress.timing.next_gate);
+    *transform = course.centreline_transform(checkpoint);
+    velocity.0 = Vec3::ZERO;
+    *collision = CollisionState::at(transform.translation);
+    progress.timing.last_position = Some(transform.translation);
+
+    progress.distance = checkpoint;
+    let penalty = progress.penalise(PenaltyKind::Reset, RESET_TIME_PENALTY);
+    penalties.write(PenaltyApplied { car, penalty });
+    state.speed = 0.0;
+    state.gear = 1;
 }
 
-/// The core physics engine for the car.
-/// This system calculates all car properties (acceleration, grip, etc.) based on
-/// actual hardware performance (CPU, GPU, RAM) and applies them to the 3D entity.
+/// The core physics engine for the cars.
+/// This system calculates all car properties (acceleration, grip, etc.) of every car based
+/// on the actual hardware performance (CPU, GPU, RAM) behind it, and applies them to the
+/// car's 3D entity following its driver's controls.
+#[allow(clippy::type_complexity)]
 pub fn car_physics_system(
     time: Res<Time>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut session: ResMut<GameSession>,
-    base_car: Res<BaseCarStatus>,
-    pc_status: Res<PcStatus>,
-    mut car_status: ResMut<CarStatus>,
-    mut next_state: ResMut<NextState<AppState>>,
-    mut query: Query<(&mut Transform, &mut Velocity), With<PlayerCar>>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    conditions: Res<TrackConditions>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Velocity,
+        &CollisionState,
+        &CarControls,
+        &mut CarState,
+        &mut CarProgress,
+        &mut CarStatus,
+        &BaseCarStatus,
+        &PcStatus,
+    )>,
+    mut penalties: MessageWriter<PenaltyApplied>,
 ) {
     if session.is_game_over {
         return;
@@ -61,146 +196,203 @@ pub fn car_physics_system(
     const BEVY_BRAKING_SCALE: f32 = 3000.0;
     const BEVY_ENGINE_FORCE_SCALE: f32 = 500.0;
     const BEVY_DRAG_SCALE: f32 = 0.4;
-    const BEVY_STEERING_SENSITIVITY: f32 = 1.2;
     const BEVY_GRAVITY_SCALE: f32 = 3.0;
 
     const FUEL_BURN_MULTIPLIER: f32 = 0.5;
     const GROUND_FRICTION: f32 = 2.0;
     const COURSE_OUT_PENALTY_RATE: f32 = 2.0; // Seconds of penalty per actual second off-road
+    const LATERAL_GRIP_RATE: f32 = 60.0; // How quickly tyres turn sideways motion into heading
+
+    // How much a fully destroyed part reduces the stats it affects
+    const ENGINE_DAMAGE_EFFECT: f32 = 0.5;
+    const STEERING_DAMAGE_EFFECT: f32 = 0.6;
+    const AERO_DAMAGE_EFFECT: f32 = 0.5;
+    const AERO_GRIP_DAMAGE_EFFECT: f32 = 0.25;
 
     // The 'const' value mentioned in specification.md for fine-tuning.
     let const_val = 1.0;
 
-    // --- Car Status Dynamic Calculations (Strict Spec Alignment) ---
-    // These calculations are performed every frame to reflect hardware state.
-
-    // Calculate Max Speed based on CPU Clock
-    let cpu_u = pc_status.cpu_usage / 100.0;
-    car_status.max_speed = base_car.base_max_speed
-        * (1.0
-            + (base_car.cpu_impact * CPU_IMPACT_FACTOR)
-                * pc_status.cpu_frequency as f32
-                * (1.0 + cpu_u))
-        * const_val;
-
-    // Calculate Dynamic Weight based on remaining fuel
-    let fuel_ratio = (session.current_fuel / car_status.fuel_capacity).clamp(0.0, 1.0);
-    car_status.weight = base_car.base_weight * (1.0 + fuel_ratio) * const_val;
-
-    // Calculate Fuel Consumption based on Temperature and GPU usage
-    car_status.fuel_consumption = base_car.base_fuel_consumption
-        * (1.0
-            + (base_car.temp_impact * TEMP_IMPACT_FACTOR)
-                * (pc_status.cpu_temp + pc_status.gpu_temp)
-                * (base_car.gpu_impact * GPU_IMPACT_FACTOR)
-                * pc_status.gpu_clock)
-        * const_val;
-
-    // Calculate Grip based on available RAM
-    let ram_avail = (pc_status.total_memory - pc_status.used_memory) as f32;
-    car_status.grip = base_car.base_grip
-        * (1.0 + (base_car.ram_impact * RAM_IMPACT_FACTOR) * ram_avail)
-        * const_val;
-
-    // Calculate Handling (Steering Agility)
-    // Formula: handling = base handling * grip / weight
-    car_status.handling = base_car.base_handling * car_status.grip / car_status.weight
-        * const_val
-        * BEVY_HANDLING_SCALE;
-
-    // Calculate Aerodynamics based on GPU performance
-    let gpu_u = pc_status.gpu_usage / 100.0;
-    car_status.aerodynamics = base_car.base_aerodynamics
-        * (1.0 + (base_car.gpu_impact * GPU_IMPACT_FACTOR) * pc_status.gpu_clock * (1.0 + gpu_u))
-        * const_val;
-
-    // Gear appropriate logic: acceleration is 2.0x if gear is near ideal for speed ratio
-    let gear_limit_ratio = session.current_gear as f32 / 6.0;
-    let gear_max_speed = car_status.max_speed * gear_limit_ratio;
-
-    let speed_ratio = if car_status.max_speed > 0.0 {
-        session.current_speed / car_status.max_speed
-    } else {
-        0.0
-    };
-    let ideal_gear = (speed_ratio * 6.0).ceil().clamp(1.0, 6.0) as i32;
-    let gear_factor = if (session.current_gear - ideal_gear).abs() <= 1 {
-        2.0
-    } else {
-        1.0
-    };
+    for (
+        car,
+        mut transform,
+        mut velocity,
+        collision,
+        controls,
+        mut state,
+        mut progress,
+        mut car_status,
+        base_car,
+        pc_status,
+    ) in &mut query
+    {
+        // Surface under the wheels (asphalt, kerb, grass, gravel)
+        let contact = wheel_contact(&transform, &course, &conditions);
+
+        // --- Car Status Dynamic Calculations (Strict Spec Alignment) ---
+        // These calculations are performed every frame to reflect hardware state.
+
+        // Collision Damage: worn parts reduce the stats they are responsible for.
+        // Each multiplier is applied where its stat is computed, before anything derives from it.
+        let damage = state.damage;
+        let engine_health = 1.0 - damage.engine * ENGINE_DAMAGE_EFFECT;
+        let steering_health = 1.0 - damage.steering * STEERING_DAMAGE_EFFECT;
+        let aero_health = 1.0 - damage.aero * AERO_DAMAGE_EFFECT;
+
+        // Calculate Max Speed based on CPU Clock
+        let cpu_u = pc_status.cpu_usage / 100.0;
+        car_status.max_speed = base_car.base_max_speed
+            * (1.0
+                + (base_car.cpu_impact * CPU_IMPACT_FACTOR)
+                    * pc_status.cpu_frequency as f32
+                    * (1.0 + cpu_u))
+            * const_val
+            * engine_health;
+
+        // Calculate Dynamic Weight based on remaining fuel
+        let fuel_ratio = (state.fuel / car_status.fuel_capacity).clamp(0.0, 1.0);
+        car_status.weight = base_car.base_weight * (1.0 + fuel_ratio) * const_val;
+
+        // Calculate Fuel Consumption based on Temperature and GPU usage
+        car_status.fuel_consumption = base_car.base_fuel_consumption
+            * (1.0
+                + (base_car.temp_impact * TEMP_IMPACT_FACTOR)
+                    * (pc_status.cpu_temp + pc_status.gpu_temp)
+                    * (base_car.gpu_impact * GPU_IMPACT_FACTOR)
+                    * pc_status.gpu_clock)
+            * const_val;
+
+        // Calculate Grip based on available RAM
+        let ram_avail = (pc_status.total_memory - pc_status.used_memory) as f32;
+        car_status.grip = base_car.base_grip
+            * (1.0 + (base_car.ram_impact * RAM_IMPACT_FACTOR) * ram_avail)
+            * const_val
+            * (1.0 - damage.aero * AERO_GRIP_DAMAGE_EFFECT)
+            * contact.grip;
+
+        // Calculate Handling (Steering Agility)
+        // Formula: handling = base handling * grip / weight
+        car_status.handling = base_car.base_handling * car_status.grip / car_status.weight
+            * const_val
+            * BEVY_HANDLING_SCALE
+            * steering_health;
+
+        // Calculate Aerodynamics based on GPU performance
+        let gpu_u = pc_status.gpu_usage / 100.0;
+        car_status.aerodynamics = base_car.base_aerodynamics
+            * (1.0
+                + (base_car.gpu_impact * GPU_IMPACT_FACTOR) * pc_status.gpu_clock * (1.0 + gpu_u))
+            * const_val;
+
+        // Gear appropriate logic: acceleration is 2.0x if gear is near ideal for speed ratio
+        // Reverse is geared like 1st.
+        let effective_gear = state.gear.max(1);
+        let gear_limit_ratio = effective_gear as f32 / 6.0;
+        let gear_max_speed = car_status.max_speed * gear_limit_ratio;
+
+        let speed_ratio = if car_status.max_speed > 0.0 {
+            state.speed / car_status.max_speed
+        } else {
+            0.0
+        };
+        let ideal_gear = (speed_ratio * 6.0).ceil().clamp(1.0, 6.0) as i32;
+        let gear_factor = if (effective_gear - ideal_gear).abs() <= 1 {
+            2.0
+        } else {
+            1.0
+        };
 
-    // Calculate Acceleration
-    car_status.acceleration = base_car.base_acceleration
-        * ((1.0 + (base_car.gpu_impact * GPU_IMPACT_FACTOR) * pc_status.gpu_clock * (1.0 + gpu_u))
-            * gear_factor
-            / car_status.weight)
-        * const_val
-        * BEVY_ACCEL_SCALE;
-
-    // DRS Boosts
-    car_status.drs_acceleration = car_status.acceleration + car_status.aerodynamics * const_val;
-    car_status.drs_max_speed = car_status.max_speed + car_status.aerodynamics * const_val;
-
-    // Apply Gear Limit to Max Speed
-    let final_max_speed = if session.drs_enabled {
-        (car_status.drs_max_speed * gear_limit_ratio).min(car_status.drs_max_speed)
-    } else {
-        gear_max_speed.min(car_status.max_speed)
-    };
+        // Calculate Acceleration
+        car_status.acceleration = base_car.base_acceleration
+            * ((1.0
+                + (base_car.gpu_impact * GPU_IMPACT_FACTOR) * pc_status.gpu_clock * (1.0 + gpu_u))
+                * gear_factor
+                / car_status.weight)
+            * const_val
+            * BEVY_ACCEL_SCALE
+            * engine_health;
+
+        // DRS Boosts (a damaged wing gives less boost; drag is left untouched)
+        // Thinner air gives the open wing less to work with.
+        let air = course.air_density_ratio();
+        car_status.drs_acceleration =
+            car_status.acceleration + car_status.aerodynamics * const_val * aero_health * air;
+        car_status.drs_max_speed =
+            car_status.max_speed + car_status.aerodynamics * const_val * aero_health * air;
+
+        // Apply Gear Limit to Max Speed
+        let final_max_speed = if state.drs_enabled {
+            (car_status.drs_max_speed * gear_limit_ratio).min(car_status.drs_max_speed)
+        } else {
+            gear_max_speed.min(car_status.max_speed)
+        };
 
-    // Calculate Braking performance
-    car_status.braking = base_car.base_braking * car_status.grip / car_status.weight
-        * const_val
-        * BEVY_BRAKING_SCALE;
+        // Calculate Braking performance
+        car_status.braking = base_car.base_braking * car_status.grip / car_status.weight
+            * const_val
+            * BEVY_BRAKING_SCALE
+            * contact.braking;
 
-    if let Some((mut transform, mut velocity)) = query.iter_mut().next() {
         let mut force = Vec3::ZERO;
 
         // A. Gravity Calculation
-        let gravity_base = Vec3::new(0.0, -9.81, 0.0);
+        let gravity_base = Vec3::new(0.0, -course.gravity, 0.0);
         force += gravity_base * BEVY_GRAVITY_SCALE * (car_status.weight / 100.0);
 
         // B. Engine / Brake Force Determination
+        // Forces act along the car's current forward-facing direction
+        let forward_dir = transform.forward();
+        let forward_flat = Vec3::new(forward_dir.x, 0.0, forward_dir.z).normalize_or_zero();
+
         let mut engine_force_mag = 0.0;
         let current_speed_ms = velocity.0.length();
         let current_speed_kmh = current_speed_ms * 3.6;
-        session.current_speed = current_speed_kmh;
+        state.speed = current_speed_kmh;
 
-        if input.pressed(KeyCode::KeyW) {
+        // +1 when rolling forwards, -1 when rolling backwards
+        let moving_sign = if velocity.0.dot(forward_flat) >= 0.0 {
+            1.0
+        } else {
+            -1.0
+        };
+        // In reverse the engine pushes the car backwards
+        let drive_sign = if state.gear == REVERSE_GEAR {
+            -1.0
+        } else {
+            1.0
+        };
+
+        // The engine only runs while there's fuel in the tank
+        if controls.throttle && state.fuel > 0.0 {
             // Apply acceleration based on whether DRS is open
-            let accel = if session.drs_enabled {
+            let accel = if state.drs_enabled {
                 car_status.drs_acceleration
             } else {
                 car_status.acceleration
             };
-            engine_force_mag += accel * BEVY_ENGINE_FORCE_SCALE;
+            engine_force_mag += accel * BEVY_ENGINE_FORCE_SCALE * drive_sign;
 
             // Consume fuel while accelerating
             let burn_rate = car_status.fuel_consumption * FUEL_BURN_MULTIPLIER * dt;
-            session.current_fuel -= burn_rate;
-        } else if input.pressed(KeyCode::KeyS) {
-            // Apply braking force only if the car is currently moving
+            state.fuel -= burn_rate;
+        } else if controls.brake {
+            // Apply braking force against the direction of travel only if the car is moving
             if current_speed_ms > 0.1 {
-                engine_force_mag -= car_status.braking;
+                engine_force_mag -= car_status.braking * moving_sign;
             } else {
                 velocity.0 = Vec3::ZERO; // Come to a complete stop
             }
         }
 
-        // Apply forces in the car's current forward-facing direction
-        let forward_dir = transform.forward();
-        let forward_flat = Vec3::new(forward_dir.x, 0.0, forward_dir.z).normalize_or_zero();
         force += forward_flat * engine_force_mag;
 
         // C. Air Resistance (Drag) and Ground Friction
-        let drag_coeff = if session.drs_enabled {
+        let drag_coeff = if state.drs_enabled {
             car_status.aerodynamics * BEVY_DRAG_SCALE
         } else {
             car_status.aerodynamics
         };
-        force -= velocity.0 * drag_coeff * BEVY_DRAG_SCALE;
-        force -= velocity.0 * GROUND_FRICTION;
+        force -= velocity.0 * drag_coeff * BEVY_DRAG_SCALE * air;
+        force -= velocity.0 * GROUND_FRICTION * contact.rolling_resistance;
 
         // --- Physics Integration ---
         // Basic F=ma and v=u+at implementation
@@ -210,7 +402,7 @@ pub fn car_physics_system(
 
         // --- Speed Capping Logic ---
         // Ensure the car never exceeds the physical maximums calculated earlier
-        let absolute_max_ms = if session.drs_enabled {
+        let absolute_max_ms = if state.drs_enabled {
             car_status.drs_max_speed
         } else {
             car_status.max_speed
@@ -224,59 +416,54 @@ pub fn car_physics_system(
 
         // Update Position based on Velocity
         transform.translation += velocity.0 * dt;
-        session.distance_traveled += velocity.0.length() * dt;
 
         // --- Steering Logic ---
         // Handles horizontal rotation (Yaw) using the Handling attribute
-        let handling = car_status.handling * BEVY_STEERING_SENSITIVITY;
-        let mut rotation = 0.0;
-        if input.pressed(KeyCode::KeyA) {
-            rotation = handling * dt;
-        }
-        if input.pressed(KeyCode::KeyD) {
-            rotation = -handling * dt;
-        }
-        transform.rotate_y(rotation);
+        transform.rotate_y(-controls.steer.clamp(-1.0, 1.0) * turn_rate(&car_status) * dt);
 
-        // Align velocity direction with the car's orientation to prevent drifting
-        if velocity.0.length() > 0.1 {
+        // Align velocity direction with the car's orientation to prevent drifting.
+        // How fast it aligns depends on the surface grip, so grass and gravel feel slippery.
+        // Right after an impact the car slides freely so the bounce isn't undone.
+        if velocity.0.length() > 0.1 && collision.slip_timer <= 0.0 {
             let fwd = *transform.forward();
-            velocity.0 = fwd * velocity.0.length();
+            let direction = if velocity.0.dot(fwd) >= 0.0 {
+                fwd
+            } else {
+                -fwd
+            };
+            let aligned = direction * velocity.0.length();
+            let align = (contact.grip * LATERAL_GRIP_RATE * dt).min(1.0);
+            velocity.0 = velocity.0.lerp(aligned, align);
         }
 
         // --- Environment Collision & Course-Out Rules ---
 
-        // Ground Height Mapping (Sine wave hills)
-        let z_pos_course = -transform.translation.z;
-        let ground_y = if z_pos_course >= 0.0 {
-            (z_pos_course / 1000.0).sin() * 5.0
-        } else {
-            0.0
-        };
+        // Progress is where the car is along the track, not how far it has driven
+        let projection = course.project(transform.translation);
+        progress.distance = projection.distance;
 
-        // Keep the car on the ground surface
+        // Keep the car on the road surface (hills and banking come from the course)
+        let ground_y = projection.height;
         if transform.translation.y < ground_y + 0.5 {
             transform.translation.y = ground_y + 0.5;
-            if velocity.0.y < 0.0 {
-                velocity.0.y = 0.0;
+            // Cancel any motion into the road surface
+            let into_surface = velocity.0.dot(projection.normal);
+            if into_surface < 0.0 {
+                velocity.0 -= projection.normal * into_surface;
             }
         }
 
         // Course-Out Determination (Spec 94)
-        let road_limit = 20.0;
-        let crash_limit = 35.0;
-
-        if transform.translation.x.abs() > road_limit {
+        // The car is off the course once no wheel touches asphalt or kerbs. The run-off
+        // surface already slows it down; on top of that the time keeps a penalty.
+        if contact.wheels_on_track == 0 && progress.ended.is_none() {
             // Apply time penalty while off-road
-            session.play_time += dt * COURSE_OUT_PENALTY_RATE;
-        }
-
-        if transform.translation.x.abs() > crash_limit {
-            // Irrecoverable crash if too far from the track center
-            println!("Game Over: Course Out Crash!");
-            session.is_game_over = true;
-            session.game_over_cause = GameOverCause::Crash;
-            next_state.set(AppState::Result);
+            let penalty = dt * COURSE_OUT_PENALTY_RATE;
+            let time = progress.time;
+            progress.penalties.course_out(time, dt, penalty);
+            progress.time += penalty;
+        } else if let Some(penalty) = progress.penalties.back_on_track() {
+            penalties.write(PenaltyApplied { car, penalty });
         }
     }
 }
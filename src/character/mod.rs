@@ -1 +0,0 @@
-pub mod character;
\ No newline at end of file
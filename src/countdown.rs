@@ -0,0 +1,363 @@
+use crate::car::components::{PlayerCar, Velocity};
+use crate::events::{PenaltyApplied, RaceStarted};
+use crate::game::GameWorld;
+use crate::resources::{CarProgress, PenaltyKind};
+use crate::settings::CycleSetting;
+use crate::states::{PauseState, RacePhase};
+use bevy::audio::Pitch;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Red lights, one coming on every second before GO: 3, 2, 1.
+const LIGHT_COUNT: usize = 3;
+/// Seconds from the first light to GO.
+const COUNTDOWN_TIME: f32 = LIGHT_COUNT as f32;
+/// Seconds the green lights stay up after GO.
+const GO_DISPLAY_TIME: f32 = 1.0;
+/// Seconds added to the run time for a jump start.
+pub const JUMP_START_PENALTY: f32 = 5.0;
+/// How quickly the revs build with the throttle held on the grid, and drop without (per second).
+const REV_RISE_RATE: f32 = 0.8;
+const REV_FALL_RATE: f32 = 0.6;
+/// Revs (share of the limiter) that launch the car cleanly. Fewer bog down, more spin the wheels.
+const LAUNCH_WINDOW: (f32, f32) = (0.65, 0.85);
+/// Speed a launch in the middle of the window gets the car off the line with (km/h).
+const LAUNCH_BOOST: f32 = 30.0;
+
+const LIGHT_OFF: Color = Color::srgb(0.15, 0.15, 0.15);
+const LIGHT_RED: Color = Color::srgb(0.9, 0.1, 0.1);
+const LIGHT_GREEN: Color = Color::srgb(0.1, 0.9, 0.2);
+
+/// Launch control minigame, switched on the settings screen: hold the brake and work the
+/// throttle to keep the revs in the window at GO for a quicker start.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LaunchControl {
+    #[default]
+    On,
+    Off,
+}
+
+impl CycleSetting for LaunchControl {
+    const NAME: &'static str = "Launch Control";
+
+    fn next(&self) -> Self {
+        match self {
+            LaunchControl::On => LaunchControl::Off,
+            LaunchControl::Off => LaunchControl::On,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            LaunchControl::On => "On",
+            LaunchControl::Off => "Off",
+        }
+        .to_string()
+    }
+}
+
+/// Progress of the start: how long the lights have been running and what the player did.
+#[derive(Resource, Debug, Default)]
+struct StartSequence {
+    elapsed: f32,     // Seconds since the first light
+    lights: usize,    // Red lights on so far
+    jump_start: bool, // The player went before GO
+    revs: f32,        // Engine revs on the grid (share of the limiter, 0 to 1)
+}
+
+/// Parent of the start lights display, removed shortly after GO.
+#[derive(Component)]
+struct StartLightsUi;
+
+#[derive(Component)]
+struct StartLight(usize);
+
+#[derive(Component)]
+struct StartText;
+
+/// Filled part of the launch control rev meter.
+#[derive(Component)]
+struct RevMeterFill;
+
+/// Plugin that holds the cars on the grid behind the start lights (3, 2, 1, GO!) and
+/// starts the race clock at GO.
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaunchControl>()
+            .add_systems(OnEnter(RacePhase::Countdown), setup_start_lights)
+            .add_systems(
+                Update,
+                (
+                    countdown_system.run_if(in_state(RacePhase::Countdown)),
+                    start_lights_system,
+                )
+                    .chain()
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+fn setup_start_lights(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    launch_control: Res<LaunchControl>,
+) {
+    commands.insert_resource(StartSequence::default());
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(20.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            StartLightsUi,
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for index in 0..LIGHT_COUNT {
+                        parent.spawn((
+                            Node {
+                                width: Val::Px(60.0),
+                                height: Val::Px(60.0),
+                                border_radius: BorderRadius::MAX,
+                                ..default()
+                            },
+                            BackgroundColor(LIGHT_OFF),
+                            StartLight(index),
+                        ));
+                    }
+                });
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                    font_size: 72.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                StartText,
+            ));
+            if *launch_control == LaunchControl::On {
+                spawn_rev_meter(parent, &asset_server);
+            }
+        });
+}
+
+/// Rev meter with the launch window marked on it.
+fn spawn_rev_meter(parent: &mut ChildSpawnerCommands, asset_server: &AssetServer) {
+    parent.spawn((
+        Text::new("Hold S, work W: revs in the green at GO"),
+        TextFont {
+            font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    ));
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(300.0),
+                height: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.12, 0.8)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(LAUNCH_WINDOW.0 * 100.0),
+                    width: Val::Percent((LAUNCH_WINDOW.1 - LAUNCH_WINDOW.0) * 100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(LIGHT_GREEN.with_alpha(0.4)),
+            ));
+            parent.spawn((
+                Node {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::WHITE.with_alpha(0.8)),
+                RevMeterFill,
+            ));
+        });
+}
+
+/// Share of `LAUNCH_BOOST` a launch at `revs` gets: all of it in the middle of the window,
+/// less towards its edges, none outside it.
+fn launch_quality(revs: f32) -> f32 {
+    let (low, high) = LAUNCH_WINDOW;
+    if !(low..=high).contains(&revs) {
+        return 0.0;
+    }
+    let middle = (low + high) / 2.0;
+    1.0 - (revs - middle).abs() / (high - middle) * 0.5
+}
+
+/// Runs the lights and watches the player on the grid. Going on the throttle without
+/// holding the brake before GO is a jump start. At GO the race clock starts, with the
+/// part of this frame after GO already counted, and the launch boost is given.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn countdown_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    launch_control: Res<LaunchControl>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    mut sequence: ResMut<StartSequence>,
+    mut cars: Query<(
+        Entity,
+        &mut CarProgress,
+        Option<(&Transform, &mut Velocity)>,
+        Has<PlayerCar>,
+    )>,
+    mut next_phase: ResMut<NextState<RacePhase>>,
+    (mut started, mut penalties): (MessageWriter<RaceStarted>, MessageWriter<PenaltyApplied>),
+) {
+    let dt = time.delta_secs();
+    sequence.elapsed += dt;
+
+    // A light comes on every second, each with a beep
+    let lights = (sequence.elapsed.floor() as usize + 1).min(LIGHT_COUNT);
+    if lights > sequence.lights && sequence.elapsed < COUNTDOWN_TIME {
+        sequence.lights = lights;
+        beep(&mut commands, &mut pitches, 440.0, 0.2);
+    }
+
+    let throttle = input.pressed(KeyCode::KeyW);
+    let holding = input.pressed(KeyCode::KeyS);
+    if *launch_control == LaunchControl::On && holding {
+        let rate = if throttle {
+            REV_RISE_RATE
+        } else {
+            -REV_FALL_RATE
+        };
+        sequence.revs = (sequence.revs + rate * dt).clamp(0.0, 1.0);
+    } else {
+        sequence.revs = 0.0;
+    }
+
+    if throttle && !holding && !sequence.jump_start {
+        sequence.jump_start = true;
+        for (car, mut progress, _, player) in &mut cars {
+            if player {
+                let penalty = progress.penalise(PenaltyKind::JumpStart, JUMP_START_PENALTY);
+                penalties.write(PenaltyApplied { car, penalty });
+            }
+        }
+    }
+
+    if sequence.elapsed < COUNTDOWN_TIME {
+        return;
+    }
+    beep(&mut commands, &mut pitches, 880.0, 0.5);
+    let after_go = sequence.elapsed - COUNTDOWN_TIME;
+    let launch = if *launch_control == LaunchControl::On && !sequence.jump_start {
+        launch_quality(sequence.revs)
+    } else {
+        0.0
+    };
+    for (_, mut progress, car, player) in &mut cars {
+        progress.time += after_go;
+        if player
+            && launch > 0.0
+            && let Some((transform, mut velocity)) = car
+        {
+            velocity.0 = transform.forward() * LAUNCH_BOOST * launch / 3.6;
+            info!("Launch at {:.0}% revs", sequence.revs * 100.0);
+        }
+    }
+    next_phase.set(RacePhase::Racing);
+    started.write(RaceStarted);
+}
+
+/// Plays a tone of a frequency (Hz) for a number of seconds.
+fn beep(commands: &mut Commands, pitches: &mut Assets<Pitch>, frequency: f32, seconds: f32) {
+    commands.spawn((
+        AudioPlayer(pitches.add(Pitch::new(frequency, Duration::from_secs_f32(seconds)))),
+        PlaybackSettings::DESPAWN,
+    ));
+}
+
+/// Shows the lights and the count: red lights with 3, 2, 1, then green with GO! (or the
+/// jump start), cleared a moment after the start.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn start_lights_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    phase: Res<State<RacePhase>>,
+    sequence: Option<ResMut<StartSequence>>,
+    ui: Query<Entity, With<StartLightsUi>>,
+    mut lights: Query<(&StartLight, &mut BackgroundColor), Without<RevMeterFill>>,
+    mut text: Query<&mut Text, With<StartText>>,
+    mut rev_meter: Query<&mut Node, With<RevMeterFill>>,
+) {
+    let Some(mut sequence) = sequence else {
+        return;
+    };
+    let racing = *phase.get() == RacePhase::Racing;
+    if racing {
+        sequence.elapsed += time.delta_secs();
+        if sequence.elapsed >= COUNTDOWN_TIME + GO_DISPLAY_TIME {
+            for entity in &ui {
+                commands.entity(entity).despawn();
+            }
+            commands.remove_resource::<StartSequence>();
+            return;
+        }
+    }
+
+    for (light, mut color) in &mut lights {
+        color.0 = if racing {
+            LIGHT_GREEN
+        } else if light.0 < sequence.lights {
+            LIGHT_RED
+        } else {
+            LIGHT_OFF
+        };
+    }
+    for mut text in &mut text {
+        text.0 = if racing && sequence.jump_start {
+            format!("JUMP START +{:.0}s", JUMP_START_PENALTY)
+        } else if racing {
+            "GO!".to_string()
+        } else {
+            (LIGHT_COUNT + 1 - sequence.lights).to_string()
+        };
+    }
+    for mut node in &mut rev_meter {
+        node.width = Val::Percent(sequence.revs * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launches_in_the_window_get_a_boost() {
+        let middle = (LAUNCH_WINDOW.0 + LAUNCH_WINDOW.1) / 2.0;
+        assert_eq!(launch_quality(middle), 1.0);
+        assert!(launch_quality(LAUNCH_WINDOW.0) >= 0.5);
+        assert!(launch_quality(LAUNCH_WINDOW.1 - 0.01) < launch_quality(middle - 0.01));
+        assert_eq!(launch_quality(0.3), 0.0);
+        assert_eq!(launch_quality(1.0), 0.0);
+    }
+}
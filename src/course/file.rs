@@ -0,0 +1,485 @@
+use super::spline::{CentreLine, ControlPoint};
+use super::surface::CourseSurfaces;
+use super::{
+    Course, STANDARD_AIR_DENSITY, STANDARD_AMBIENT_TEMPERATURE, SceneryPlacement, StartGrid,
+};
+use crate::weather::Weather;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version of the course file format written by this build. Files with any other
+/// version are rejected instead of being guessed at.
+pub const COURSE_FORMAT_VERSION: u32 = 1;
+
+/// Steepest banking a course may use (radians).
+const MAX_BANKING: f32 = 0.6;
+/// Shortest allowed distance between two consecutive control points, measured across
+/// the ground so the road never runs straight up or down (m).
+const MIN_CONTROL_POINT_SPACING: f32 = 1.0;
+/// Coldest and hottest ambient temperature a course may declare (Celsius).
+const AMBIENT_TEMPERATURE_RANGE: (f32, f32) = (-60.0, 60.0);
+
+/// On-disk description of a course (`*.course.ron`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseFile {
+    pub version: u32,
+    pub name: String,
+    pub weather: Weather,
+    pub gravity: f32,
+    #[serde(default = "default_air_density")]
+    pub air_density: f32,
+    #[serde(default = "default_ambient_temperature")]
+    pub ambient_temperature: f32,
+    pub runoff_width: f32,
+    pub centreline: Vec<ControlPointDef>,
+    pub surfaces: CourseSurfaces,
+    pub checkpoints: Vec<f32>,
+    pub start_grid: StartGrid,
+    pub finish: f32,
+    #[serde(default)]
+    pub laps: Option<u32>, // Set on circuits, whose centreline loops back to its start
+    #[serde(default)]
+    pub scenery: Vec<SceneryPlacement>,
+}
+
+/// A centreline control point as written in a course file.
+/// `x` and `z` place it on the map, `elevation` is its height.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ControlPointDef {
+    pub x: f32,
+    pub z: f32,
+    pub elevation: f32,
+    pub width: f32,
+    #[serde(default)]
+    pub banking: f32,
+}
+
+/// File name stem for a course name, shared by course, ghost and replay files: lower
+/// case, with anything but letters and digits replaced ("My Course" -> "my_course").
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn default_air_density() -> f32 {
+    STANDARD_AIR_DENSITY
+}
+
+fn default_ambient_temperature() -> f32 {
+    STANDARD_AMBIENT_TEMPERATURE
+}
+
+/// Only the version field, read first so older or newer files give a clear error.
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+/// Reasons a course file can't be turned into a course.
+#[derive(Debug, Error)]
+pub enum CourseFileError {
+    #[error("could not read course file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse course file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("unsupported course format version {0} (expected {COURSE_FORMAT_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("the centreline needs at least 2 control points (3 on a circuit), found {0}")]
+    TooFewControlPoints(usize),
+    #[error("control point {index} is invalid: {reason}")]
+    InvalidControlPoint { index: usize, reason: &'static str },
+    #[error("surface zone {index} is invalid: {reason}")]
+    InvalidZone { index: usize, reason: &'static str },
+    #[error("checkpoints are invalid: {0}")]
+    InvalidCheckpoints(&'static str),
+    #[error("start grid is invalid: {0}")]
+    InvalidStartGrid(&'static str),
+    #[error("finish at {finish} m is outside the {length:.0} m centreline")]
+    InvalidFinish { finish: f32, length: f32 },
+    #[error("scenery placement {index} is invalid: {reason}")]
+    InvalidScenery { index: usize, reason: &'static str },
+    #[error("{0} must be a positive number")]
+    NotPositive(&'static str),
+    #[error("{0} must not be negative")]
+    Negative(&'static str),
+    #[error("ambient temperature {0} C is outside the supported range")]
+    InvalidAmbientTemperature(f32),
+    #[error("generator parameters are invalid: {0}")]
+    InvalidGeneratorParams(&'static str),
+    #[error("no course layout found for seed {0}")]
+    NoLayoutFound(u64),
+}
+
+impl CourseFile {
+    /// Parses and validates a course file.
+    pub fn parse(bytes: &[u8]) -> Result<Course, CourseFileError> {
+        Self::read(bytes)?.into_course()
+    }
+
+    /// Reads the description from a course file without building the course.
+    pub fn read(bytes: &[u8]) -> Result<CourseFile, CourseFileError> {
+        let header: VersionHeader = ron::de::from_bytes(bytes)?;
+        if header.version != COURSE_FORMAT_VERSION {
+            return Err(CourseFileError::UnsupportedVersion(header.version));
+        }
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    /// Writes the description in the course file format. Comments of a hand-written
+    /// file aren't kept.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Validates the description and builds the course from it.
+    pub fn into_course(self) -> Result<Course, CourseFileError> {
+        if self.version != COURSE_FORMAT_VERSION {
+            return Err(CourseFileError::UnsupportedVersion(self.version));
+        }
+        if !(self.gravity.is_finite() && self.gravity > 0.0) {
+            return Err(CourseFileError::NotPositive("gravity"));
+        }
+        if !(self.air_density.is_finite() && self.air_density >= 0.0) {
+            return Err(CourseFileError::Negative("air_density"));
+        }
+        let (coldest, hottest) = AMBIENT_TEMPERATURE_RANGE;
+        if !(coldest..=hottest).contains(&self.ambient_temperature) {
+            return Err(CourseFileError::InvalidAmbientTemperature(
+                self.ambient_temperature,
+            ));
+        }
+        if !(self.runoff_width.is_finite() && self.runoff_width >= 0.0) {
+            return Err(CourseFileError::Negative("runoff_width"));
+        }
+
+        if self.laps == Some(0) {
+            return Err(CourseFileError::NotPositive("laps"));
+        }
+        let circuit = self.laps.is_some();
+        let points = self.control_points()?;
+        let centreline = self.centreline_through(&points);
+        let length = centreline.length();
+
+        for (index, zone) in self.surfaces.zones.iter().enumerate() {
+            let bounds = [zone.start, zone.end, zone.left, zone.right];
+            let reason = if !bounds.iter().all(|bound| bound.is_finite()) {
+                Some("bounds must be finite")
+            } else if zone.start >= zone.end {
+                Some("start must be before end")
+            } else if zone.left >= zone.right {
+                Some("left must be less than right")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(CourseFileError::InvalidZone { index, reason });
+            }
+        }
+
+        // On a circuit the finish line sits just behind the grid and the lap runs from
+        // it around the loop; on a point-to-point course it's ahead of the grid
+        let finish_valid = if circuit {
+            self.finish >= 0.0 && self.finish < length
+        } else {
+            self.finish > 0.0 && self.finish <= length
+        };
+        if !finish_valid {
+            return Err(CourseFileError::InvalidFinish {
+                finish: self.finish,
+                length,
+            });
+        }
+        let last_gate = if circuit { length } else { self.finish };
+
+        if self.checkpoints.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(CourseFileError::InvalidCheckpoints(
+                "distances must be strictly increasing",
+            ));
+        }
+        if self
+            .checkpoints
+            .iter()
+            .any(|checkpoint| !(*checkpoint > self.start_grid.distance && *checkpoint < last_gate))
+        {
+            return Err(CourseFileError::InvalidCheckpoints(
+                "every checkpoint must lie between the start grid and the end of the lap",
+            ));
+        }
+
+        if self.start_grid.slots.is_empty() {
+            return Err(CourseFileError::InvalidStartGrid("needs at least one slot"));
+        }
+        if !self.start_grid.distance.is_finite()
+            || self
+                .start_grid
+                .slots
+                .iter()
+                .any(|slot| !(slot.lateral.is_finite() && slot.back.is_finite()))
+        {
+            return Err(CourseFileError::InvalidStartGrid(
+                "positions must be finite",
+            ));
+        }
+        let rear = self
+            .start_grid
+            .slots
+            .iter()
+            .map(|slot| slot.back)
+            .fold(self.start_grid.distance, |rear, back| {
+                rear.min(self.start_grid.distance - back)
+            });
+        let grid_valid = if circuit {
+            rear > self.finish && self.start_grid.distance < length
+        } else {
+            self.start_grid.distance >= 0.0 && self.start_grid.distance < self.finish
+        };
+        if !grid_valid {
+            return Err(CourseFileError::InvalidStartGrid(if circuit {
+                "every slot must lie between the finish line and the end of the loop"
+            } else {
+                "the grid line must lie between the start of the centreline and the finish"
+            }));
+        }
+        let narrowest = points.iter().map(|p| p.width).fold(f32::MAX, f32::min);
+        if self
+            .start_grid
+            .slots
+            .iter()
+            .any(|slot| slot.back < 0.0 || slot.lateral.abs() > narrowest / 2.0)
+        {
+            return Err(CourseFileError::InvalidStartGrid(
+                "slots must be behind the grid line and on the road",
+            ));
+        }
+
+        for (index, placement) in self.scenery.iter().enumerate() {
+            let reason = if !(placement.distance.is_finite() && placement.lateral.is_finite()) {
+                Some("position must be finite")
+            } else if !placement.scale.is_finite() || placement.scale <= 0.0 {
+                Some("scale must be positive")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(CourseFileError::InvalidScenery { index, reason });
+            }
+        }
+
+        Ok(Course {
+            name: self.name,
+            centreline,
+            runoff_width: self.runoff_width,
+            checkpoints: self.checkpoints,
+            start_grid: self.start_grid,
+            finish: self.finish,
+            laps: self.laps.unwrap_or(1),
+            gravity: self.gravity,
+            air_density: self.air_density,
+            ambient_temperature: self.ambient_temperature,
+            weather: self.weather,
+            surfaces: self.surfaces,
+            scenery: self.scenery,
+        })
+    }
+
+    /// Builds just the centreline, a closed loop on circuits.
+    pub fn centreline(&self) -> Result<CentreLine, CourseFileError> {
+        Ok(self.centreline_through(&self.control_points()?))
+    }
+
+    /// The centreline through control points already checked by `control_points`.
+    fn centreline_through(&self, points: &[ControlPoint]) -> CentreLine {
+        if self.laps.is_some() {
+            CentreLine::closed(points)
+        } else {
+            CentreLine::new(points)
+        }
+    }
+
+    fn control_points(&self) -> Result<Vec<ControlPoint>, CourseFileError> {
+        let circuit = self.laps.is_some();
+        if self.centreline.len() < if circuit { 3 } else { 2 } {
+            return Err(CourseFileError::TooFewControlPoints(self.centreline.len()));
+        }
+
+        let mut points: Vec<ControlPoint> = Vec::with_capacity(self.centreline.len());
+        for (index, def) in self.centreline.iter().enumerate() {
+            let position = Vec3::new(def.x, def.elevation, def.z);
+            let reason = if !position.is_finite() {
+                Some("position must be finite")
+            } else if !def.width.is_finite() || def.width <= 0.0 {
+                Some("width must be positive")
+            } else if !def.banking.is_finite() || def.banking.abs() > MAX_BANKING {
+                Some("banking is too steep")
+            } else if points.last().is_some_and(|prev| {
+                prev.position.xz().distance(position.xz()) < MIN_CONTROL_POINT_SPACING
+            }) {
+                Some("too close to the previous control point")
+            } else if circuit
+                && index == self.centreline.len() - 1
+                && points[0].position.xz().distance(position.xz()) < MIN_CONTROL_POINT_SPACING
+            {
+                Some("too close to the first control point, a circuit closes by itself")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(CourseFileError::InvalidControlPoint { index, reason });
+            }
+            points.push(ControlPoint {
+                position,
+                width: def.width,
+                banking: def.banking,
+            });
+        }
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::generator::GeneratorParams;
+
+    const COURSE_1: &[u8] = include_bytes!("../../assets/courses/course_1.course.ron");
+
+    #[test]
+    fn shipped_courses_are_valid() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/courses");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = std::fs::read(&path).unwrap();
+            let result = if path.to_string_lossy().ends_with(".gen.ron") {
+                ron::de::from_bytes::<GeneratorParams>(&bytes)
+                    .map_err(CourseFileError::from)
+                    .and_then(|params| params.generate())
+            } else {
+                CourseFile::parse(&bytes)
+            };
+            if let Err(error) = result {
+                panic!("{}: {}", path.display(), error);
+            }
+        }
+    }
+
+    #[test]
+    fn course_1_matches_the_original_layout() {
+        let course = CourseFile::parse(COURSE_1).unwrap();
+        assert!((course.length() - 10000.0).abs() < 1.0);
+        assert_eq!(course.finish, 5000.0);
+        assert_eq!(course.centreline.max_width(), 40.0);
+    }
+
+    #[test]
+    fn written_files_read_back_the_same() {
+        let file = CourseFile::read(COURSE_1).unwrap();
+        let written = file.to_ron().unwrap();
+        let course = CourseFile::parse(written.as_bytes()).unwrap();
+        let original = CourseFile::parse(COURSE_1).unwrap();
+        assert_eq!(course.length(), original.length());
+        assert_eq!(course.checkpoints, original.checkpoints);
+        assert_eq!(course.surfaces.zones.len(), original.surfaces.zones.len());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let text =
+            String::from_utf8(COURSE_1.to_vec())
+                .unwrap()
+                .replacen("version: 1", "version: 99", 1);
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn rejects_control_points_stacked_on_top_of_each_other() {
+        let text = String::from_utf8(COURSE_1.to_vec()).unwrap().replacen(
+            "(x: 0.0, z: -250.0, elevation: 1.237,",
+            "(x: 0.0, z: 0.0, elevation: 5.0,",
+            1,
+        );
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::InvalidControlPoint { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_numbers() {
+        let course_1 = String::from_utf8(COURSE_1.to_vec()).unwrap();
+        let text = course_1.replacen(
+            "(kind: Kerb, left: -20.0, right: -18.0)",
+            "(kind: Kerb, left: NaN, right: -18.0)",
+            1,
+        );
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::InvalidZone { index: 0, .. })
+        ));
+
+        let text = course_1.replacen("(lateral: 6.0, back: 8.0)", "(lateral: 6.0, back: NaN)", 1);
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::InvalidStartGrid(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_finish_past_the_end() {
+        let text = String::from_utf8(COURSE_1.to_vec()).unwrap().replacen(
+            "finish: 5000.0",
+            "finish: 20000.0",
+            1,
+        );
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::InvalidFinish { .. })
+        ));
+    }
+
+    #[test]
+    fn environment_defaults_to_standard_air() {
+        let text = String::from_utf8(COURSE_1.to_vec())
+            .unwrap()
+            .replacen("air_density: 1.225,", "", 1)
+            .replacen("ambient_temperature: 20.0,", "", 1);
+        let course = CourseFile::parse(text.as_bytes()).unwrap();
+        assert_eq!(course.air_density, STANDARD_AIR_DENSITY);
+        assert_eq!(course.engine_heat(), 0.0);
+
+        let moon =
+            CourseFile::parse(include_bytes!("../../assets/courses/moon_1.course.ron")).unwrap();
+        assert!(moon.gravity < course.gravity / 5.0);
+        assert_eq!(moon.air_density_ratio(), 0.0);
+
+        let text = String::from_utf8(COURSE_1.to_vec()).unwrap().replacen(
+            "ambient_temperature: 20.0",
+            "ambient_temperature: 150.0",
+            1,
+        );
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::InvalidAmbientTemperature(_))
+        ));
+
+        let text = String::from_utf8(COURSE_1.to_vec()).unwrap().replacen(
+            "air_density: 1.225",
+            "air_density: -1.0",
+            1,
+        );
+        assert!(matches!(
+            CourseFile::parse(text.as_bytes()),
+            Err(CourseFileError::Negative("air_density"))
+        ));
+    }
+}
@@ -0,0 +1,408 @@
+use super::file::{COURSE_FORMAT_VERSION, ControlPointDef, CourseFile, CourseFileError};
+use super::surface::{CourseSurfaces, SurfaceKind, SurfaceZone};
+use super::{
+    Course, GridSlot, STANDARD_AIR_DENSITY, STANDARD_AMBIENT_TEMPERATURE, SceneryKind,
+    SceneryPlacement, StartGrid,
+};
+use crate::weather::Weather;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Distance between generated control points (m).
+const STEP: f32 = 100.0;
+/// Straight control points at the start so the grid sits on a straight.
+const STRAIGHT_START_POINTS: usize = 3;
+/// Sharpest turn between two control points at full curviness (radians).
+const MAX_TURN: f32 = 0.6;
+/// Steepest grade at full hilliness (rise over run).
+const MAX_GRADE: f32 = 0.08;
+/// Steepest banking the generator puts into a corner (radians).
+const MAX_GENERATED_BANKING: f32 = 0.12;
+/// Extra free space kept between separate parts of the track (m).
+const CLEARANCE_MARGIN: f32 = 20.0;
+/// Attempts at placing a single control point before giving up on a layout.
+const PLACEMENT_ATTEMPTS: usize = 24;
+/// Layouts tried (each from a seed derived from the original) before giving up.
+const LAYOUT_ATTEMPTS: u64 = 64;
+/// Length of road generated past the finish line (m).
+const RUN_OFF_AFTER_FINISH: f32 = 300.0;
+const RUNOFF_WIDTH: f32 = 8.0;
+const KERB_WIDTH: f32 = 1.5;
+
+/// Parameters of a generated course (`*.gen.ron`). The same parameters always produce
+/// the same course, so sharing them is enough to race the same track.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorParams {
+    pub seed: u64,
+    pub length: f32,    // Distance from the grid to the finish (m)
+    pub curviness: f32, // 0.0 (straight) - 1.0 (tight corners everywhere)
+    pub hilliness: f32, // 0.0 (flat) - 1.0 (steep hills)
+    pub min_width: f32, // Narrowest road (m)
+    pub max_width: f32, // Widest road (m)
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            length: 5000.0,
+            curviness: 0.5,
+            hilliness: 0.5,
+            min_width: 20.0,
+            max_width: 30.0,
+        }
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64). Kept in-tree so a seed gives the same
+/// course on every build and platform.
+pub(crate) struct SeedRng(pub u64);
+
+impl SeedRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0.0..1.0`.
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform value in `min..max`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.unit()
+    }
+}
+
+impl GeneratorParams {
+    fn validate(&self) -> Result<(), CourseFileError> {
+        let reason = if !(1000.0..=50000.0).contains(&self.length) {
+            Some("length must be between 1000 and 50000 m")
+        } else if !(0.0..=1.0).contains(&self.curviness) {
+            Some("curviness must be between 0 and 1")
+        } else if !(0.0..=1.0).contains(&self.hilliness) {
+            Some("hilliness must be between 0 and 1")
+        } else if !(self.min_width >= 12.0
+            && self.min_width <= self.max_width
+            && self.max_width <= 60.0)
+        {
+            Some("widths must satisfy 12 <= min_width <= max_width <= 60")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => Err(CourseFileError::InvalidGeneratorParams(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Generates the course. The result goes through the same validation as a course file.
+    pub fn generate(&self) -> Result<Course, CourseFileError> {
+        self.course_file()?.into_course()
+    }
+
+    /// Generates the course description, as it would be written to a course file.
+    pub fn course_file(&self) -> Result<CourseFile, CourseFileError> {
+        self.validate()?;
+        (0..LAYOUT_ATTEMPTS)
+            .find_map(|attempt| {
+                let mut rng = SeedRng(self.seed ^ attempt.wrapping_mul(0xA24B_AED4_963E_E407));
+                self.layout(&mut rng)
+                    .map(|points| self.describe(points, &mut rng))
+            })
+            .ok_or(CourseFileError::NoLayoutFound(self.seed))
+    }
+
+    /// Random walk of control points that never comes back near itself.
+    fn layout(&self, rng: &mut SeedRng) -> Option<Vec<ControlPointDef>> {
+        let count = ((self.length + RUN_OFF_AFTER_FINISH) / STEP).ceil() as usize + 1;
+        let clearance = self.max_width + RUNOFF_WIDTH * 2.0 + CLEARANCE_MARGIN;
+        let max_turn = MAX_TURN * self.curviness;
+        let max_grade = MAX_GRADE * self.hilliness;
+
+        let mut points: Vec<ControlPointDef> = Vec::with_capacity(count);
+        let mut position = Vec2::ZERO; // (x, z)
+        let mut heading = 0.0_f32; // 0 = -Z, positive turns right
+        let mut turn = 0.0_f32;
+        let mut grade = 0.0_f32;
+        let mut elevation = 0.0_f32;
+        let mut width = rng.range(self.min_width, self.max_width);
+
+        for index in 0..count {
+            if index > 0 {
+                let mut placed = None;
+                for _ in 0..PLACEMENT_ATTEMPTS {
+                    // Corners build up and unwind over several points instead of zig-zagging
+                    let candidate_turn = if index < STRAIGHT_START_POINTS {
+                        0.0
+                    } else {
+                        (turn * 0.5 + rng.range(-max_turn, max_turn) * 0.5)
+                            .clamp(-max_turn, max_turn)
+                    };
+                    let candidate_heading = heading + candidate_turn;
+                    let candidate = position
+                        + Vec2::new(candidate_heading.sin(), -candidate_heading.cos()) * STEP;
+                    if is_clear(&points, candidate, clearance) {
+                        placed = Some((candidate, candidate_heading, candidate_turn));
+                        break;
+                    }
+                }
+                let (next, next_heading, next_turn) = placed?;
+                position = next;
+                heading = next_heading;
+                turn = next_turn;
+
+                grade = (grade * 0.6 + rng.range(-max_grade, max_grade) * 0.4)
+                    .clamp(-max_grade, max_grade);
+                elevation += grade * STEP;
+                width = (width + rng.range(-2.0, 2.0)).clamp(self.min_width, self.max_width);
+            }
+
+            points.push(ControlPointDef {
+                x: position.x,
+                z: position.y,
+                elevation,
+                width,
+                banking: 0.0,
+            });
+        }
+
+        // Bank each corner into the turn: the outside edge (opposite the turn) is raised
+        for index in 1..count - 1 {
+            let before = Vec2::new(
+                points[index].x - points[index - 1].x,
+                points[index].z - points[index - 1].z,
+            );
+            let after = Vec2::new(
+                points[index + 1].x - points[index].x,
+                points[index + 1].z - points[index].z,
+            );
+            // Positive when turning right (towards +x when heading -z)
+            let turning = before.angle_to(after);
+            points[index].banking =
+                (-turning * 0.25).clamp(-MAX_GENERATED_BANKING, MAX_GENERATED_BANKING);
+        }
+        Some(points)
+    }
+
+    /// Builds the full course description around a laid-out centreline.
+    fn describe(&self, points: Vec<ControlPointDef>, rng: &mut SeedRng) -> CourseFile {
+        let width_at = |distance: f32| {
+            let index = ((distance / STEP) as usize).min(points.len() - 1);
+            points[index].width
+        };
+
+        // Kerbs follow the road edge in short pieces since the width changes along the way
+        let mut zones = Vec::new();
+        let mut start = 0.0;
+        while start < self.length + RUN_OFF_AFTER_FINISH {
+            let half_road = width_at(start) / 2.0;
+            for side in [-1.0, 1.0] {
+                let (left, right) = if side < 0.0 {
+                    (-half_road, -half_road + KERB_WIDTH)
+                } else {
+                    (half_road - KERB_WIDTH, half_road)
+                };
+                zones.push(SurfaceZone {
+                    kind: SurfaceKind::Kerb,
+                    start,
+                    end: start + STEP,
+                    left,
+                    right,
+                });
+            }
+            start += STEP;
+        }
+
+        // Gravel on the outside of the sharper corners (control points are roughly
+        // STEP apart along the centreline, which is close enough for run-off placement)
+        for (index, point) in points.iter().enumerate() {
+            let banking = point.banking;
+            if banking.abs() < MAX_GENERATED_BANKING * 0.5 {
+                continue;
+            }
+            let half_road = point.width / 2.0;
+            // The raised edge is the outside of the corner
+            let (left, right) = if banking > 0.0 {
+                (half_road, half_road + RUNOFF_WIDTH)
+            } else {
+                (-half_road - RUNOFF_WIDTH, -half_road)
+            };
+            let centre = index as f32 * STEP;
+            zones.push(SurfaceZone {
+                kind: SurfaceKind::Gravel,
+                start: centre - STEP / 2.0,
+                end: centre + STEP / 2.0,
+                left,
+                right,
+            });
+        }
+
+        let checkpoints = (1..)
+            .map(|i| i as f32 * 500.0)
+            .take_while(|distance| *distance < self.length)
+            .collect();
+
+        // Two staggered columns, a quarter of the narrowest road either side of the centre
+        let slots = (0..8)
+            .map(|i| GridSlot {
+                lateral: if i % 2 == 0 { -1.0 } else { 1.0 } * self.min_width / 4.0,
+                back: i as f32 * 8.0,
+            })
+            .collect();
+
+        let scenery = (1..)
+            .map(|i| i as f32 * 200.0)
+            .take_while(|distance| *distance < self.length)
+            .map(|distance| {
+                let side = if rng.unit() < 0.5 { -1.0 } else { 1.0 };
+                SceneryPlacement {
+                    kind: SceneryKind::Tree,
+                    distance,
+                    lateral: side
+                        * (width_at(distance) / 2.0 + RUNOFF_WIDTH + rng.range(6.0, 20.0)),
+                    scale: rng.range(0.7, 1.4),
+                }
+            })
+            .collect();
+
+        CourseFile {
+            version: COURSE_FORMAT_VERSION,
+            name: format!("Generated #{}", self.seed),
+            weather: Weather::Dry,
+            gravity: 9.81,
+            air_density: STANDARD_AIR_DENSITY,
+            ambient_temperature: STANDARD_AMBIENT_TEMPERATURE,
+            runoff_width: RUNOFF_WIDTH,
+            centreline: points,
+            surfaces: CourseSurfaces {
+                fallback: SurfaceKind::Grass,
+                zones,
+            },
+            checkpoints,
+            start_grid: StartGrid {
+                distance: 0.0,
+                slots,
+            },
+            finish: self.length,
+            laps: None,
+            scenery,
+        }
+    }
+}
+
+/// True if a new control point keeps its distance from every earlier part of the track
+/// (except the last few points it's connected to).
+fn is_clear(points: &[ControlPointDef], candidate: Vec2, clearance: f32) -> bool {
+    let Some(last) = points.last() else {
+        return true;
+    };
+    let midpoint = (Vec2::new(last.x, last.z) + candidate) / 2.0;
+    let far = points.len().saturating_sub(3);
+    points[..far].windows(2).all(|pair| {
+        let a = Vec2::new(pair[0].x, pair[0].z);
+        let b = Vec2::new(pair[1].x, pair[1].z);
+        segment_distance(a, b, candidate) >= clearance
+            && segment_distance(a, b, midpoint) >= clearance
+    })
+}
+
+fn segment_distance(a: Vec2, b: Vec2, point: Vec2) -> f32 {
+    let ab = b - a;
+    let t = ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+    (a + ab * t).distance(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(seed: u64) -> GeneratorParams {
+        // Vary the parameters with the seed so many shapes get covered
+        let mut rng = SeedRng(seed.wrapping_mul(31));
+        let min_width = rng.range(12.0, 30.0);
+        GeneratorParams {
+            seed,
+            length: rng.range(1000.0, 8000.0),
+            curviness: rng.unit(),
+            hilliness: rng.unit(),
+            min_width,
+            max_width: min_width + rng.range(0.0, 20.0),
+        }
+    }
+
+    /// Smallest horizontal distance between two parts of the centreline that are
+    /// more than `gap` metres apart along the track.
+    fn closest_approach(course: &Course, gap: f32) -> f32 {
+        let step = 20.0;
+        let samples: Vec<(f32, Vec2)> = (0..=(course.length() / step) as usize)
+            .map(|i| {
+                let distance = i as f32 * step;
+                let position = course.frame_at(distance).position;
+                (distance, Vec2::new(position.x, position.z))
+            })
+            .collect();
+        let mut closest = f32::MAX;
+        for (i, (da, a)) in samples.iter().enumerate() {
+            for (db, b) in &samples[i + 1..] {
+                if db - da > gap {
+                    closest = closest.min(a.distance(*b));
+                }
+            }
+        }
+        closest
+    }
+
+    #[test]
+    fn generated_courses_are_valid_across_seeds() {
+        for seed in 0..150 {
+            let params = params(seed);
+            let course = params
+                .generate()
+                .unwrap_or_else(|error| panic!("seed {seed}: {error}"));
+
+            assert!(course.finish == params.length, "seed {seed}");
+            assert!(course.length() >= params.length, "seed {seed}");
+            assert!(!course.checkpoints.is_empty() || params.length <= 500.0);
+
+            // Separate parts of the track never get close enough to touch
+            let road_and_runoff = params.max_width + RUNOFF_WIDTH * 2.0;
+            let gap = road_and_runoff * 4.0;
+            assert!(
+                closest_approach(&course, gap) > road_and_runoff,
+                "seed {seed} crosses itself"
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_same_course() {
+        let a = params(7).generate().unwrap();
+        let b = params(7).generate().unwrap();
+        let c = GeneratorParams {
+            seed: 8,
+            ..params(7)
+        }
+        .generate()
+        .unwrap();
+        let probe = a.finish / 2.0;
+        assert_eq!(a.frame_at(probe).position, b.frame_at(probe).position);
+        assert_ne!(a.frame_at(probe).position, c.frame_at(probe).position);
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        let params = GeneratorParams {
+            curviness: 2.0,
+            ..default()
+        };
+        assert!(matches!(
+            params.generate(),
+            Err(CourseFileError::InvalidGeneratorParams(_))
+        ));
+    }
+}
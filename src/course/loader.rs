@@ -0,0 +1,133 @@
+use super::Course;
+use super::file::{CourseFile, CourseFileError};
+use super::generator::GeneratorParams;
+use bevy::asset::{AssetLoader, LoadContext, LoadedFolder, io::Reader};
+use bevy::prelude::*;
+
+/// Folder (inside `assets/`) that every course file is loaded from.
+const COURSE_FOLDER: &str = "courses";
+
+/// Loads and validates `*.course.ron` files as `Course` assets.
+#[derive(Default, TypePath)]
+pub struct CourseLoader;
+
+impl AssetLoader for CourseLoader {
+    type Asset = Course;
+    type Settings = ();
+    type Error = CourseFileError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        CourseFile::parse(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["course.ron"]
+    }
+}
+
+/// Loads `*.gen.ron` generator parameters as the `Course` they generate.
+#[derive(Default, TypePath)]
+pub struct GeneratedCourseLoader;
+
+impl AssetLoader for GeneratedCourseLoader {
+    type Asset = Course;
+    type Settings = ();
+    type Error = CourseFileError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let params: GeneratorParams = ron::de::from_bytes(&bytes)?;
+        params.generate()
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gen.ron"]
+    }
+}
+
+/// Every course found in the course folder, sorted by name, followed by the courses
+/// generated during this session. Files that fail to load or validate are reported in
+/// the log and left out.
+#[derive(Resource, Default)]
+pub struct CourseLibrary {
+    folder: Handle<LoadedFolder>,
+    generated: Vec<Handle<Course>>,
+    pub courses: Vec<Handle<Course>>,
+}
+
+impl CourseLibrary {
+    /// Adds a course generated in-game and returns its index in the library.
+    pub fn add_generated(&mut self, handle: Handle<Course>) -> usize {
+        self.generated.push(handle.clone());
+        self.courses.push(handle);
+        self.courses.len() - 1
+    }
+}
+
+/// Course picked on the course select screen for the next run.
+#[derive(Resource, Default)]
+pub struct SelectedCourse(pub Option<Handle<Course>>);
+
+/// Plugin that registers the course asset and loads the course folder at startup.
+pub struct CoursePlugin;
+
+impl Plugin for CoursePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Course>()
+            .init_asset_loader::<CourseLoader>()
+            .init_asset_loader::<GeneratedCourseLoader>()
+            .init_resource::<CourseLibrary>()
+            .init_resource::<SelectedCourse>()
+            .add_systems(Startup, load_courses)
+            .add_systems(Update, collect_loaded_courses);
+    }
+}
+
+fn load_courses(asset_server: Res<AssetServer>, mut library: ResMut<CourseLibrary>) {
+    library.folder = asset_server.load_folder(COURSE_FOLDER);
+}
+
+/// Keeps the library in sync with the course folder as courses finish loading
+/// (or are edited while the game runs).
+fn collect_loaded_courses(
+    mut folder_events: MessageReader<AssetEvent<LoadedFolder>>,
+    mut course_events: MessageReader<AssetEvent<Course>>,
+    folders: Res<Assets<LoadedFolder>>,
+    courses: Res<Assets<Course>>,
+    mut library: ResMut<CourseLibrary>,
+) {
+    let folder_changed = folder_events.read().count() > 0;
+    let courses_changed = course_events.read().count() > 0;
+    if !folder_changed && !courses_changed {
+        return;
+    }
+    let Some(folder) = folders.get(&library.folder) else {
+        return;
+    };
+
+    let mut handles: Vec<Handle<Course>> = folder
+        .handles
+        .iter()
+        .filter_map(|handle| handle.clone().try_typed::<Course>().ok())
+        .filter(|handle| courses.contains(handle))
+        .collect();
+    handles.sort_by_key(|handle| courses.get(handle).map(|course| course.name.clone()));
+    if handles.len() + library.generated.len() != library.courses.len() {
+        info!("Loaded {} course(s)", handles.len());
+    }
+    handles.extend(library.generated.iter().cloned());
+    library.courses = handles;
+}
@@ -0,0 +1,362 @@
+use super::Course;
+use super::spline::TrackFrame;
+use super::surface::SurfaceKind;
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+
+/// Length of course covered by one chunk (m).
+pub const CHUNK_LENGTH: f32 = 200.0;
+/// Spacing of the cross-sections the course meshes are built from (m).
+const SECTION_SPACING: f32 = 5.0;
+/// Length of road covered by one repeat of a surface texture (m).
+const UV_TILE: f32 = 10.0;
+/// Length of each red or white kerb stripe (m).
+const KERB_STRIPE_LENGTH: f32 = 5.0;
+/// Length of road built behind the rearmost grid slot (m).
+const LEAD_IN: f32 = 10.0;
+
+/// Barrier cross-section: width, bottom and top relative to the road surface (m).
+const BARRIER_WIDTH: f32 = 2.0;
+const BARRIER_BOTTOM: f32 = -0.5;
+const BARRIER_TOP: f32 = 1.0;
+
+/// Which material a merged chunk mesh is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkMaterial {
+    Surface(SurfaceKind),
+    KerbStripe, // White stripes painted between the red kerb stripes
+    Barrier,
+}
+
+/// The merged meshes of one chunk, one per material.
+pub struct ChunkMeshes {
+    pub meshes: Vec<(ChunkMaterial, Mesh)>,
+}
+
+/// Distance along the course where the geometry starts, just behind the grid.
+/// Circuits are built once around the loop from its start.
+pub fn geometry_start(course: &Course) -> f32 {
+    if course.is_circuit() {
+        0.0
+    } else {
+        course.rear_of_grid() - LEAD_IN
+    }
+}
+
+/// Number of chunks needed to cover the course from where the geometry starts to its end.
+pub fn chunk_count(course: &Course) -> usize {
+    ((course.length() - geometry_start(course)) / CHUNK_LENGTH)
+        .ceil()
+        .max(1.0) as usize
+}
+
+/// Distance range along the course covered by a chunk.
+pub fn chunk_range(course: &Course, index: usize) -> (f32, f32) {
+    let start = geometry_start(course) + index as f32 * CHUNK_LENGTH;
+    (start, (start + CHUNK_LENGTH).min(course.length()))
+}
+
+/// Centre and radius of a sphere enclosing a chunk, used to decide when it's in view.
+pub fn chunk_bounds(course: &Course, index: usize) -> (Vec3, f32) {
+    let (start, end) = chunk_range(course, index);
+    let points: Vec<Vec3> = sections(start, end)
+        .map(|distance| course.frame_at(distance).position)
+        .collect();
+    let centre = points.iter().sum::<Vec3>() / points.len() as f32;
+    let radius = points
+        .iter()
+        .map(|point| point.distance(centre))
+        .fold(0.0, f32::max);
+    (centre, radius + course.half_width() + BARRIER_WIDTH)
+}
+
+/// Builds the ground, road, surface zones and barriers of one chunk as a handful of
+/// merged meshes following the centreline, with normals from the road surface and
+/// UVs running along the track.
+pub fn build_chunk(course: &Course, index: usize) -> ChunkMeshes {
+    let (start, end) = chunk_range(course, index);
+    let mut builders: Vec<(ChunkMaterial, MeshBuilder)> = Vec::new();
+
+    // Ground covered by the fallback surface, and the road just above it
+    let half_width = course.half_width();
+    builder(
+        &mut builders,
+        ChunkMaterial::Surface(course.surfaces.fallback),
+    )
+    .add_ribbon(course, start, end, |_| (-half_width, half_width), -0.02);
+    builder(&mut builders, ChunkMaterial::Surface(SurfaceKind::Asphalt)).add_ribbon(
+        course,
+        start,
+        end,
+        |frame| (-frame.width / 2.0, frame.width / 2.0),
+        -0.01,
+    );
+
+    // Surface zones, each painted slightly above the previous one
+    for (zone_index, zone) in course.surfaces.zones.iter().enumerate() {
+        let zone_start = zone.start.max(start);
+        let zone_end = zone.end.min(end);
+        if zone_end <= zone_start {
+            continue;
+        }
+        let height = zone_index as f32 * 0.002;
+        let lateral = |_: &TrackFrame| (zone.left, zone.right);
+        if zone.kind != SurfaceKind::Kerb {
+            builder(&mut builders, ChunkMaterial::Surface(zone.kind))
+                .add_ribbon(course, zone_start, zone_end, lateral, height);
+            continue;
+        }
+        // Kerbs get red and white stripes at fixed distances along the course
+        let mut stripe = (zone_start / KERB_STRIPE_LENGTH).floor();
+        while stripe * KERB_STRIPE_LENGTH < zone_end {
+            let stripe_start = (stripe * KERB_STRIPE_LENGTH).max(zone_start);
+            let stripe_end = ((stripe + 1.0) * KERB_STRIPE_LENGTH).min(zone_end);
+            let material = if stripe as i64 % 2 == 0 {
+                ChunkMaterial::Surface(SurfaceKind::Kerb)
+            } else {
+                ChunkMaterial::KerbStripe
+            };
+            builder(&mut builders, material).add_ribbon(
+                course,
+                stripe_start,
+                stripe_end,
+                lateral,
+                height,
+            );
+            stripe += 1.0;
+        }
+    }
+
+    // Barriers on both sides, just outside the run-off
+    let barrier_offset = half_width + BARRIER_WIDTH / 2.0;
+    let barriers = builder(&mut builders, ChunkMaterial::Barrier);
+    for side in [-1.0, 1.0] {
+        let inner = side * barrier_offset - BARRIER_WIDTH / 2.0;
+        let outer = side * barrier_offset + BARRIER_WIDTH / 2.0;
+        barriers.add_ribbon(course, start, end, |_| (inner, outer), BARRIER_TOP);
+        for (lateral, facing) in [(inner, -1.0), (outer, 1.0)] {
+            barriers.add_strip(
+                sections(start, end).map(|distance| {
+                    let frame = course.frame_at(distance);
+                    let base = frame.point(lateral);
+                    Section {
+                        a: base + frame.normal * BARRIER_BOTTOM,
+                        b: base + frame.normal * BARRIER_TOP,
+                        normal: frame.right * facing,
+                        v: distance / UV_TILE,
+                    }
+                }),
+                (BARRIER_TOP - BARRIER_BOTTOM) / UV_TILE,
+            );
+        }
+    }
+
+    ChunkMeshes {
+        meshes: builders
+            .into_iter()
+            .filter(|(_, builder)| !builder.indices.is_empty())
+            .map(|(material, builder)| (material, builder.build()))
+            .collect(),
+    }
+}
+
+/// The builder collecting everything drawn with a material, created on first use.
+fn builder(
+    builders: &mut Vec<(ChunkMaterial, MeshBuilder)>,
+    material: ChunkMaterial,
+) -> &mut MeshBuilder {
+    let position = builders
+        .iter()
+        .position(|(existing, _)| *existing == material)
+        .unwrap_or_else(|| {
+            builders.push((material, MeshBuilder::default()));
+            builders.len() - 1
+        });
+    &mut builders[position].1
+}
+
+/// Distances of the cross-sections between `start` and `end`, both ends included.
+fn sections(start: f32, end: f32) -> impl Iterator<Item = f32> {
+    let count = ((end - start) / SECTION_SPACING).ceil().max(1.0) as usize;
+    (0..=count).map(move |i| start + (end - start) * i as f32 / count as f32)
+}
+
+/// One cross-section of a strip: an edge from `a` to `b` with the surface normal.
+struct Section {
+    a: Vec3,
+    b: Vec3,
+    normal: Vec3,
+    v: f32, // Texture coordinate along the strip
+}
+
+/// Accumulates vertices and triangles of many strips into a single mesh.
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    /// Adds a strip lying on the road surface between two lateral offsets,
+    /// lifted `height` off the road.
+    fn add_ribbon(
+        &mut self,
+        course: &Course,
+        start: f32,
+        end: f32,
+        lateral: impl Fn(&TrackFrame) -> (f32, f32),
+        height: f32,
+    ) {
+        if end - start < 0.01 {
+            return;
+        }
+        let mut width = 0.0_f32;
+        let rows: Vec<Section> = sections(start, end)
+            .map(|distance| {
+                let frame = course.frame_at(distance);
+                let (left, right) = lateral(&frame);
+                width = width.max(right - left);
+                let lift = frame.normal * height;
+                Section {
+                    a: frame.point(left) + lift,
+                    b: frame.point(right) + lift,
+                    normal: frame.normal,
+                    v: distance / UV_TILE,
+                }
+            })
+            .collect();
+        self.add_strip(rows.into_iter(), width / UV_TILE);
+    }
+
+    /// Adds a strip of quads joining consecutive sections, with U running from `a` (0)
+    /// to `b` (`u_width`) and triangles wound to face along the sections' normals.
+    fn add_strip(&mut self, sections: impl Iterator<Item = Section>, u_width: f32) {
+        let first = self.positions.len() as u32;
+        let mut count = 0;
+        let mut winding = 0.0;
+        let mut previous: Option<Vec3> = None;
+        for section in sections {
+            if let Some(previous_a) = previous {
+                winding += (section.b - section.a)
+                    .cross(previous_a - section.a)
+                    .dot(section.normal);
+            }
+            previous = Some(section.a);
+            self.positions.push(section.a.into());
+            self.positions.push(section.b.into());
+            self.normals.push(section.normal.into());
+            self.normals.push(section.normal.into());
+            self.uvs.push([0.0, section.v]);
+            self.uvs.push([u_width, section.v]);
+            count += 1;
+        }
+        // Counter-clockwise when seen from the side the normals point to
+        let flip = winding > 0.0;
+        for i in 0..count.max(1) as u32 - 1 {
+            let a0 = first + i * 2;
+            let (b0, a1, b1) = (a0 + 1, a0 + 2, a0 + 3);
+            let quad = if flip {
+                [a0, a1, b0, b0, a1, b1]
+            } else {
+                [a0, b0, a1, b0, b1, a1]
+            };
+            self.indices.extend_from_slice(&quad);
+        }
+    }
+
+    fn build(self) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+        .with_inserted_indices(Indices::U32(self.indices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::file::CourseFile;
+    use bevy::mesh::VertexAttributeValues;
+    use std::time::Instant;
+
+    fn course_1() -> Course {
+        CourseFile::parse(include_bytes!("../../assets/courses/course_1.course.ron")).unwrap()
+    }
+
+    #[test]
+    fn chunks_cover_the_course() {
+        let course = course_1();
+        let count = chunk_count(&course);
+        assert_eq!(chunk_range(&course, 0).0, geometry_start(&course));
+        assert_eq!(chunk_range(&course, count - 1).1, course.length());
+        for index in 1..count {
+            assert_eq!(
+                chunk_range(&course, index - 1).1,
+                chunk_range(&course, index).0
+            );
+        }
+    }
+
+    #[test]
+    fn road_faces_up_with_matching_normals() {
+        let course = course_1();
+        let chunk = build_chunk(&course, 1);
+        let (_, road) = chunk
+            .meshes
+            .iter()
+            .find(|(material, _)| *material == ChunkMaterial::Surface(SurfaceKind::Asphalt))
+            .unwrap();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            road.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("road has no positions");
+        };
+        let Some(Indices::U32(indices)) = road.indices() else {
+            panic!("road has no indices");
+        };
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i] as usize]));
+            let face = (b - a).cross(c - a).normalize();
+            assert!(face.y > 0.9, "road triangle faces {face}");
+        }
+        assert!(road.attribute(Mesh::ATTRIBUTE_UV_0).is_some());
+        // A few merged meshes instead of one per piece
+        assert!(chunk.meshes.len() <= 6);
+    }
+
+    /// Times building every chunk of each shipped course.
+    /// Run with `cargo test --release course_load_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn course_load_benchmark() {
+        for entry in std::fs::read_dir("assets/courses").unwrap() {
+            let path = entry.unwrap().path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !name.ends_with(".course.ron") {
+                continue;
+            }
+            let started = Instant::now();
+            let course = CourseFile::parse(&std::fs::read(&path).unwrap()).unwrap();
+            let parsed = started.elapsed();
+            let vertices: usize = (0..chunk_count(&course))
+                .flat_map(|index| build_chunk(&course, index).meshes)
+                .map(|(_, mesh)| mesh.count_vertices())
+                .sum();
+            println!(
+                "{name}: parsed in {:.1?}, {} chunks ({vertices} vertices) built in {:.1?}",
+                parsed,
+                chunk_count(&course),
+                started.elapsed() - parsed,
+            );
+        }
+    }
+}
@@ -0,0 +1,329 @@
+// Course Module Definition
+// This module describes a course: the centreline it follows, the surfaces it is made of
+// and everything placed along it. Courses are loaded from `assets/courses/*.course.ron`.
+pub mod file;
+pub mod generator;
+pub mod loader;
+pub mod mesh;
+pub mod preview;
+pub mod spline;
+pub mod streaming;
+pub mod surface;
+
+use crate::weather::Weather;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use spline::*;
+use surface::*;
+
+/// Resource (and asset) describing the course currently being driven.
+/// Everything on a course is placed in course coordinates: the distance along the
+/// centreline and the lateral offset from it (positive = right). Rules and meshes go
+/// through the centreline's projection, so nothing assumes the track is straight.
+#[derive(Asset, TypePath, Resource, Debug, Clone)]
+pub struct Course {
+    pub name: String,
+    pub centreline: CentreLine,
+    pub runoff_width: f32, // Width of the run-off area on each side of the road (m)
+    pub checkpoints: Vec<f32>, // Timing and reset checkpoints (distances along the track, increasing)
+    pub start_grid: StartGrid,
+    pub finish: f32, // Distance of the finish line (m), crossed once per lap on circuits
+    pub laps: u32,   // Laps to drive; 1 on point-to-point courses
+    pub gravity: f32, // Gravitational acceleration (m/s^2)
+    pub air_density: f32, // Density of the air (kg/m^3); drag and the DRS gain scale with it
+    pub ambient_temperature: f32, // Air temperature (Celsius); warm air cools the engine less
+    pub weather: Weather, // Default weather when the player doesn't pick one
+    pub surfaces: CourseSurfaces,
+    pub scenery: Vec<SceneryPlacement>,
+}
+
+/// Air density at sea level on Earth (kg/m^3), which the car's aerodynamics are tuned for.
+pub const STANDARD_AIR_DENSITY: f32 = 1.225;
+/// Ambient temperature the hardware temperatures are taken as (Celsius).
+pub const STANDARD_AMBIENT_TEMPERATURE: f32 = 20.0;
+
+/// Height of the checkpoint and finish gates above the road (m).
+const GATE_HEIGHT: f32 = 10.0;
+
+/// How far past a gate a reset car is placed, so it can't count the gate twice (m).
+const RESTART_PAST_GATE: f32 = 2.0;
+
+/// Where cars line up at the start of a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartGrid {
+    pub distance: f32,        // Distance of the grid line along the track (m)
+    pub slots: Vec<GridSlot>, // Grid positions, pole position first
+}
+
+/// A single grid position relative to the grid line.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GridSlot {
+    pub lateral: f32, // Offset from the centreline (m)
+    pub back: f32,    // Distance behind the grid line (m)
+}
+
+/// Kind of decoration placed beside the track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SceneryKind {
+    Tree,
+    Building,
+    Grandstand,
+}
+
+/// A decoration placed in course coordinates.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SceneryPlacement {
+    pub kind: SceneryKind,
+    pub distance: f32, // Distance along the track (m)
+    pub lateral: f32,  // Offset from the centreline (m)
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl Course {
+    /// Length of the centreline (m).
+    pub fn length(&self) -> f32 {
+        self.centreline.length()
+    }
+
+    /// Distance from the centreline to the outer edge of the run-off on either side.
+    pub fn half_width(&self) -> f32 {
+        self.centreline.max_width() / 2.0 + self.runoff_width
+    }
+
+    /// Drag and the DRS gain relative to a course at standard air density.
+    pub fn air_density_ratio(&self) -> f32 {
+        self.air_density / STANDARD_AIR_DENSITY
+    }
+
+    /// Temperature the engine gains (or loses) from the course's air. The CPU and the GPU
+    /// both run that much hotter or cooler than at the standard temperature.
+    pub fn engine_heat(&self) -> f32 {
+        (self.ambient_temperature - STANDARD_AMBIENT_TEMPERATURE) * 2.0
+    }
+
+    /// Distance of the rearmost grid slot along the track, where the course geometry starts.
+    pub fn rear_of_grid(&self) -> f32 {
+        let back = self
+            .start_grid
+            .slots
+            .iter()
+            .map(|slot| slot.back)
+            .fold(0.0, f32::max);
+        self.start_grid.distance - back
+    }
+
+    /// True for circuits, whose centreline is a closed loop driven for several laps.
+    pub fn is_circuit(&self) -> bool {
+        self.centreline.is_closed()
+    }
+
+    /// Distances along the track where a lap's racing starts and ends: the grid and the
+    /// finish on point-to-point courses, once around from the finish line on circuits.
+    pub fn racing_span(&self) -> (f32, f32) {
+        if self.is_circuit() {
+            (self.finish, self.finish + self.length())
+        } else {
+            (self.start_grid.distance, self.finish)
+        }
+    }
+
+    /// Length of one lap (m).
+    pub fn lap_length(&self) -> f32 {
+        let (start, end) = self.racing_span();
+        end - start
+    }
+
+    /// Timing gates of a lap in the order they must be driven through: every checkpoint,
+    /// then the finish line. Each gate ends a sector.
+    pub fn gates(&self) -> impl Iterator<Item = f32> + '_ {
+        self.checkpoints
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.finish))
+    }
+
+    /// Number of sectors in a lap.
+    pub fn sector_count(&self) -> usize {
+        self.checkpoints.len() + 1
+    }
+
+    /// Where a car is put back on the track after passing `passed_gates` gates of a lap:
+    /// just past the last checkpoint passed, or where the lap started.
+    pub fn restart_distance(&self, first_lap: bool, passed_gates: usize) -> f32 {
+        match passed_gates.checked_sub(1) {
+            Some(gate) => self.checkpoints[gate] + RESTART_PAST_GATE,
+            None if first_lap => self.start_grid.distance,
+            None => self.finish + RESTART_PAST_GATE,
+        }
+    }
+
+    /// Share of the whole run driven (0 to 1) for a car at a distance along the track,
+    /// having completed `laps` laps and passed `passed_gates` gates of the current one.
+    pub fn progress(&self, distance: f32, laps: u32, passed_gates: usize) -> f32 {
+        let lap = if self.is_circuit() {
+            let length = self.length();
+            let along = (distance - self.finish).rem_euclid(length);
+            let first_gate = self
+                .gates()
+                .map(|gate| (gate - self.finish).rem_euclid(length))
+                .find(|gate| *gate > 0.0)
+                .unwrap_or(length);
+            // Behind the line before the first gate: the lap hasn't really started
+            if passed_gates == 0 && along > first_gate {
+                0.0
+            } else {
+                along / length
+            }
+        } else {
+            let (start, end) = self.racing_span();
+            (distance - start) / (end - start)
+        };
+        ((laps as f32 + lap.clamp(0.0, 1.0)) / self.laps.max(1) as f32).clamp(0.0, 1.0)
+    }
+
+    /// True if moving from `from` to `to` drives forwards through the gate at a distance.
+    /// A gate spans the full width of the course up to `GATE_HEIGHT`, so driving around
+    /// it (or being projected past it by cutting across the infield) doesn't count.
+    pub fn crosses_gate(&self, distance: f32, from: Vec3, to: Vec3) -> bool {
+        let frame = self.frame_at(distance);
+        let before = (from - frame.position).dot(frame.tangent);
+        let after = (to - frame.position).dot(frame.tangent);
+        if !(before < 0.0 && after >= 0.0) {
+            return false;
+        }
+        let crossing = from.lerp(to, before / (before - after)) - frame.position;
+        let height = crossing.dot(frame.normal);
+        crossing.dot(frame.right).abs() <= self.half_width()
+            && (-2.0..=GATE_HEIGHT).contains(&height)
+    }
+
+    /// Lowest and highest point of the road over one lap (m).
+    pub fn elevation_range(&self) -> (f32, f32) {
+        self.racing_frames()
+            .map(|frame| frame.position.y)
+            .fold((f32::MAX, f32::MIN), |(low, high), y| {
+                (low.min(y), high.max(y))
+            })
+    }
+
+    /// Difficulty rating from 1 (easy) to 5 (hard), judged from how much the road turns,
+    /// how hilly it is and how narrow it gets over one lap.
+    pub fn difficulty(&self) -> u32 {
+        let frames: Vec<_> = self.racing_frames().collect();
+        let turning: f32 = frames
+            .windows(2)
+            .map(|pair| {
+                let a = pair[0].tangent.with_y(0.0).normalize_or_zero();
+                let b = pair[1].tangent.with_y(0.0).normalize_or_zero();
+                a.angle_between(b)
+            })
+            .sum();
+        let kilometres = (self.lap_length() / 1000.0).max(0.1);
+        let (low, high) = self.elevation_range();
+        let narrowest = frames.iter().map(|f| f.width).fold(f32::MAX, f32::min);
+
+        let curviness = turning.to_degrees() / kilometres / 180.0; // Half turns per km
+        let hilliness = (high - low) / 50.0;
+        let narrowness = (40.0 - narrowest).max(0.0) / 20.0;
+        (1.0 + 2.0 * curviness + hilliness + narrowness)
+            .round()
+            .clamp(1.0, 5.0) as u32
+    }
+
+    /// Frames every 10 m over one lap of racing.
+    fn racing_frames(&self) -> impl Iterator<Item = TrackFrame> + '_ {
+        let (start, end) = self.racing_span();
+        let steps = ((end - start) / 10.0).ceil().max(1.0) as usize;
+        (0..=steps).map(move |i| self.frame_at(start + (end - start) * i as f32 / steps as f32))
+    }
+
+    /// Projects a world position into course coordinates.
+    pub fn project(&self, position: Vec3) -> TrackProjection {
+        self.centreline.project(position)
+    }
+
+    /// Track frame at a distance along the centreline.
+    pub fn frame_at(&self, distance: f32) -> TrackFrame {
+        self.centreline.frame_at(distance)
+    }
+
+    /// World transform of a car placed at a lateral offset and distance, facing down the track.
+    pub fn car_transform(&self, distance: f32, lateral: f32) -> Transform {
+        let frame = self.frame_at(distance);
+        Transform::from_translation(frame.point(lateral) + frame.normal * 0.5)
+            .looking_to(frame.tangent.with_y(0.0), Vec3::Y)
+    }
+
+    /// World transform of a car placed on the centreline at a distance, facing down the track.
+    pub fn centreline_transform(&self, distance: f32) -> Transform {
+        self.car_transform(distance, 0.0)
+    }
+
+    /// World transform of a car lined up in a slot of the start grid.
+    pub fn grid_transform(&self, slot: &GridSlot) -> Transform {
+        self.car_transform(self.start_grid.distance - slot.back, slot.lateral)
+    }
+
+    /// Returns the surface at a projected position.
+    pub fn surface_at(&self, projection: &TrackProjection) -> SurfaceKind {
+        self.surfaces.surface_at(
+            projection.distance,
+            projection.lateral,
+            projection.width / 2.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::file::CourseFile;
+
+    #[test]
+    fn twisty_hilly_courses_rate_harder() {
+        let course_1 =
+            CourseFile::parse(include_bytes!("../../assets/courses/course_1.course.ron")).unwrap();
+        let course_2 =
+            CourseFile::parse(include_bytes!("../../assets/courses/course_2.course.ron")).unwrap();
+        assert_eq!(course_1.difficulty(), 1);
+        assert!(course_2.difficulty() > course_1.difficulty());
+    }
+
+    #[test]
+    fn gates_only_count_when_driven_through() {
+        let circuit =
+            CourseFile::parse(include_bytes!("../../assets/courses/circuit_1.course.ron")).unwrap();
+        assert!(circuit.is_circuit());
+        assert_eq!(circuit.laps, 3);
+
+        let gate = circuit.checkpoints[0];
+        let frame = circuit.frame_at(gate);
+        let before = frame.position + frame.normal - frame.tangent * 3.0;
+        let after = frame.position + frame.normal + frame.tangent * 3.0;
+        assert!(circuit.crosses_gate(gate, before, after));
+        assert!(!circuit.crosses_gate(gate, after, before));
+
+        // Cutting across the infield passes the gate's plane far away from the road
+        let infield = frame.right * 100.0;
+        assert!(!circuit.crosses_gate(gate, before + infield, after + infield));
+    }
+
+    #[test]
+    fn progress_counts_laps_and_distance() {
+        let circuit =
+            CourseFile::parse(include_bytes!("../../assets/courses/circuit_1.course.ron")).unwrap();
+        let half_lap = circuit.finish + circuit.length() / 2.0;
+        assert!((circuit.progress(half_lap, 1, 1) - 0.5).abs() < 1e-3);
+        // Rolling back behind the line at the start doesn't count as a nearly finished lap
+        assert_eq!(circuit.progress(circuit.finish - 5.0, 0, 0), 0.0);
+
+        let sprint =
+            CourseFile::parse(include_bytes!("../../assets/courses/course_1.course.ron")).unwrap();
+        assert_eq!(sprint.progress(sprint.start_grid.distance, 0, 0), 0.0);
+        assert_eq!(sprint.progress(sprint.finish + 50.0, 0, 1), 1.0);
+    }
+}
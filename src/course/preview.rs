@@ -0,0 +1,137 @@
+use super::Course;
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+const BACKGROUND: [u8; 4] = [20, 20, 24, 255];
+const TRACK_COLOR: Color = Color::srgb(0.85, 0.85, 0.85);
+const START_COLOR: Color = Color::srgb(0.2, 0.8, 0.2);
+const FINISH_COLOR: Color = Color::srgb(0.9, 0.2, 0.2);
+const PROFILE_COLOR: Color = Color::srgb(0.3, 0.55, 0.85);
+
+/// Number of centreline samples drawn on the previews.
+const PREVIEW_SAMPLES: usize = 600;
+
+fn blank_image(width: u32, height: u32) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &BACKGROUND,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn draw_dot(image: &mut Image, center: Vec2, radius: i32, color: Color) {
+    let size = image.size().as_ivec2();
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            let x = center.x as i32 + dx;
+            let y = center.y as i32 + dy;
+            if x >= 0 && y >= 0 && x < size.x && y < size.y {
+                let _ = image.set_color_at(x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+/// Maps world positions onto a square top-down map of the course, so markers can be
+/// placed over the map image. North (-Z) is up.
+#[derive(Debug, Clone, Copy)]
+pub struct MapProjection {
+    min: Vec2,    // Lowest world X/Z of the drawn centreline
+    scale: f32,   // Pixels per metre
+    offset: Vec2, // Pixel position of `min`
+}
+
+impl MapProjection {
+    /// Fits the centreline points into a square map of `size` pixels with a margin.
+    fn fit(points: &[Vec2], size: u32) -> Self {
+        let min = points.iter().fold(Vec2::MAX, |acc, p| acc.min(*p));
+        let max = points.iter().fold(Vec2::MIN, |acc, p| acc.max(*p));
+        let margin = size as f32 * 0.08;
+        let usable = size as f32 - margin * 2.0;
+        let scale = usable / (max - min).max_element().max(1.0);
+        // Centre the drawing in the square image
+        let offset = (Vec2::splat(usable) - (max - min) * scale) / 2.0 + margin;
+        Self { min, scale, offset }
+    }
+
+    /// Projection for the map of a course drawn at `size` pixels.
+    pub fn new(course: &Course, size: u32) -> Self {
+        Self::fit(&map_points(course), size)
+    }
+
+    /// Pixel position of a world position on the map.
+    pub fn to_pixel(self, position: Vec3) -> Vec2 {
+        (Vec2::new(position.x, position.z) - self.min) * self.scale + self.offset
+    }
+}
+
+/// Centreline points (world X/Z) drawn on the map: from the grid to the finish, or the
+/// whole loop of a circuit.
+fn map_points(course: &Course) -> Vec<Vec2> {
+    let (start, end) = if course.is_circuit() {
+        (0.0, course.length())
+    } else {
+        (course.rear_of_grid(), course.finish)
+    };
+    (0..=PREVIEW_SAMPLES)
+        .map(|i| {
+            let distance = start + (end - start) * i as f32 / PREVIEW_SAMPLES as f32;
+            let position = course.frame_at(distance).position;
+            Vec2::new(position.x, position.z)
+        })
+        .collect()
+}
+
+/// Top-down map of the course's centreline, from the grid to the finish, or the whole
+/// loop of a circuit. North (-Z) is up, the start is marked green and the finish red.
+pub fn map_image(course: &Course, size: u32) -> Image {
+    let points = map_points(course);
+    let projection = MapProjection::fit(&points, size);
+
+    let mut image = blank_image(size, size);
+    for point in &points {
+        let pixel = projection.to_pixel(Vec3::new(point.x, 0.0, point.y));
+        draw_dot(&mut image, pixel, 2, TRACK_COLOR);
+    }
+    let grid = course.frame_at(course.start_grid.distance).position;
+    draw_dot(&mut image, projection.to_pixel(grid), 5, START_COLOR);
+    let finish = course.frame_at(course.finish).position;
+    draw_dot(&mut image, projection.to_pixel(finish), 5, FINISH_COLOR);
+    image
+}
+
+/// Side view of the road height over one lap.
+pub fn elevation_image(course: &Course, width: u32, height: u32) -> Image {
+    let (start, end) = course.racing_span();
+    let heights: Vec<f32> = (0..width)
+        .map(|x| {
+            let distance = start + (end - start) * x as f32 / (width - 1) as f32;
+            course.frame_at(distance).position.y
+        })
+        .collect();
+
+    let low = heights.iter().copied().fold(f32::MAX, f32::min);
+    let high = heights.iter().copied().fold(f32::MIN, f32::max);
+    // Flat courses still get a visible band instead of an exaggerated profile
+    let range = (high - low).max(20.0);
+    let usable = height as f32 * 0.8;
+
+    let mut image = blank_image(width, height);
+    for (x, h) in heights.iter().enumerate() {
+        let filled = ((h - low) / range * usable) as u32 + height / 10;
+        for y in (height - filled.min(height))..height {
+            let _ = image.set_color_at(x as u32, y, PROFILE_COLOR);
+        }
+    }
+    image
+}
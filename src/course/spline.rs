@@ -0,0 +1,366 @@
+use bevy::prelude::*;
+
+/// Target spacing (m) between the precomputed samples of the centreline.
+const SAMPLE_SPACING: f32 = 5.0;
+
+/// A point the centreline passes through, together with the shape of the road there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlPoint {
+    pub position: Vec3, // World position of the centreline
+    pub width: f32,     // Road width including kerbs (m)
+    pub banking: f32,   // Roll of the road (radians), positive raises the right edge
+}
+
+/// Position and orientation of the track at a distance along the centreline.
+#[derive(Debug, Clone, Copy)]
+pub struct TrackFrame {
+    pub distance: f32,  // Distance along the centreline (m)
+    pub position: Vec3, // Point on the centreline
+    pub tangent: Vec3,  // Direction of travel
+    pub right: Vec3,    // Across the road surface towards the right edge (banked)
+    pub normal: Vec3,   // Up out of the road surface
+    pub width: f32,     // Road width (m)
+}
+
+impl TrackFrame {
+    /// World position on the road surface at a lateral offset from the centreline.
+    pub fn point(&self, lateral: f32) -> Vec3 {
+        self.position + self.right * lateral
+    }
+
+    /// Rotation whose local X, Y and -Z axes are the frame's right, normal and tangent,
+    /// so Bevy meshes placed with it lie flat on the road facing down the track.
+    pub fn rotation(&self) -> Quat {
+        Quat::from_mat3(&Mat3::from_cols(self.right, self.normal, -self.tangent))
+    }
+
+    fn lerp(&self, other: &TrackFrame, t: f32) -> TrackFrame {
+        // The position keeps following the end segments past either end of the centreline
+        let clamped = t.clamp(0.0, 1.0);
+        TrackFrame {
+            distance: self.distance + (other.distance - self.distance) * t,
+            position: self.position.lerp(other.position, t),
+            tangent: self.tangent.lerp(other.tangent, clamped).normalize(),
+            right: self.right.lerp(other.right, clamped).normalize(),
+            normal: self.normal.lerp(other.normal, clamped).normalize(),
+            width: self.width + (other.width - self.width) * clamped,
+        }
+    }
+}
+
+/// Where a world position lies relative to the track.
+#[derive(Debug, Clone, Copy)]
+pub struct TrackProjection {
+    pub distance: f32, // Distance along the centreline (m)
+    pub lateral: f32,  // Offset from the centreline across the road (m), positive = right
+    pub height: f32,   // Height of the road surface under the position
+    pub normal: Vec3,  // Up direction of the road surface
+    pub width: f32,    // Road width at this distance (m)
+}
+
+/// Smooth 3D centreline of a course: a Catmull-Rom spline through the control points,
+/// sampled by arc length so distances along it are real metres.
+/// A closed centreline joins its last control point back to the first, and distances
+/// along it wrap around the loop.
+#[derive(Debug, Clone)]
+pub struct CentreLine {
+    samples: Vec<TrackFrame>,
+    closed: bool,
+}
+
+impl CentreLine {
+    /// Builds an open centreline through at least two control points.
+    pub fn new(points: &[ControlPoint]) -> Self {
+        assert!(
+            points.len() >= 2,
+            "a centreline needs at least two control points"
+        );
+        Self::build(points, false)
+    }
+
+    /// Builds a closed loop through at least three control points.
+    pub fn closed(points: &[ControlPoint]) -> Self {
+        assert!(
+            points.len() >= 3,
+            "a closed centreline needs at least three control points"
+        );
+        Self::build(points, true)
+    }
+
+    fn build(points: &[ControlPoint], closed: bool) -> Self {
+        let count = points.len();
+        let spans = if closed { count } else { count - 1 };
+        let mut samples: Vec<TrackFrame> = Vec::new();
+        for span in 0..spans {
+            let p1 = points[span];
+            let p2 = points[(span + 1) % count];
+            // Loops wrap around; open lines mirror the neighbours at the ends so the
+            // spline starts and ends straight
+            let p0 = if closed {
+                points[(span + count - 1) % count].position
+            } else if span == 0 {
+                p1.position * 2.0 - p2.position
+            } else {
+                points[span - 1].position
+            };
+            let p3 = if closed {
+                points[(span + 2) % count].position
+            } else if span + 1 == spans {
+                p2.position * 2.0 - p1.position
+            } else {
+                points[span + 2].position
+            };
+
+            let steps = ((p2.position - p1.position).length() / SAMPLE_SPACING)
+                .ceil()
+                .max(1.0) as usize;
+            // The first point of every span but the first is the previous span's last point
+            let first_step = if span == 0 { 0 } else { 1 };
+            for step in first_step..=steps {
+                let t = step as f32 / steps as f32;
+                let position = catmull_rom(p0, p1.position, p2.position, p3, t);
+                let tangent =
+                    catmull_rom_derivative(p0, p1.position, p2.position, p3, t).normalize();
+                let distance = samples
+                    .last()
+                    .map(|prev| prev.distance + (position - prev.position).length())
+                    .unwrap_or(0.0);
+                let width = p1.width + (p2.width - p1.width) * t;
+                let banking = p1.banking + (p2.banking - p1.banking) * t;
+                let previous_right = samples.last().map_or(Vec3::X, |prev| prev.right);
+                samples.push(frame(
+                    distance,
+                    position,
+                    tangent,
+                    width,
+                    banking,
+                    previous_right,
+                ));
+            }
+        }
+
+        Self { samples, closed }
+    }
+
+    /// True if the centreline is a closed loop.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Total length of the centreline (m).
+    pub fn length(&self) -> f32 {
+        self.samples.last().map(|s| s.distance).unwrap_or(0.0)
+    }
+
+    /// Widest point of the road (m).
+    pub fn max_width(&self) -> f32 {
+        self.samples.iter().map(|s| s.width).fold(0.0, f32::max)
+    }
+
+    /// Frame at a distance along the centreline. On an open line, distances before the
+    /// start or past the end continue straight along the first or last segment; on a
+    /// loop they wrap around.
+    pub fn frame_at(&self, distance: f32) -> TrackFrame {
+        let distance = if self.closed {
+            distance.rem_euclid(self.length())
+        } else {
+            distance
+        };
+        let index = self
+            .samples
+            .partition_point(|s| s.distance <= distance)
+            .clamp(1, self.samples.len() - 1);
+        let a = &self.samples[index - 1];
+        let b = &self.samples[index];
+        a.lerp(b, (distance - a.distance) / (b.distance - a.distance))
+    }
+
+    /// Projects a world position onto the track.
+    pub fn project(&self, position: Vec3) -> TrackProjection {
+        let last = self.samples.len() - 2;
+        let mut best = (f32::MAX, 0, 0.0);
+        for (i, pair) in self.samples.windows(2).enumerate() {
+            let (a, b) = (&pair[0], &pair[1]);
+            let segment = b.position - a.position;
+            let mut t = (position - a.position).dot(segment) / segment.length_squared();
+            // Only the end segments of an open line reach past its ends
+            if i > 0 || self.closed {
+                t = t.max(0.0);
+            }
+            if i < last || self.closed {
+                t = t.min(1.0);
+            }
+            let distance_squared = (a.position + segment * t).distance_squared(position);
+            if distance_squared < best.0 {
+                best = (distance_squared, i, t);
+            }
+        }
+
+        let (_, i, t) = best;
+        let frame = self.samples[i].lerp(&self.samples[i + 1], t);
+        let lateral = (position - frame.position).dot(frame.right);
+        TrackProjection {
+            distance: frame.distance,
+            lateral,
+            height: frame.point(lateral).y,
+            normal: frame.normal,
+            width: frame.width,
+        }
+    }
+}
+
+/// Frame of the centreline at one sample. Where the line runs straight up or down, the
+/// road keeps facing the way it did at the previous sample.
+fn frame(
+    distance: f32,
+    position: Vec3,
+    tangent: Vec3,
+    width: f32,
+    banking: f32,
+    previous_right: Vec3,
+) -> TrackFrame {
+    let flat_right = tangent.cross(Vec3::Y).normalize_or(previous_right);
+    let flat_normal = flat_right.cross(tangent);
+    let bank = Quat::from_axis_angle(tangent, -banking);
+    TrackFrame {
+        distance,
+        position,
+        tangent,
+        right: bank * flat_right,
+        normal: bank * flat_normal,
+        width,
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+fn catmull_rom_derivative(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    0.5 * ((p2 - p0)
+        + 2.0 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t
+        + 3.0 * (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(position: Vec3, width: f32) -> ControlPoint {
+        ControlPoint {
+            position,
+            width,
+            banking: 0.0,
+        }
+    }
+
+    fn straight() -> CentreLine {
+        CentreLine::new(&[
+            point(Vec3::ZERO, 20.0),
+            point(Vec3::new(0.0, 0.0, -100.0), 20.0),
+            point(Vec3::new(0.0, 0.0, -200.0), 20.0),
+        ])
+    }
+
+    #[test]
+    fn straight_line_projects_to_distance_and_offset() {
+        let line = straight();
+        assert!((line.length() - 200.0).abs() < 1e-3);
+
+        let projection = line.project(Vec3::new(3.0, 1.0, -50.0));
+        assert!((projection.distance - 50.0).abs() < 1e-3);
+        assert!((projection.lateral - 3.0).abs() < 1e-3);
+        assert!(projection.height.abs() < 1e-3);
+        assert!(projection.normal.abs_diff_eq(Vec3::Y, 1e-5));
+    }
+
+    #[test]
+    fn vertical_sections_keep_their_frame() {
+        // Ends going straight up
+        let line = CentreLine::new(&[
+            point(Vec3::ZERO, 20.0),
+            point(Vec3::new(0.0, 0.0, -100.0), 20.0),
+            point(Vec3::new(0.0, 10.0, -100.0), 20.0),
+        ]);
+        let end = line.frame_at(line.length());
+        assert!(end.tangent.abs_diff_eq(Vec3::Y, 1e-5));
+        assert!((end.right.length() - 1.0).abs() < 1e-5);
+        assert!(end.right.dot(end.tangent).abs() < 1e-5);
+        assert!(end.normal.is_finite());
+    }
+
+    #[test]
+    fn positions_past_the_ends_extrapolate() {
+        let line = straight();
+        assert!((line.project(Vec3::new(0.0, 0.0, 10.0)).distance + 10.0).abs() < 1e-3);
+        assert!((line.project(Vec3::new(0.0, 0.0, -230.0)).distance - 230.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn curved_line_follows_the_bend() {
+        // A quarter circle of radius 100 turning from -Z towards +X
+        let points: Vec<_> = (0..=8)
+            .map(|i| {
+                let angle = i as f32 / 8.0 * std::f32::consts::FRAC_PI_2;
+                let position = Vec3::new(100.0 - 100.0 * angle.cos(), 0.0, -100.0 * angle.sin());
+                point(position, 20.0)
+            })
+            .collect();
+        let line = CentreLine::new(&points);
+        let quarter = std::f32::consts::FRAC_PI_2 * 100.0;
+        assert!((line.length() - quarter).abs() < 1.0);
+
+        // A point just inside the bend at 45 degrees
+        let angle = std::f32::consts::FRAC_PI_4;
+        let inside = Vec3::new(100.0 - 95.0 * angle.cos(), 0.0, -95.0 * angle.sin());
+        let projection = line.project(inside);
+        assert!((projection.distance - quarter / 2.0).abs() < 1.0);
+        assert!((projection.lateral - 5.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn banking_raises_the_right_edge() {
+        let mut points = [
+            point(Vec3::ZERO, 20.0),
+            point(Vec3::new(0.0, 0.0, -100.0), 20.0),
+        ];
+        for point in &mut points {
+            point.banking = 0.1;
+        }
+        let line = CentreLine::new(&points);
+        let frame = line.frame_at(50.0);
+        assert!(frame.point(5.0).y > frame.position.y);
+        assert!(line.project(Vec3::new(5.0, 0.0, -50.0)).height > 0.0);
+    }
+
+    #[test]
+    fn closed_loop_wraps_around() {
+        let points: Vec<_> = (0..12)
+            .map(|i| {
+                let angle = i as f32 / 12.0 * std::f32::consts::TAU;
+                point(
+                    Vec3::new(100.0 * angle.cos(), 0.0, 100.0 * angle.sin()),
+                    20.0,
+                )
+            })
+            .collect();
+        let line = CentreLine::closed(&points);
+        let circumference = std::f32::consts::TAU * 100.0;
+        assert!((line.length() - circumference).abs() < 2.0);
+
+        let wrapped = line.frame_at(line.length() + 10.0);
+        assert!(
+            wrapped
+                .position
+                .abs_diff_eq(line.frame_at(10.0).position, 1e-2)
+        );
+        // Just before the start of the loop projects near the end, not past the start
+        let before_start = line.frame_at(-5.0).position;
+        assert!((line.project(before_start).distance - (line.length() - 5.0)).abs() < 0.5);
+    }
+}
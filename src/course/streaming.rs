@@ -0,0 +1,120 @@
+use super::Course;
+use super::mesh::{self, ChunkMaterial};
+use crate::game::GameWorld;
+use crate::states::OnTrack;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Chunks closer to the camera than this are kept built (m).
+pub const VIEW_DISTANCE: f32 = 800.0;
+/// Extra distance a chunk must move past the view distance before it's removed again,
+/// so chunks on the edge don't rebuild every frame (m).
+const UNLOAD_MARGIN: f32 = 100.0;
+
+/// Marker for the parent entity of a streamed course chunk.
+#[derive(Component)]
+pub struct CourseChunk;
+
+/// Resource tracking which chunks of the current course are built.
+#[derive(Resource, Default)]
+pub struct StreamedChunks {
+    bounds: Vec<(Vec3, f32)>, // Enclosing sphere of every chunk, filled on first use
+    loaded: HashMap<usize, Entity>,
+    materials: HashMap<ChunkMaterial, Handle<StandardMaterial>>,
+}
+
+/// Plugin that builds the course geometry around the camera in chunks and removes
+/// chunks that fall out of view. Barrier colliders don't depend on it, only the visuals.
+pub struct CourseStreamingPlugin;
+
+impl Plugin for CourseStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(OnTrack), reset_streamed_chunks)
+            .add_systems(
+                Update,
+                stream_course_chunks.run_if(in_state(OnTrack).and(resource_exists::<Course>)),
+            );
+    }
+}
+
+fn reset_streamed_chunks(mut commands: Commands) {
+    commands.insert_resource(StreamedChunks::default());
+}
+
+/// Builds chunks that came into view and despawns chunks that left it.
+fn stream_course_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut streamed: ResMut<StreamedChunks>,
+    course: Res<Course>,
+    camera: Query<&Transform, With<Camera3d>>,
+) {
+    let Ok(camera) = camera.single() else {
+        return;
+    };
+    if streamed.bounds.is_empty() {
+        streamed.bounds = (0..mesh::chunk_count(&course))
+            .map(|index| mesh::chunk_bounds(&course, index))
+            .collect();
+    }
+
+    let viewer = camera.translation;
+    for index in 0..streamed.bounds.len() {
+        let (centre, radius) = streamed.bounds[index];
+        let distance = viewer.distance(centre) - radius;
+        let loaded = streamed.loaded.contains_key(&index);
+        if !loaded && distance <= VIEW_DISTANCE {
+            let chunk = spawn_chunk(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut streamed,
+                &course,
+                index,
+            );
+            streamed.loaded.insert(index, chunk);
+        } else if distance > VIEW_DISTANCE + UNLOAD_MARGIN
+            && let Some(chunk) = streamed.loaded.remove(&index)
+        {
+            commands.entity(chunk).despawn();
+        }
+    }
+}
+
+fn spawn_chunk(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    streamed: &mut StreamedChunks,
+    course: &Course,
+    index: usize,
+) -> Entity {
+    let chunk = mesh::build_chunk(course, index);
+    commands
+        .spawn((
+            CourseChunk,
+            Transform::default(),
+            Visibility::default(),
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            for (material, mesh) in chunk.meshes {
+                let material = streamed
+                    .materials
+                    .entry(material)
+                    .or_insert_with(|| materials.add(chunk_color(material)))
+                    .clone();
+                parent.spawn((Mesh3d(meshes.add(mesh)), MeshMaterial3d(material)));
+            }
+        })
+        .id()
+}
+
+fn chunk_color(material: ChunkMaterial) -> Color {
+    match material {
+        ChunkMaterial::Surface(kind) => kind.color(),
+        ChunkMaterial::KerbStripe => Color::srgb(0.95, 0.95, 0.95),
+        ChunkMaterial::Barrier => Color::srgb(0.8, 0.8, 0.8),
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The material a part of the course is made of.
+/// Each surface changes how much grip the tyres get and how much it slows the car down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SurfaceKind {
+    #[default]
+    Asphalt,
+    Kerb,
+    Grass,
+    Gravel,
+}
+
+impl SurfaceKind {
+    /// Multiplier applied to the car's grip while driving on this surface.
+    pub fn grip(&self) -> f32 {
+        match self {
+            SurfaceKind::Asphalt => 1.0,
+            SurfaceKind::Kerb => 0.85,
+            SurfaceKind::Grass => 0.45,
+            SurfaceKind::Gravel => 0.35,
+        }
+    }
+
+    /// Multiplier applied to the ground friction (rolling resistance) on this surface.
+    pub fn rolling_resistance(&self) -> f32 {
+        match self {
+            SurfaceKind::Asphalt => 1.0,
+            SurfaceKind::Kerb => 5.0,
+            SurfaceKind::Grass => 120.0,
+            SurfaceKind::Gravel => 300.0,
+        }
+    }
+
+    /// True for surfaces that count as part of the track (not course-out).
+    pub fn is_track(&self) -> bool {
+        matches!(self, SurfaceKind::Asphalt | SurfaceKind::Kerb)
+    }
+
+    /// Base colour of the surface's visual material.
+    pub fn color(&self) -> Color {
+        match self {
+            SurfaceKind::Asphalt => Color::srgb(0.2, 0.2, 0.25),
+            SurfaceKind::Kerb => Color::srgb(0.85, 0.1, 0.1),
+            SurfaceKind::Grass => Color::srgb(0.25, 0.45, 0.2),
+            SurfaceKind::Gravel => Color::srgb(0.65, 0.58, 0.45),
+        }
+    }
+}
+
+/// A rectangular area of the course in course coordinates:
+/// distance along the track and lateral offset from the centreline (positive = right).
+/// Zones without a start or end run along the whole course.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SurfaceZone {
+    pub kind: SurfaceKind,
+    #[serde(
+        default = "whole_course_start",
+        skip_serializing_if = "is_whole_course"
+    )]
+    pub start: f32, // Distance along the track where the zone begins (m)
+    #[serde(default = "whole_course_end", skip_serializing_if = "is_whole_course")]
+    pub end: f32, // Distance along the track where the zone ends (m)
+    pub left: f32,  // Lateral offset of the zone's left edge (m)
+    pub right: f32, // Lateral offset of the zone's right edge (m)
+}
+
+fn whole_course_start() -> f32 {
+    f32::MIN
+}
+
+fn whole_course_end() -> f32 {
+    f32::MAX
+}
+
+fn is_whole_course(distance: &f32) -> bool {
+    *distance == f32::MIN || *distance == f32::MAX
+}
+
+impl SurfaceZone {
+    pub fn contains(&self, distance: f32, lateral: f32) -> bool {
+        distance >= self.start
+            && distance <= self.end
+            && lateral >= self.left
+            && lateral <= self.right
+    }
+}
+
+/// Surface layout of a course. The road itself is asphalt and everything beside it uses
+/// the `fallback` surface; zones are painted on top, later zones over earlier ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CourseSurfaces {
+    pub fallback: SurfaceKind,
+    pub zones: Vec<SurfaceZone>,
+}
+
+impl CourseSurfaces {
+    /// Returns the surface at the given course coordinates on a road `half_road` wide
+    /// on either side of the centreline.
+    pub fn surface_at(&self, distance: f32, lateral: f32, half_road: f32) -> SurfaceKind {
+        self.zones
+            .iter()
+            .rev()
+            .find(|zone| zone.contains(distance, lateral))
+            .map(|zone| zone.kind)
+            .unwrap_or(if lateral.abs() <= half_road {
+                SurfaceKind::Asphalt
+            } else {
+                self.fallback
+            })
+    }
+}
+
+/// Combined contact of all four wheels with the ground.
+#[derive(Debug, Clone, Copy)]
+pub struct WheelContact {
+    pub grip: f32,               // Average grip multiplier
+    pub rolling_resistance: f32, // Average rolling resistance multiplier
+    pub braking: f32,            // Braking force multiplier (track conditions)
+    pub wheels_on_track: usize,  // Number of wheels on asphalt or kerbs
+}
+
+impl Default for WheelContact {
+    fn default() -> Self {
+        Self {
+            grip: 1.0,
+            rolling_resistance: 1.0,
+            braking: 1.0,
+            wheels_on_track: 4,
+        }
+    }
+}
+
+impl WheelContact {
+    /// Combines the surfaces under each wheel into a single contact.
+    pub fn from_surfaces(surfaces: &[SurfaceKind]) -> Self {
+        if surfaces.is_empty() {
+            return Self::default();
+        }
+        let count = surfaces.len() as f32;
+        Self {
+            grip: surfaces.iter().map(|s| s.grip()).sum::<f32>() / count,
+            rolling_resistance: surfaces.iter().map(|s| s.rolling_resistance()).sum::<f32>()
+                / count,
+            braking: 1.0,
+            wheels_on_track: surfaces.iter().filter(|s| s.is_track()).count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surfaces() -> CourseSurfaces {
+        CourseSurfaces {
+            fallback: SurfaceKind::Grass,
+            zones: vec![
+                SurfaceZone {
+                    kind: SurfaceKind::Kerb,
+                    start: 0.0,
+                    end: 100.0,
+                    left: 8.0,
+                    right: 10.0,
+                },
+                SurfaceZone {
+                    kind: SurfaceKind::Gravel,
+                    start: 0.0,
+                    end: 100.0,
+                    left: 10.0,
+                    right: 20.0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn zones_paint_over_road_and_fallback() {
+        let surfaces = surfaces();
+        assert_eq!(surfaces.surface_at(50.0, 0.0, 10.0), SurfaceKind::Asphalt);
+        assert_eq!(surfaces.surface_at(50.0, 9.0, 10.0), SurfaceKind::Kerb);
+        assert_eq!(surfaces.surface_at(50.0, 15.0, 10.0), SurfaceKind::Gravel);
+        assert_eq!(surfaces.surface_at(50.0, -15.0, 10.0), SurfaceKind::Grass);
+        assert_eq!(surfaces.surface_at(150.0, 0.0, 10.0), SurfaceKind::Asphalt);
+    }
+
+    #[test]
+    fn wheel_contact_averages_surfaces() {
+        let contact = WheelContact::from_surfaces(&[
+            SurfaceKind::Asphalt,
+            SurfaceKind::Asphalt,
+            SurfaceKind::Grass,
+            SurfaceKind::Grass,
+        ]);
+        assert_eq!(contact.wheels_on_track, 2);
+        assert!(contact.grip < SurfaceKind::Asphalt.grip());
+        assert!(contact.grip > SurfaceKind::Grass.grip());
+    }
+}
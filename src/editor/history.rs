@@ -0,0 +1,90 @@
+/// Most edits kept for undoing. Older ones are dropped.
+const MAX_UNDO_STEPS: usize = 200;
+
+/// Undo/redo stacks of whole-document snapshots. Courses are small, so keeping full
+/// copies is simpler than recording every kind of edit and its inverse.
+#[derive(Debug, Clone)]
+pub struct EditHistory<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> Default for EditHistory<T> {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl<T> EditHistory<T> {
+    /// Records the document as it was before an edit. Redoing past it is no longer possible.
+    pub fn record(&mut self, before: T) {
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    /// Steps back one edit: returns the previous document and keeps `current` for redo.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Steps forward one undone edit: returns that document and keeps `current` for undo.
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_walk_through_edits() {
+        let mut history = EditHistory::default();
+        let mut document = 1;
+        for next in [2, 3] {
+            history.record(document);
+            document = next;
+        }
+
+        document = history.undo(document).unwrap();
+        assert_eq!(document, 2);
+        document = history.undo(document).unwrap();
+        assert_eq!(document, 1);
+        assert!(history.undo(document).is_none());
+
+        document = history.redo(document).unwrap();
+        assert_eq!(document, 2);
+
+        // A new edit drops what was undone
+        history.record(document);
+        document = 4;
+        assert!(history.clone().redo(document).is_none());
+        assert_eq!(history.undo(document), Some(2));
+    }
+
+    #[test]
+    fn oldest_edits_are_dropped() {
+        let mut history = EditHistory::default();
+        for step in 0..MAX_UNDO_STEPS + 10 {
+            history.record(step);
+        }
+        let mut current = usize::MAX;
+        let mut steps = 0;
+        while let Some(previous) = history.undo(current) {
+            current = previous;
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_UNDO_STEPS);
+        assert_eq!(current, 10);
+    }
+}
@@ -0,0 +1,846 @@
+// Track Editor Module Definition
+// This module lets designers build courses in the game: shape the centreline in 3D, set
+// widths, banking and surfaces, place the timing gates, grid and finish, test-drive the
+// result and save it as a course file.
+pub mod history;
+
+use crate::course::Course;
+use crate::course::file::{COURSE_FORMAT_VERSION, ControlPointDef, CourseFile, file_stem};
+use crate::course::generator::GeneratorParams;
+use crate::course::loader::SelectedCourse;
+use crate::course::spline::CentreLine;
+use crate::course::surface::{CourseSurfaces, SurfaceKind, SurfaceZone};
+use crate::course::{GridSlot, STANDARD_AIR_DENSITY, STANDARD_AMBIENT_TEMPERATURE, StartGrid};
+use crate::states::{AppState, GameMode};
+use crate::weather::Weather;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::input::mouse::AccumulatedMouseScroll;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use history::EditHistory;
+use std::path::{Path, PathBuf};
+
+/// Folder (inside `assets/`) that edited courses are saved to.
+const SAVE_FOLDER: &str = "courses";
+/// How close to a control point the cursor must be to pick it (pixels).
+const PICK_RADIUS: f32 = 15.0;
+/// Existing checkpoints this close to a control point are toggled off instead of adding one (m).
+const CHECKPOINT_SNAP: f32 = 5.0;
+/// Spacing of the samples drawn along the track (m).
+const DRAW_STEP: f32 = 10.0;
+/// Laps given to a course when it's turned into a circuit.
+const DEFAULT_LAPS: u32 = 3;
+
+const ELEVATION_STEP: f32 = 1.0;
+const WIDTH_STEP: f32 = 1.0;
+const BANKING_STEP: f32 = 0.02;
+
+/// Marker for the editor's UI.
+#[derive(Component)]
+struct EditorUi;
+
+/// Marker for the text describing the course and the selection.
+#[derive(Component)]
+struct EditorStatusText;
+
+/// Set while a course from the editor is being test-driven, so the run returns to the
+/// editor afterwards.
+#[derive(Resource, Default)]
+pub struct TestDrive(pub bool);
+
+/// The course being edited. It outlives the editor screen so a test drive comes back
+/// to the same document, history included.
+#[derive(Resource, Default)]
+struct EditorDocument {
+    file: Option<CourseFile>,
+    path: Option<PathBuf>, // Course file it was opened from or saved to, inside `assets/`
+    history: EditHistory<CourseFile>,
+    centreline: Option<CentreLine>, // Built from the control points, for drawing
+    problem: Option<String>,        // Why the course isn't valid yet
+    selected: Option<usize>,        // Selected control point
+    dragging: bool,
+    drag_before: Option<CourseFile>, // Course before the drag, recorded once it moves
+    unsaved: bool,
+    leave_pressed: bool, // Escape pressed once with unsaved changes
+    message: String,     // Result of the last save or test drive
+    test_handle: Option<Handle<Course>>, // Course built for the last test drive
+}
+
+impl EditorDocument {
+    fn file(&self) -> &CourseFile {
+        self.file
+            .as_ref()
+            .expect("the editor always has a course open")
+    }
+
+    fn file_mut(&mut self) -> &mut CourseFile {
+        self.file
+            .as_mut()
+            .expect("the editor always has a course open")
+    }
+
+    /// Starts editing a course, dropping the previous document and its history.
+    fn open(&mut self, file: CourseFile, path: Option<PathBuf>) {
+        *self = Self {
+            file: Some(file),
+            path,
+            ..default()
+        };
+        self.rebuild();
+    }
+
+    /// Applies an edit as one undo step.
+    fn edit(&mut self, change: impl FnOnce(&mut CourseFile)) {
+        let before = self.file().clone();
+        self.history.record(before);
+        change(self.file_mut());
+        self.changed();
+    }
+
+    fn undo(&mut self) {
+        let current = self.file().clone();
+        if let Some(previous) = self.history.undo(current) {
+            self.file = Some(previous);
+            self.changed();
+        }
+    }
+
+    fn redo(&mut self) {
+        let current = self.file().clone();
+        if let Some(next) = self.history.redo(current) {
+            self.file = Some(next);
+            self.changed();
+        }
+    }
+
+    fn changed(&mut self) {
+        self.unsaved = true;
+        self.leave_pressed = false;
+        let count = self.file().centreline.len();
+        self.selected = self.selected.filter(|index| *index < count);
+        self.rebuild();
+    }
+
+    /// Rebuilds the drawn centreline and checks the whole course.
+    fn rebuild(&mut self) {
+        let file = self.file();
+        let centreline = file.centreline().ok();
+        let problem = file
+            .clone()
+            .into_course()
+            .err()
+            .map(|error| error.to_string());
+        // Keep drawing the last good centreline while the control points are invalid
+        if centreline.is_some() {
+            self.centreline = centreline;
+        }
+        self.problem = problem;
+    }
+
+    /// Distance of a control point along the centreline (m).
+    fn point_distance(&self, index: usize) -> Option<f32> {
+        let def = self.file().centreline.get(index)?;
+        if index == 0 {
+            return Some(0.0);
+        }
+        let centreline = self.centreline.as_ref()?;
+        Some(centreline.project(point_position(def)).distance)
+    }
+
+    /// File the course is saved to, relative to `assets/`.
+    fn save_path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| {
+            Path::new(SAVE_FOLDER).join(format!("{}.course.ron", file_stem(&self.file().name)))
+        })
+    }
+}
+
+/// Orbit camera looking down at the course.
+#[derive(Resource)]
+struct EditorCamera {
+    focus: Vec3,   // Point the camera looks at
+    distance: f32, // Distance from the focus (m)
+    yaw: f32,      // Rotation around the vertical axis (radians)
+}
+
+impl EditorCamera {
+    /// Camera looking at the whole of the course from above.
+    fn framing(file: &CourseFile) -> Self {
+        let positions: Vec<Vec3> = file.centreline.iter().map(point_position).collect();
+        let min = positions.iter().fold(Vec3::MAX, |acc, p| acc.min(*p));
+        let max = positions.iter().fold(Vec3::MIN, |acc, p| acc.max(*p));
+        Self {
+            focus: (min + max) / 2.0,
+            distance: ((max - min).length() * 0.8).clamp(100.0, 8000.0),
+            yaw: 0.0,
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        let rotation = Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-1.0);
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance)
+            .looking_at(self.focus, Vec3::Y)
+    }
+}
+
+/// Plugin for `AppState::TrackEditor`.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorDocument>()
+            .init_resource::<TestDrive>()
+            .add_systems(OnEnter(AppState::TrackEditor), setup_editor)
+            .add_systems(OnExit(AppState::TrackEditor), cleanup_editor)
+            .add_systems(
+                Update,
+                (
+                    editor_camera_system,
+                    editor_pointer_system,
+                    editor_keyboard_system,
+                    editor_status_system,
+                    draw_course_system,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::TrackEditor)),
+            );
+    }
+}
+
+/// Opens the course picked on the course select screen (or a new one), unless the editor
+/// is coming back from a test drive, and builds the editor's UI.
+fn setup_editor(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    selected: Res<SelectedCourse>,
+    mut document: ResMut<EditorDocument>,
+    mut test_drive: ResMut<TestDrive>,
+) {
+    let returning = std::mem::take(&mut test_drive.0)
+        || (document.file.is_some() && selected.0.is_some() && selected.0 == document.test_handle);
+    if !returning {
+        let (file, path) = selected
+            .0
+            .as_ref()
+            .and_then(|handle| asset_server.get_path(handle.id()))
+            .and_then(|path| open_course_file(path.path()))
+            .unwrap_or_else(|| (new_course(), None));
+        info!("Editing course {}", file.name);
+        document.open(file, path);
+    }
+    commands.insert_resource(EditorCamera::framing(document.file()));
+
+    let font = asset_server.load("fonts/NotoSansJP-Bold.ttf");
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            EditorUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                EditorStatusText,
+            ));
+            parent.spawn((
+                Text::new(
+                    "Click: select / drag point | Right click: add point | Del: remove point\n\
+                     Up/Down: elevation | [ ]: width | , .: banking | V: run-off surface | F: fallback surface\n\
+                     C: checkpoint | G: start grid | X: finish | L: circuit / sprint\n\
+                     WASD: pan | Q/E: rotate | Wheel: zoom | Ctrl+Z/Y: undo/redo | Ctrl+S: save\n\
+                     Enter: test drive | Esc: leave",
+                ),
+                TextFont {
+                    font,
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.75, 0.75, 0.75)),
+            ));
+        });
+}
+
+fn cleanup_editor(mut commands: Commands, query: Query<Entity, With<EditorUi>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<EditorCamera>();
+}
+
+/// Reads the description behind a loaded course: a course file as written, or the
+/// layout a generator file produces (saved as a new course file).
+fn open_course_file(path: &Path) -> Option<(CourseFile, Option<PathBuf>)> {
+    let name = path.to_string_lossy();
+    let bytes = match std::fs::read(assets_dir().join(path)) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!("Could not open {} in the editor: {}", name, error);
+            return None;
+        }
+    };
+    let result = if name.ends_with(".course.ron") {
+        CourseFile::read(&bytes).map(|file| (file, Some(path.to_path_buf())))
+    } else {
+        ron::de::from_bytes::<GeneratorParams>(&bytes)
+            .map_err(Into::into)
+            .and_then(|params| params.course_file())
+            .map(|file| (file, None))
+    };
+    result
+        .inspect_err(|error| warn!("Could not open {} in the editor: {}", name, error))
+        .ok()
+}
+
+/// Short straight sprint to start a new course from.
+fn new_course() -> CourseFile {
+    let width = 20.0;
+    CourseFile {
+        version: COURSE_FORMAT_VERSION,
+        name: "New Course".to_string(),
+        weather: Weather::Dry,
+        gravity: 9.81,
+        air_density: STANDARD_AIR_DENSITY,
+        ambient_temperature: STANDARD_AMBIENT_TEMPERATURE,
+        runoff_width: 8.0,
+        centreline: (0..4)
+            .map(|i| ControlPointDef {
+                x: 0.0,
+                z: -200.0 * i as f32,
+                elevation: 0.0,
+                width,
+                banking: 0.0,
+            })
+            .collect(),
+        surfaces: CourseSurfaces {
+            fallback: SurfaceKind::Grass,
+            zones: Vec::new(),
+        },
+        checkpoints: vec![300.0],
+        start_grid: StartGrid {
+            distance: 50.0,
+            slots: vec![GridSlot {
+                lateral: 0.0,
+                back: 0.0,
+            }],
+        },
+        finish: 550.0,
+        laps: None,
+        scenery: Vec::new(),
+    }
+}
+
+fn assets_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets")
+}
+
+fn point_position(def: &ControlPointDef) -> Vec3 {
+    Vec3::new(def.x, def.elevation, def.z)
+}
+
+/// WASD pans, Q/E rotate and the mouse wheel zooms.
+fn editor_camera_system(
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    scroll: Res<AccumulatedMouseScroll>,
+    mut camera: ResMut<EditorCamera>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let dt = time.delta_secs();
+    // Panning speed scales with the zoom so it feels the same at any height
+    let pan = camera.distance * dt;
+    let forward = Quat::from_rotation_y(camera.yaw) * Vec3::NEG_Z;
+    let right = Quat::from_rotation_y(camera.yaw) * Vec3::X;
+    let mut movement = Vec3::ZERO;
+    if input.pressed(KeyCode::KeyW) {
+        movement += forward;
+    }
+    if input.pressed(KeyCode::KeyS) {
+        movement -= forward;
+    }
+    if input.pressed(KeyCode::KeyD) {
+        movement += right;
+    }
+    if input.pressed(KeyCode::KeyA) {
+        movement -= right;
+    }
+    camera.focus += movement * pan;
+    if input.pressed(KeyCode::KeyQ) {
+        camera.yaw += dt;
+    }
+    if input.pressed(KeyCode::KeyE) {
+        camera.yaw -= dt;
+    }
+    camera.distance = (camera.distance * (1.0 - scroll.delta.y * 0.1)).clamp(20.0, 10000.0);
+
+    if let Some(mut transform) = camera_query.iter_mut().next() {
+        *transform = camera.transform();
+    }
+}
+
+/// Picks, drags and adds control points with the mouse.
+fn editor_pointer_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut document: ResMut<EditorDocument>,
+) {
+    if !mouse.pressed(MouseButton::Left) {
+        document.dragging = false;
+    }
+    let Some(cursor) = window
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some((camera, camera_transform)) = camera_query.iter().next() else {
+        return;
+    };
+    // Where the cursor points on the horizontal plane at a height
+    let ground_at = |height: f32| {
+        let ray = camera.viewport_to_world(camera_transform, cursor).ok()?;
+        let hit = ray.intersect_plane(Vec3::Y * height, InfinitePlane3d::new(Vec3::Y))?;
+        Some(ray.get_point(hit))
+    };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let picked = document
+            .file()
+            .centreline
+            .iter()
+            .enumerate()
+            .filter_map(|(index, def)| {
+                let screen = camera
+                    .world_to_viewport(camera_transform, point_position(def))
+                    .ok()?;
+                Some((index, screen.distance(cursor)))
+            })
+            .filter(|(_, distance)| *distance <= PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+        document.selected = picked;
+        if picked.is_some() {
+            document.dragging = true;
+            document.drag_before = Some(document.file().clone());
+        }
+    } else if document.dragging
+        && let Some(index) = document.selected
+    {
+        let point = document.file().centreline[index];
+        if let Some(target) = ground_at(point.elevation)
+            && target.xz() != Vec2::new(point.x, point.z)
+        {
+            // The whole drag is a single undo step
+            if let Some(before) = document.drag_before.take() {
+                document.history.record(before);
+            }
+            let file = document.file_mut();
+            file.centreline[index].x = target.x;
+            file.centreline[index].z = target.z;
+            document.changed();
+        }
+    }
+
+    if mouse.just_pressed(MouseButton::Right) {
+        let file = document.file();
+        let after = document.selected.unwrap_or(file.centreline.len() - 1);
+        let template = file.centreline[after];
+        let Some(target) = ground_at(template.elevation) else {
+            return;
+        };
+        document.edit(|file| {
+            file.centreline.insert(
+                after + 1,
+                ControlPointDef {
+                    x: target.x,
+                    z: target.z,
+                    ..template
+                },
+            );
+        });
+        document.selected = Some(after + 1);
+    }
+}
+
+/// Keyboard edits of the selected control point and of the whole course, plus undo/redo,
+/// saving, test driving and leaving the editor.
+#[allow(clippy::too_many_arguments)]
+fn editor_keyboard_system(
+    input: Res<ButtonInput<KeyCode>>,
+    mut document: ResMut<EditorDocument>,
+    mut courses: ResMut<Assets<Course>>,
+    mut selected_course: ResMut<SelectedCourse>,
+    mut test_drive: ResMut<TestDrive>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let ctrl = input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if ctrl {
+        if (input.just_pressed(KeyCode::KeyZ) && shift) || input.just_pressed(KeyCode::KeyY) {
+            document.redo();
+        } else if input.just_pressed(KeyCode::KeyZ) {
+            document.undo();
+        }
+        if input.just_pressed(KeyCode::KeyS) {
+            save_course(&mut document);
+        }
+        return;
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        if document.unsaved && !document.leave_pressed {
+            document.leave_pressed = true;
+            document.message = "Unsaved changes - press Esc again to leave".to_string();
+        } else {
+            next_state.set(AppState::ModeSelect);
+        }
+        return;
+    }
+
+    if input.just_pressed(KeyCode::Enter) {
+        match document.file().clone().into_course() {
+            Ok(course) => {
+                let handle = courses.add(course);
+                selected_course.0 = Some(handle.clone());
+                document.test_handle = Some(handle);
+                document.message.clear();
+                test_drive.0 = true;
+                *mode = GameMode::TimeAttack;
+                next_state.set(AppState::TimeAttackGame);
+            }
+            Err(error) => document.message = format!("Can't test drive: {}", error),
+        }
+        return;
+    }
+
+    // --- Whole course ---
+    if input.just_pressed(KeyCode::KeyF) {
+        document.edit(|file| {
+            file.surfaces.fallback = match file.surfaces.fallback {
+                SurfaceKind::Grass => SurfaceKind::Gravel,
+                SurfaceKind::Gravel => SurfaceKind::Asphalt,
+                _ => SurfaceKind::Grass,
+            };
+        });
+    }
+    if input.just_pressed(KeyCode::KeyL) {
+        document.edit(|file| {
+            file.laps = match file.laps {
+                Some(_) => None,
+                None => Some(DEFAULT_LAPS),
+            };
+        });
+    }
+
+    // --- Selected control point ---
+    let Some(index) = document.selected else {
+        return;
+    };
+    let point_steps = [
+        (KeyCode::ArrowUp, ELEVATION_STEP, 0.0, 0.0),
+        (KeyCode::ArrowDown, -ELEVATION_STEP, 0.0, 0.0),
+        (KeyCode::BracketRight, 0.0, WIDTH_STEP, 0.0),
+        (KeyCode::BracketLeft, 0.0, -WIDTH_STEP, 0.0),
+        (KeyCode::Period, 0.0, 0.0, BANKING_STEP),
+        (KeyCode::Comma, 0.0, 0.0, -BANKING_STEP),
+    ];
+    for (key, elevation, width, banking) in point_steps {
+        if input.just_pressed(key) {
+            document.edit(|file| {
+                let point = &mut file.centreline[index];
+                point.elevation += elevation;
+                point.width = (point.width + width).max(WIDTH_STEP);
+                point.banking += banking;
+            });
+        }
+    }
+
+    if input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        let minimum = if document.file().laps.is_some() { 3 } else { 2 };
+        if document.file().centreline.len() > minimum {
+            document.edit(|file| {
+                file.centreline.remove(index);
+            });
+            document.selected = index.checked_sub(1);
+        }
+        return;
+    }
+
+    let Some(distance) = document.point_distance(index) else {
+        return;
+    };
+    if input.just_pressed(KeyCode::KeyC) {
+        document.edit(|file| {
+            let existing = file
+                .checkpoints
+                .iter()
+                .position(|checkpoint| (checkpoint - distance).abs() <= CHECKPOINT_SNAP);
+            match existing {
+                Some(existing) => {
+                    file.checkpoints.remove(existing);
+                }
+                None => {
+                    let at = file.checkpoints.partition_point(|c| *c < distance);
+                    file.checkpoints.insert(at, distance);
+                }
+            }
+        });
+    }
+    if input.just_pressed(KeyCode::KeyG) {
+        document.edit(|file| file.start_grid.distance = distance);
+    }
+    if input.just_pressed(KeyCode::KeyX) {
+        document.edit(|file| file.finish = distance);
+    }
+    if input.just_pressed(KeyCode::KeyV) {
+        let next = document.point_distance(index + 1).or_else(|| {
+            let centreline = document.centreline.as_ref()?;
+            centreline.is_closed().then(|| centreline.length())
+        });
+        if let Some(end) = next {
+            document.edit(|file| cycle_runoff_surface(file, index, distance, end));
+        }
+    }
+}
+
+/// Cycles the run-off beside the section from control point `index` (at `start`) to the
+/// next one (at `end`): no zone, then gravel, grass and paved run-off on both sides.
+fn cycle_runoff_surface(file: &mut CourseFile, index: usize, start: f32, end: f32) {
+    let next_index = (index + 1) % file.centreline.len();
+    let half_road = file.centreline[index]
+        .width
+        .max(file.centreline[next_index].width)
+        / 2.0;
+    let outer = half_road + file.runoff_width;
+    let in_section = |zone: &SurfaceZone| zone.start == start && zone.end == end;
+
+    let current = file
+        .surfaces
+        .zones
+        .iter()
+        .find(|zone| in_section(zone))
+        .map(|zone| zone.kind);
+    file.surfaces.zones.retain(|zone| !in_section(zone));
+    let next = match current {
+        None => Some(SurfaceKind::Gravel),
+        Some(SurfaceKind::Gravel) => Some(SurfaceKind::Grass),
+        Some(SurfaceKind::Grass) => Some(SurfaceKind::Asphalt),
+        Some(_) => None,
+    };
+    if let Some(kind) = next {
+        for (left, right) in [(-outer, -half_road), (half_road, outer)] {
+            file.surfaces.zones.push(SurfaceZone {
+                kind,
+                start,
+                end,
+                left,
+                right,
+            });
+        }
+    }
+}
+
+/// Validates the course and writes it to its course file.
+fn save_course(document: &mut EditorDocument) {
+    if let Some(problem) = &document.problem {
+        document.message = format!("Can't save: {}", problem);
+        return;
+    }
+    let path = document.save_path();
+    let result = document
+        .file()
+        .to_ron()
+        .map_err(|error| error.to_string())
+        .and_then(|text| {
+            std::fs::write(assets_dir().join(&path), text).map_err(|error| error.to_string())
+        });
+    match result {
+        Ok(()) => {
+            info!("Saved course to {}", path.display());
+            document.message = format!("Saved to assets/{}", path.display());
+            document.path = Some(path);
+            document.unsaved = false;
+        }
+        Err(error) => {
+            warn!("Could not save {}: {}", path.display(), error);
+            document.message = format!("Could not save: {}", error);
+        }
+    }
+}
+
+fn editor_status_system(
+    document: Res<EditorDocument>,
+    mut text_query: Query<&mut Text, With<EditorStatusText>>,
+) {
+    let Some(mut text) = text_query.iter_mut().next() else {
+        return;
+    };
+    let file = document.file();
+    let kind = match file.laps {
+        Some(laps) => format!("Circuit, {} laps", laps),
+        None => "Sprint".to_string(),
+    };
+    let length = document
+        .centreline
+        .as_ref()
+        .map(|centreline| centreline.length())
+        .unwrap_or_default();
+    let mut status = format!(
+        "TRACK EDITOR - {}{}  ({})\n{} | {:.0} m | {} control points | Run-off: {:?}",
+        file.name,
+        if document.unsaved { " *" } else { "" },
+        document
+            .path
+            .as_ref()
+            .map(|path| format!("assets/{}", path.display()))
+            .unwrap_or_else(|| "not saved yet".to_string()),
+        kind,
+        length,
+        file.centreline.len(),
+        file.surfaces.fallback,
+    );
+    if let Some(index) = document.selected {
+        let point = &file.centreline[index];
+        status += &format!(
+            "\nPoint {}: {:.0} m along | elevation {:.1} m | width {:.1} m | banking {:.2} rad",
+            index + 1,
+            document.point_distance(index).unwrap_or_default(),
+            point.elevation,
+            point.width,
+            point.banking
+        );
+    }
+    status += &match &document.problem {
+        Some(problem) => format!("\nNot drivable yet: {}", problem),
+        None => "\nReady to drive".to_string(),
+    };
+    if !document.message.is_empty() {
+        status += &format!("\n{}", document.message);
+    }
+    text.0 = status;
+}
+
+/// Draws the course as lines: the road and run-off edges, surface zones, timing gates,
+/// grid and finish, and the control points.
+fn draw_course_system(document: Res<EditorDocument>, mut gizmos: Gizmos) {
+    gizmos.grid(
+        Isometry3d::from_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        UVec2::splat(100),
+        Vec2::splat(100.0),
+        Color::srgba(1.0, 1.0, 1.0, 0.05),
+    );
+
+    let file = document.file();
+    if let Some(centreline) = &document.centreline {
+        let length = centreline.length();
+        // Points along the track at a lateral offset, plus `edge` times the half road
+        // width so the road edges follow the local width
+        let along = |start: f32, end: f32, lateral: f32, edge: f32| {
+            let start = start.max(0.0);
+            let end = end.min(length);
+            let steps = ((end - start) / DRAW_STEP).ceil().max(1.0) as usize;
+            (0..=steps).map(move |i| {
+                let frame = centreline.frame_at(start + (end - start) * i as f32 / steps as f32);
+                frame.point(lateral + edge * frame.width / 2.0)
+            })
+        };
+        let across = |distance: f32, color: Color, gizmos: &mut Gizmos| {
+            let frame = centreline.frame_at(distance);
+            let half = frame.width / 2.0 + file.runoff_width;
+            let lift = frame.normal * 2.0;
+            gizmos.line(frame.point(-half) + lift, frame.point(half) + lift, color);
+        };
+
+        gizmos.linestrip(
+            along(0.0, length, 0.0, 0.0),
+            Color::srgba(1.0, 1.0, 1.0, 0.3),
+        );
+        for side in [-1.0, 1.0] {
+            gizmos.linestrip(along(0.0, length, 0.0, side), Color::srgb(0.6, 0.6, 0.65));
+            gizmos.linestrip(
+                along(0.0, length, side * file.runoff_width, side),
+                file.surfaces.fallback.color(),
+            );
+        }
+        for zone in &file.surfaces.zones {
+            for lateral in [zone.left, zone.right] {
+                gizmos.linestrip(along(zone.start, zone.end, lateral, 0.0), zone.kind.color());
+            }
+        }
+
+        for checkpoint in &file.checkpoints {
+            across(*checkpoint, Color::srgb(0.95, 0.8, 0.1), &mut gizmos);
+        }
+        across(
+            file.start_grid.distance,
+            Color::srgb(0.2, 0.8, 0.2),
+            &mut gizmos,
+        );
+        across(file.finish, Color::srgb(0.9, 0.2, 0.2), &mut gizmos);
+        for slot in &file.start_grid.slots {
+            let frame = centreline.frame_at(file.start_grid.distance - slot.back);
+            gizmos.cross(
+                Isometry3d::from_translation(frame.point(slot.lateral) + frame.normal),
+                2.0,
+                Color::srgb(0.2, 0.8, 0.2),
+            );
+        }
+    }
+
+    for (index, def) in file.centreline.iter().enumerate() {
+        let color = if Some(index) == document.selected {
+            Color::srgb(1.0, 0.85, 0.1)
+        } else {
+            Color::srgb(0.3, 0.6, 1.0)
+        };
+        gizmos.sphere(
+            Isometry3d::from_translation(point_position(def)),
+            3.0,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_course_is_drivable() {
+        assert!(new_course().into_course().is_ok());
+    }
+
+    #[test]
+    fn runoff_surface_cycles_back_to_nothing() {
+        let mut file = new_course();
+        let kinds: Vec<Option<SurfaceKind>> = (0..4)
+            .map(|_| {
+                cycle_runoff_surface(&mut file, 1, 200.0, 400.0);
+                file.surfaces.zones.first().map(|zone| zone.kind)
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(SurfaceKind::Gravel),
+                Some(SurfaceKind::Grass),
+                Some(SurfaceKind::Asphalt),
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::resources::{GameOverCause, Penalty, TrackIncident};
+use bevy::prelude::*;
+
+// Game events, sent as Bevy messages so the HUD, audio, replays, stats and tests can all
+// follow a run without reaching into the systems that run it. Events about one car carry
+// its entity.
+
+/// The start lights went out: the race clock is running.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct RaceStarted;
+
+/// A car drove through its next timing gate.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct CheckpointPassed {
+    pub car: Entity,
+    pub gate: usize, // Index of the gate on the lap (the last one is the finish line)
+    pub time: f32,   // Run time of the car when it passed (seconds)
+}
+
+/// A car completed a lap.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct LapCompleted {
+    pub car: Entity,
+    pub lap: u32,  // Number of the lap (1-based)
+    pub time: f32, // Lap time (seconds)
+}
+
+/// A car was given a time penalty. A course out is sent once the car is back on the track
+/// or its run ends, with the whole time it spent off it.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct PenaltyApplied {
+    pub car: Entity,
+    pub penalty: Penalty,
+}
+
+/// A car broke the track limits in a way that adds no time: drove the wrong way, or cut
+/// a corner when cuts only warn or cost the lap.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct TrackLimitsBreached {
+    pub car: Entity,
+    pub incident: TrackIncident,
+}
+
+/// A car shifted gear.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct GearChanged {
+    pub car: Entity,
+    pub gear: i32,
+}
+
+/// A car opened or closed its DRS.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct DrsToggled {
+    pub car: Entity,
+    pub enabled: bool,
+}
+
+/// A car's run is over, for whatever reason. Whether that ends the session is up to the
+/// game, which then sends `GameOver`.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct RunEnded {
+    pub car: Entity,
+    pub cause: GameOverCause,
+}
+
+/// The session is over: the player's run ended.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct GameOver {
+    pub cause: GameOverCause,
+}
+
+/// Plugin that registers the game events and logs them.
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<RaceStarted>()
+            .add_message::<CheckpointPassed>()
+            .add_message::<LapCompleted>()
+            .add_message::<PenaltyApplied>()
+            .add_message::<TrackLimitsBreached>()
+            .add_message::<GearChanged>()
+            .add_message::<DrsToggled>()
+            .add_message::<RunEnded>()
+            .add_message::<GameOver>()
+            .add_systems(PostUpdate, log_game_events);
+    }
+}
+
+/// Writes the events of a run to the log, naming the car they happened to.
+#[allow(clippy::too_many_arguments)]
+fn log_game_events(
+    mut started: MessageReader<RaceStarted>,
+    mut checkpoints: MessageReader<CheckpointPassed>,
+    mut laps: MessageReader<LapCompleted>,
+    mut penalties: MessageReader<PenaltyApplied>,
+    mut breaches: MessageReader<TrackLimitsBreached>,
+    mut gears: MessageReader<GearChanged>,
+    mut drs: MessageReader<DrsToggled>,
+    mut runs: MessageReader<RunEnded>,
+    mut game_over: MessageReader<GameOver>,
+    names: Query<&Name>,
+) {
+    let name = |car: Entity| names.get(car).map_or("Car".to_string(), Name::to_string);
+    for _ in started.read() {
+        info!("Race started");
+    }
+    for event in checkpoints.read() {
+        debug!(
+            "{}: gate {} at {:.2}s",
+            name(event.car),
+            event.gate + 1,
+            event.time
+        );
+    }
+    for event in laps.read() {
+        info!(
+            "{}: lap {} completed in {:.2}s",
+            name(event.car),
+            event.lap,
+            event.time
+        );
+    }
+    for event in penalties.read() {
+        info!("{}: {}", name(event.car), event.penalty.summary());
+    }
+    for event in breaches.read() {
+        info!("{}: {}", name(event.car), event.incident.summary());
+    }
+    for event in gears.read() {
+        debug!("{}: gear {}", name(event.car), event.gear);
+    }
+    for event in drs.read() {
+        debug!(
+            "{}: DRS {}",
+            name(event.car),
+            if event.enabled { "open" } else { "closed" }
+        );
+    }
+    for event in runs.read() {
+        info!("{}: run over ({:?})", name(event.car), event.cause);
+    }
+    for event in game_over.read() {
+        info!("Run finished: {:?}", event.cause);
+    }
+}
@@ -1,8 +1,20 @@
 use crate::car::components::*;
+use crate::car::{CarSpec, car_bundle};
+use crate::course::Course;
+use crate::course::loader::{CourseLibrary, SelectedCourse};
+use crate::course::mesh;
+use crate::events::{GameOver, PenaltyApplied, RunEnded};
+use crate::ghost::{GhostSelection, spawn_ghost};
+use crate::minimap::{MinimapMarker, PLAYER_MARKER_COLOR, spawn_minimap};
+use crate::race::spawn_race;
+use crate::records::PersonalBests;
 use crate::resources::*;
-use crate::states::AppState;
+use crate::scenery::{GraphicsQuality, spawn_scenery};
+use crate::states::{AppState, GameMode, InGame, OnTrack, Racing};
+use crate::weather::{TrackConditions, WeatherSelection};
 use bevy::prelude::*;
 use std::process::Command;
+use std::time::Instant;
 
 /// Component used to mark entities that belong to the game world (level geometry, lights, etc.)
 /// for easy cleanup when leaving the game state.
@@ -23,28 +35,38 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameSession>()
-            .add_systems(OnEnter(AppState::TimeAttackGame), setup_game)
-            .add_systems(OnExit(AppState::TimeAttackGame), cleanup_game)
+            .add_systems(OnEnter(InGame), setup_game)
+            // Also clears what the replay viewer puts on track
+            .add_systems(OnExit(OnTrack), cleanup_game)
             .add_systems(
                 Update,
-                (game_logic_system, hud_update_system, update_temps)
-                    .run_if(in_state(AppState::TimeAttackGame)),
+                (
+                    (
+                        game_logic_system.run_if(in_state(Racing)),
+                        end_of_run_system,
+                    )
+                        .chain()
+                        .run_if(in_state(InGame)),
+                    // The sensors are still read while paused
+                    (hud_update_system, update_temps).run_if(in_state(InGame)),
+                ),
             )
-            .add_systems(
-                PostUpdate,
-                camera_follow.run_if(in_state(AppState::TimeAttackGame)),
-            );
+            .add_systems(PostUpdate, camera_follow.run_if(in_state(InGame)));
     }
 }
 
 /// System that updates PC hardware temperatures and usage every 1 second.
 /// It uses a combination of 'sysinfo' for general data and 'nvidia-smi' as a fallback for GPU data.
+/// The readings go to every car driven by this PC; all cars then take their engine
+/// temperature from their own hardware.
 fn update_temps(
     time: Res<Time>,
     mut timer: Local<f32>,
     mut monitor: ResMut<PcMonitor>,
     mut pc_status: ResMut<PcStatus>,
-    mut session: ResMut<GameSession>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut cars: Query<(&mut PcStatus, &mut CarState, &HardwareSource)>,
 ) {
     if session.is_game_over {
         return;
@@ -63,16 +85,13 @@ fn update_temps(
 
         for component in &components {
             let label = component.label().to_lowercase();
-            if let Some(t) = component.temperature() {
-                if t > 0.0
-                    && (label.contains("cpu")
-                        || label.contains("core")
-                        || label.contains("package"))
-                {
-                    cpu_temp_sum += t;
-                    cpu_count += 1;
-                    found_cpu_temp = true;
-                }
+            if let Some(t) = component.temperature()
+                && t > 0.0
+                && (label.contains("cpu") || label.contains("core") || label.contains("package"))
+            {
+                cpu_temp_sum += t;
+                cpu_count += 1;
+                found_cpu_temp = true;
             }
         }
 
@@ -114,113 +133,211 @@ fn update_temps(
         }
 
         pc_status.sensor_error = !found_cpu_temp && pc_status.gpu_temp == 0.0;
-        session.current_temp = pc_status.cpu_temp + pc_status.gpu_temp;
+        for (mut hardware, mut state, source) in &mut cars {
+            if *source == HardwareSource::Local {
+                *hardware = pc_status.clone();
+            }
+            // Hot courses cool the engine less, cold ones more
+            state.temp = hardware.cpu_temp + hardware.gpu_temp + course.engine_heat();
+        }
     }
 }
 
 // Redefining build to be cleaner
 
-/// Startup system that initializes the Time Attack course, player car, and UI.
+/// Startup system that initializes the course, player car, and UI (and the opponents in a
+/// race, or the ghost in a time attack).
+#[allow(clippy::too_many_arguments)]
 fn setup_game(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
     asset_server: Res<AssetServer>,
     mut session: ResMut<GameSession>,
+    base_car: Res<BaseCarStatus>,
     car_status: Res<CarStatus>,
+    pc_status: Res<PcStatus>,
+    weather_selection: Res<WeatherSelection>,
+    quality: Res<GraphicsQuality>,
+    library: Res<CourseLibrary>,
+    selected: Res<SelectedCourse>,
+    courses: Res<Assets<Course>>,
+    // What's raced against: opponents, or a ghost in a time attack
+    (mode, ghost_selection): (Res<GameMode>, Res<GhostSelection>),
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
+    let Some(course) = selected
+        .0
+        .as_ref()
+        .or(library.courses.first())
+        .and_then(|handle| courses.get(handle))
+        .cloned()
+    else {
+        error!("No valid course found in assets/courses, returning to course select");
+        next_state.set(AppState::CourseSelect);
+        return;
+    };
+
     // Reset Session state for a new run
-    session.play_time = 0.0;
-    session.current_speed = 0.0;
-    session.current_fuel = car_status.fuel_capacity; // Start with full tank
-    session.current_gear = 1;
-    session.current_temp = 60.0;
-    session.drs_enabled = false;
-    session.distance_traveled = 0.0;
-    session.is_game_over = false;
-
-    // --- Procedural Course Generation ---
-    // The course is made of repeated segments to simulate a long track.
+    *session = GameSession {
+        course_name: course.name.clone(),
+        total_laps: course.laps,
+        run: CarProgress::at(course.start_grid.distance),
+        ..default()
+    };
+
+    // --- Course Geometry ---
+    // The visible road, surfaces and barriers are built in merged chunks around the
+    // player by `CourseStreamingPlugin`. Only the barrier colliders are spawned here,
+    // as invisible boxes laid along the centreline, so collisions work anywhere on
+    // the course regardless of what is currently built.
+    let build_started = Instant::now();
     let segment_length = 10.0;
-    let total_course_visual = 10000.0;
-    let num_segments = (total_course_visual / segment_length) as i32;
-    let road_width = 40.0;
-
-    let road_material = materials.add(Color::srgb(0.2, 0.2, 0.25));
-    let border_material = materials.add(Color::srgb(0.8, 0.8, 0.8));
-
+    let track_start = mesh::geometry_start(&course);
+    let num_segments = ((course.length() - track_start) / segment_length).ceil() as i32;
+    let barrier_offset = course.half_width() + 1.0;
+    let barrier_size = Vec3::new(2.0, 1.5, segment_length);
     for i in 0..num_segments {
-        let z_pos = -(i as f32) * segment_length;
-        // Simple height variation using sine wave
-        // With segment_length=10, distance = i * 10.
-        // We want distance/1000. So (i * 10) / 1000 = i * 0.01
-        let y_pos = (i as f32 * 0.01).sin() * 5.0; // Hills synchronized with car/systems.rs
+        let seg_start = track_start + i as f32 * segment_length;
+        let seg_end = seg_start + segment_length;
+        for side in [-1.0, 1.0] {
+            commands.spawn((
+                track_piece(&course, seg_start, seg_end, side * barrier_offset, 0.25),
+                Obstacle::new(barrier_size),
+                GameWorld,
+            ));
+        }
+    }
 
-        // Road Segment
+    // Back wall behind the start line so the car can't leave the course backwards.
+    // Circuits loop around instead.
+    if !course.is_circuit() {
+        let border_material = materials.add(Color::srgb(0.8, 0.8, 0.8));
+        let back_wall_size = Vec3::new(barrier_offset * 2.0 + 2.0, 1.5, 2.0);
         commands.spawn((
-            Mesh3d(meshes.add(Cuboid::new(road_width, 1.0, segment_length))),
-            MeshMaterial3d(road_material.clone()),
-            Transform::from_xyz(0.0, y_pos - 0.5, z_pos), // -0.5 to keep surface at y_pos
+            Mesh3d(meshes.add(Cuboid::from_size(back_wall_size))),
+            MeshMaterial3d(border_material),
+            track_piece(&course, track_start - 2.0, track_start, 0.0, 0.25),
+            Obstacle::new(back_wall_size),
             GameWorld,
         ));
+    }
 
-        // Side barriers
-        commands.spawn((
-            Mesh3d(meshes.add(Cuboid::new(2.0, 1.5, segment_length))),
-            MeshMaterial3d(border_material.clone()),
-            Transform::from_xyz(road_width / 2.0 + 1.0, y_pos + 0.25, z_pos),
-            GameWorld,
-        ));
-        commands.spawn((
-            Mesh3d(meshes.add(Cuboid::new(2.0, 1.5, segment_length))),
-            MeshMaterial3d(border_material.clone()),
-            Transform::from_xyz(-(road_width / 2.0 + 1.0), y_pos + 0.25, z_pos),
-            GameWorld,
-        ));
+    // Checkpoint gates are marked by a pair of posts beside the road
+    let checkpoint_mesh = meshes.add(Cuboid::new(1.0, 8.0, 1.0));
+    let checkpoint_material = materials.add(Color::srgb(0.95, 0.8, 0.1));
+    for checkpoint in &course.checkpoints {
+        let frame = course.frame_at(*checkpoint);
+        for side in [-1.0, 1.0] {
+            commands.spawn((
+                Mesh3d(checkpoint_mesh.clone()),
+                MeshMaterial3d(checkpoint_material.clone()),
+                Transform::from_translation(
+                    frame.point(side * (frame.width / 2.0 + 3.0)) + frame.normal * 4.0,
+                )
+                .with_rotation(frame.rotation()),
+                GameWorld,
+            ));
+        }
     }
 
     // --- Finish Line ---
-    let finish_z = -session.course_length;
+    let finish = course.frame_at(course.finish);
     let goal_material = materials.add(Color::srgba(1.0, 0.2, 0.2, 0.6)); // Translucent red
+    let post_material = materials.add(Color::srgb(0.5, 0.5, 0.5));
     commands.spawn((
-        Mesh3d(meshes.add(Cuboid::new(road_width, 10.0, 1.0))),
+        Mesh3d(meshes.add(Cuboid::new(finish.width, 10.0, 1.0))),
         MeshMaterial3d(goal_material),
-        Transform::from_xyz(0.0, 5.0, finish_z),
+        Transform::from_translation(finish.position + finish.normal * 5.0)
+            .with_rotation(finish.rotation()),
         GameWorld,
     ));
     // Additional Goal post decorations
-    commands.spawn((
-        Mesh3d(meshes.add(Cuboid::new(2.0, 20.0, 2.0))),
-        MeshMaterial3d(materials.add(Color::srgb(0.5, 0.5, 0.5))),
-        Transform::from_xyz(road_width / 2.0 + 2.0, 10.0, finish_z),
-        GameWorld,
-    ));
-    commands.spawn((
-        Mesh3d(meshes.add(Cuboid::new(2.0, 20.0, 2.0))),
-        MeshMaterial3d(materials.add(Color::srgb(0.5, 0.5, 0.5))),
-        Transform::from_xyz(-(road_width / 2.0 + 2.0), 10.0, finish_z),
-        GameWorld,
-    ));
-
-    // Light
-    commands.spawn((
-        DirectionalLight::default(),
-        Transform::from_xyz(50.0, 100.0, 50.0).looking_at(Vec3::ZERO, Vec3::Y),
-        GameWorld,
-    ));
+    for side in [-1.0, 1.0] {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::new(2.0, 20.0, 2.0))),
+            MeshMaterial3d(post_material.clone()),
+            Transform::from_translation(
+                finish.point(side * (finish.width / 2.0 + 2.0)) + finish.normal * 10.0,
+            )
+            .with_rotation(finish.rotation()),
+            GameWorld,
+        ));
+    }
 
-    // Player Car (Cube)
+    // --- Scenery ---
+    spawn_scenery(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &course,
+        *quality,
+    );
+
+    // Track conditions for this run, from the player's weather pick or the course default
+    let conditions = match *weather_selection {
+        WeatherSelection::CourseDefault => TrackConditions::new(&course, course.weather, false),
+        WeatherSelection::Fixed(weather) => TrackConditions::new(&course, weather, false),
+        WeatherSelection::Hardware => TrackConditions::new(&course, course.weather, true),
+    };
+    commands.insert_resource(conditions);
+
+    // Player Car (Cube), at the back of the grid in a race
+    let spec = CarSpec {
+        base: base_car.clone(),
+        status: car_status.clone(),
+        hardware: pc_status.clone(),
+        source: HardwareSource::Local,
+    };
+    let start = match *mode {
+        GameMode::TimeAttack => {
+            spawn_ghost(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &asset_server,
+                &course,
+                *ghost_selection,
+            );
+            course.centreline_transform(course.start_grid.distance)
+        }
+        GameMode::Race => spawn_race(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &asset_server,
+            &course,
+            &spec,
+        ),
+    };
     commands.spawn((
         Mesh3d(meshes.add(Cuboid::new(2.0, 1.0, 4.0))),
         MeshMaterial3d(materials.add(Color::srgb(0.9, 0.1, 0.1))), // Red sporty car
-        Transform::from_xyz(0.0, 1.0, 0.0),                        // Start slightly up
-        Velocity::default(),
+        car_bundle(spec, start, course.project(start.translation).distance),
+        Name::new("You"),
+        MinimapMarker(PLAYER_MARKER_COLOR),
         PlayerCar,
         GameWorld,
     ));
+    info!(
+        "Set up course {} in {:.1?}",
+        course.name,
+        build_started.elapsed()
+    );
 
     // 4. Create UI Overlay (HUD)
     setup_hud(&mut commands, &asset_server);
+    spawn_minimap(&mut commands, &mut images, &course);
+    commands.insert_resource(course);
+}
+
+/// Transform of a track piece covering `start..end` along the course, centred at a
+/// lateral offset and lifted `height` off the road surface, lying along the road.
+fn track_piece(course: &Course, start: f32, end: f32, lateral: f32, height: f32) -> Transform {
+    let frame = course.frame_at((start + end) / 2.0);
+    Transform::from_translation(frame.point(lateral) + frame.normal * height)
+        .with_rotation(frame.rotation())
 }
 
 fn setup_hud(commands: &mut Commands, asset_server: &AssetServer) {
@@ -289,69 +406,130 @@ fn cleanup_game(mut commands: Commands, query: Query<Entity, With<GameWorld>>) {
 
 // physics_system and input_system moved to car/systems.rs
 
-/// Core game rule checker: Handes Victory (Distance), Failure (Fuel/Overheat), and Bounds.
+/// Accumulated raw impact damage (see `CarDamage::impact`) at which the car is wrecked.
+/// Roughly two full-speed head-on hits or many hard scrapes.
+const DAMAGE_CRASH_THRESHOLD: f32 = 1.5;
+
+/// Accumulated time penalty (seconds) beyond which the car crashes out (Specification rule 94).
+pub const PENALTY_CRASH_THRESHOLD: f32 = 30.0;
+
+/// Engine temperature (CPU + GPU, Celsius) at which the engine melts down.
+pub const OVERHEAT_TEMPERATURE: f32 = 255.0;
+
+/// Seconds a track limits incident stays on the HUD.
+const INCIDENT_DISPLAY_TIME: f32 = 3.0;
+
+/// Distance past the barriers (beyond the run-off) at which the car can only have escaped them.
+const OUT_OF_BOUNDS_MARGIN: f32 = 12.0;
+
+/// Core game rule checker: Handes Victory (Laps), Failure (Fuel/Overheat), and Crashes.
+/// The rules apply to every car; a car whose run is over is reported with `RunEnded`.
 fn game_logic_system(
     time: Res<Time>,
-    mut session: ResMut<GameSession>,
-    mut next_state: ResMut<NextState<AppState>>,
-    query: Query<&Transform, With<PlayerCar>>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut cars: Query<(Entity, &Transform, &CarState, &mut CarProgress)>,
+    mut run_ends: MessageWriter<RunEnded>,
+    mut penalties: MessageWriter<PenaltyApplied>,
 ) {
     if session.is_game_over {
         return;
     }
 
-    // Advance total game time
-    session.play_time += time.delta_secs();
+    for (car, transform, state, mut progress) in &mut cars {
+        if progress.ended.is_some() {
+            continue;
+        }
+
+        // Advance the car's run time
+        progress.time += time.delta_secs();
+
+        let Some(cause) = run_over(&course, session.total_laps, transform, state, &progress) else {
+            continue;
+        };
+        progress.ended = Some(cause);
+        // A course out still running is over with the run, and may be what ended it
+        if let Some(penalty) = progress.penalties.back_on_track() {
+            penalties.write(PenaltyApplied { car, penalty });
+        }
+        run_ends.write(RunEnded { car, cause });
+    }
+}
 
-    // Condition 1: Victory - Reached the end of the course
-    if session.distance_traveled >= session.course_length {
-        println!("Goal Reached!");
+/// The one place a session ends: when the player's run is over, whatever ended it, the
+/// run is kept for the result screen and `GameOver` is sent.
+fn end_of_run_system(
+    mut session: ResMut<GameSession>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut run_ends: MessageReader<RunEnded>,
+    mut game_over: MessageWriter<GameOver>,
+    cars: Query<&CarProgress, With<PlayerCar>>,
+) {
+    for event in run_ends.read() {
+        let Ok(progress) = cars.get(event.car) else {
+            continue;
+        };
+        if session.is_game_over {
+            continue;
+        }
+        session.run = progress.clone();
         session.is_game_over = true;
-        session.game_over_cause = GameOverCause::GoalReached;
+        session.game_over_cause = event.cause;
+        game_over.write(GameOver { cause: event.cause });
         next_state.set(AppState::Result);
-        return;
+    }
+}
+
+/// Why a car's run is over, if it is.
+fn run_over(
+    course: &Course,
+    total_laps: u32,
+    transform: &Transform,
+    state: &CarState,
+    progress: &CarProgress,
+) -> Option<GameOverCause> {
+    // Condition 1: Victory - Completed every lap, driving through every checkpoint
+    if progress.timing.laps.len() as u32 >= total_laps {
+        return Some(GameOverCause::GoalReached);
     }
 
     // Condition 2: Failure - Out of Fuel
-    if session.current_fuel <= 0.0 {
-        println!("Game Over: Out of Fuel!");
-        session.is_game_over = true;
-        session.game_over_cause = GameOverCause::FuelEmpty;
-        next_state.set(AppState::Result);
-        return;
+    if state.fuel <= 0.0 {
+        return Some(GameOverCause::FuelEmpty);
     }
 
     // Condition 3: Failure - Engine Overheat (Specification rule 98)
-    if session.current_temp >= 255.0 {
-        println!("Game Over: Engine Overheat!");
-        session.is_game_over = true;
-        session.game_over_cause = GameOverCause::Overheat;
-        next_state.set(AppState::Result);
-        return;
+    if state.temp >= OVERHEAT_TEMPERATURE {
+        return Some(GameOverCause::Overheat);
     }
 
-    // Condition 4: Failure - Crash / Extreme Course Out (Specification rule 94)
-    if let Some(transform) = query.iter().next() {
-        // Road width 40.0 -> +/- 20.0 from center, plus safety
-        if transform.translation.x.abs() > 25.0 {
-            println!("Game Over: Course Out (Crash)!");
-            session.is_game_over = true;
-            session.game_over_cause = GameOverCause::Crash;
-            next_state.set(AppState::Result);
-            return;
-        }
-    }
+    // Condition 4: Failure - Crash (Specification rule 94)
+    // The car ran up too much penalty, is wrecked by barrier impacts, or somehow got past
+    // the barriers.
+    let out_of_bounds = course.project(transform.translation).lateral.abs()
+        > course.half_width() + OUT_OF_BOUNDS_MARGIN;
+    (progress.penalties.total() > PENALTY_CRASH_THRESHOLD
+        || state.damage.impact >= DAMAGE_CRASH_THRESHOLD
+        || out_of_bounds)
+        .then_some(GameOverCause::Crash)
 }
 
+#[allow(clippy::type_complexity)]
 fn hud_update_system(
     session: Res<GameSession>,
-    car_status: Res<CarStatus>,
-    pc_status: Res<PcStatus>,
+    bests: Res<PersonalBests>,
+    course: Res<Course>,
+    conditions: Res<TrackConditions>,
+    car_query: Query<(&Transform, &CarState, &CarProgress, &CarStatus, &PcStatus), With<PlayerCar>>,
     mut timer_text: Query<&mut Text, (With<GameTimerText>, Without<HudText>)>,
     mut hud_text: Query<&mut Text, (With<HudText>, Without<GameTimerText>)>,
 ) {
+    let Ok((transform, state, progress, car_status, pc_status)) = car_query.single() else {
+        return;
+    };
+
     if let Some(mut text) = timer_text.iter_mut().next() {
-        text.0 = format!("Time: {:.2}", session.play_time);
+        text.0 = timer_readout(&session, progress, &course, &bests);
     }
 
     if let Some(mut text) = hud_text.iter_mut().next() {
@@ -361,14 +539,26 @@ fn hud_update_system(
             ""
         };
 
+        let surface = course.surface_at(&course.project(transform.translation));
+
         text.0 = format!(
-            "Speed: {:.1} km/h\nGear: {}\nFuel: {:.1} / {:.1}\nTemp: {:.1} C\nDRS: {}{}\n\n[PC STATUS]\nCPU: {:.1} MHz | {:.1}%\nGPU: {:.1} MHz\nRAM: {:.1} GB",
-            session.current_speed,
-            session.current_gear,
-            session.current_fuel,
+            "Speed: {:.1} km/h\nGear: {}\nFuel: {:.1} / {:.1}\nTemp: {:.1} C\nDRS: {}\nSurface: {:?}\nWeather: {:?} (wet {:.0}%)\nDamage: E {:.0}% | S {:.0}% | A {:.0}%{}\n\n[PC STATUS]\nCPU: {:.1} MHz | {:.1}%\nGPU: {:.1} MHz\nRAM: {:.1} GB",
+            state.speed,
+            if state.gear == REVERSE_GEAR {
+                "R".to_string()
+            } else {
+                state.gear.to_string()
+            },
+            state.fuel,
             car_status.fuel_capacity,
-            session.current_temp,
-            if session.drs_enabled { "ON" } else { "OFF" },
+            state.temp,
+            if state.drs_enabled { "ON" } else { "OFF" },
+            surface,
+            conditions.weather,
+            conditions.average_wetness() * 100.0,
+            state.damage.engine * 100.0,
+            state.damage.steering * 100.0,
+            state.damage.aero * 100.0,
             sensor_msg,
             pc_status.cpu_frequency as f32,
             pc_status.cpu_usage,
@@ -378,6 +568,66 @@ fn hud_update_system(
     }
 }
 
+/// Run time, lap and sector, and the delta to the personal best at the last gate passed.
+fn timer_readout(
+    session: &GameSession,
+    progress: &CarProgress,
+    course: &Course,
+    bests: &PersonalBests,
+) -> String {
+    let timing = &progress.timing;
+    let mut readout = format!("Time: {:.2}", progress.time);
+    if course.is_circuit() {
+        let lap = (timing.laps.len() as u32 + 1).min(session.total_laps);
+        readout += &format!("\nLap {}/{}", lap, session.total_laps);
+        if let Some(last) = timing.laps.last() {
+            readout += &format!("  Last {:.2}", last.time);
+        }
+    }
+    readout += &format!(
+        "\nSector {}/{}",
+        timing.next_gate + 1,
+        course.sector_count()
+    );
+    let gate = timing.splits.len();
+    if let (Some(split), Some(best)) = (
+        timing.splits.last(),
+        gate.checked_sub(1)
+            .and_then(|index| bests.best_splits(&session.course_name).get(index)),
+    ) {
+        readout += &format!("  {:+.2}", split - best);
+    }
+    if timing.lap_invalid {
+        readout += "\nLAP INVALIDATED";
+    }
+    if let Some(missed) = timing.missed_gate {
+        readout += &format!("\nMISSED CHECKPOINT {} - GO BACK", missed + 1);
+    }
+    if progress.wrong_way {
+        readout += "\nWRONG WAY";
+    }
+    // Recent cuts stay on screen for a few seconds, whether they cost time or not
+    let cut_penalty = progress
+        .penalties
+        .list
+        .iter()
+        .rfind(|penalty| penalty.kind == PenaltyKind::Cut)
+        .filter(|penalty| progress.time - penalty.start - penalty.seconds < INCIDENT_DISPLAY_TIME)
+        .map(|penalty| format!("{} +{:.0}s", penalty.kind.label(), penalty.seconds));
+    let cut_incident = progress
+        .incidents
+        .last()
+        .filter(|incident| {
+            progress.time - incident.time < INCIDENT_DISPLAY_TIME
+                && matches!(incident.kind, IncidentKind::Cut { .. })
+        })
+        .map(TrackIncident::summary);
+    if let Some(cut) = cut_penalty.or(cut_incident) {
+        readout += &format!("\nTRACK LIMITS: {}", cut);
+    }
+    readout
+}
+
 /// Smoothly interpolates the camera to follow the car's position and orientation.
 /// This system runs in PostUpdate to minimize jitter.
 fn camera_follow(
@@ -405,3 +655,118 @@ fn camera_follow(
         cam_transform.look_at(look_target, Vec3::Y);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::car::CarPlugin;
+    use crate::course::file::CourseFile;
+    use crate::events::{DrsToggled, GearChanged};
+    use crate::race::opponent_spec;
+    use crate::states::{PauseState, RacePhase};
+    use crate::weather::Weather;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+    use std::time::Duration;
+
+    #[test]
+    fn pausing_stops_the_clock_and_the_fuel_burn() {
+        let course =
+            CourseFile::parse(include_bytes!("../assets/courses/course_1.course.ron")).unwrap();
+        let mut app = App::new();
+        app.add_plugins((TimePlugin, StatesPlugin, CarPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 30.0,
+            )))
+            .insert_state(AppState::TimeAttackGame)
+            .add_computed_state::<InGame>()
+            .add_sub_state::<RacePhase>()
+            .add_sub_state::<PauseState>()
+            .add_computed_state::<Racing>()
+            .add_message::<RunEnded>()
+            .add_message::<GameOver>()
+            .add_message::<PenaltyApplied>()
+            .add_message::<GearChanged>()
+            .add_message::<DrsToggled>()
+            .insert_resource(GameSession {
+                total_laps: 1,
+                ..default()
+            })
+            .insert_resource(TrackConditions::new(&course, Weather::Dry, false))
+            .add_systems(Update, game_logic_system.run_if(in_state(Racing)));
+        // The player holds the throttle down throughout
+        let mut input = ButtonInput::<KeyCode>::default();
+        input.press(KeyCode::KeyW);
+        app.insert_resource(input);
+        let transform = course.grid_transform(&course.start_grid.slots[0]);
+        let start = course.project(transform.translation).distance;
+        let mut spec = opponent_spec(0, &BaseCarStatus::default());
+        spec.status.fuel_capacity = 100.0;
+        let car = app
+            .world_mut()
+            .spawn((car_bundle(spec, transform, start), PlayerCar))
+            .id();
+        app.insert_resource(course);
+        // Into the game on the first update, then the lights go out
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<RacePhase>>()
+            .set(RacePhase::Racing);
+        let snapshot = |app: &App| {
+            let world = app.world();
+            let progress = world.get::<CarProgress>(car).unwrap();
+            (progress.time, world.get::<CarState>(car).unwrap().fuel)
+        };
+
+        for _ in 0..30 {
+            app.update();
+        }
+        let (time, fuel) = snapshot(&app);
+        assert!(time > 0.0);
+        assert!(fuel < 100.0);
+        assert!(app.world().get::<CarProgress>(car).unwrap().ended.is_none());
+
+        app.world_mut()
+            .resource_mut::<NextState<PauseState>>()
+            .set(PauseState::Paused);
+        app.update();
+        let paused = snapshot(&app);
+        for _ in 0..60 {
+            app.update();
+        }
+        assert_eq!(snapshot(&app), paused);
+    }
+
+    #[test]
+    fn only_the_players_run_ends_the_session() {
+        let mut app = App::new();
+        app.add_message::<RunEnded>()
+            .add_message::<GameOver>()
+            .init_resource::<GameSession>()
+            .init_resource::<NextState<AppState>>()
+            .add_systems(Update, end_of_run_system);
+        let opponent = app.world_mut().spawn(CarProgress::default()).id();
+        let player = app
+            .world_mut()
+            .spawn((CarProgress::default(), PlayerCar))
+            .id();
+
+        app.world_mut().write_message(RunEnded {
+            car: opponent,
+            cause: GameOverCause::FuelEmpty,
+        });
+        app.update();
+        assert!(!app.world().resource::<GameSession>().is_game_over);
+
+        app.world_mut().write_message(RunEnded {
+            car: player,
+            cause: GameOverCause::GoalReached,
+        });
+        app.update();
+        let session = app.world().resource::<GameSession>();
+        assert!(session.is_game_over);
+        assert_eq!(session.game_over_cause, GameOverCause::GoalReached);
+        let game_over = app.world().resource::<Messages<GameOver>>();
+        assert_eq!(game_over.iter_current_update_messages().count(), 1);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::car::components::{CarControls, PlayerCar};
+use crate::course::Course;
+use crate::course::file::file_stem;
+use crate::editor::TestDrive;
+use crate::game::GameWorld;
+use crate::minimap::MinimapMarker;
+use crate::resources::{CarProgress, GameOverCause, GameSession};
+use crate::settings::CycleSetting;
+use crate::states::{AppState, GameMode, InGame, Racing};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Folder the personal best ghost of every course is kept in, relative to the working directory.
+const GHOSTS_PATH: &str = "save/ghosts";
+/// Folder ghosts are exported to and imported from, to share runs between players.
+const SHARED_GHOSTS_PATH: &str = "ghosts";
+/// File extension of ghost files.
+const GHOST_EXTENSION: &str = "ghost.ron";
+/// Seconds between recorded frames. Poses in between are interpolated.
+const FRAME_INTERVAL: f32 = 0.1;
+/// Colour of the ghost car (translucent) and its minimap marker.
+const GHOST_COLOR: Color = Color::srgba(0.6, 0.8, 1.0, 0.35);
+
+/// Which ghost to race against in time attack, picked on the settings screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GhostSelection {
+    #[default]
+    PersonalBest, // The player's own best run on the course
+    Imported, // The fastest run on the course among the imported ghosts
+    Off,
+}
+
+impl CycleSetting for GhostSelection {
+    const NAME: &'static str = "Ghost";
+
+    fn next(&self) -> Self {
+        match self {
+            GhostSelection::PersonalBest => GhostSelection::Imported,
+            GhostSelection::Imported => GhostSelection::Off,
+            GhostSelection::Off => GhostSelection::PersonalBest,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            GhostSelection::PersonalBest => "Personal Best",
+            GhostSelection::Imported => "Fastest Imported",
+            GhostSelection::Off => "Off",
+        }
+        .to_string()
+    }
+}
+
+/// Where the car was and what the driver did at one moment of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GhostFrame {
+    pub time: f32,          // Seconds since the start (penalties not included)
+    pub progress: f32,      // Share of the run distance covered (0 to 1)
+    pub position: [f32; 3], // Car position
+    pub rotation: [f32; 4], // Car orientation (quaternion x, y, z, w)
+    pub throttle: bool,
+    pub brake: bool,
+    pub steer: f32,
+}
+
+/// A recorded run that can be driven again by a ghost car.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GhostRun {
+    pub course: String, // Name of the course it was driven on
+    pub time: f32,      // Finishing time, penalties included (seconds)
+    pub frames: Vec<GhostFrame>,
+}
+
+impl GhostRun {
+    /// Where the car was `time` seconds into the run. It stays where it finished afterwards.
+    pub fn pose_at(&self, time: f32) -> Option<Transform> {
+        let next = self.frames.partition_point(|frame| frame.time <= time);
+        let after = self.frames.get(next).or(self.frames.last())?;
+        let before = next
+            .checked_sub(1)
+            .map_or(after, |index| &self.frames[index]);
+        let span = after.time - before.time;
+        let t = if span > 0.0 {
+            ((time - before.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let position = Vec3::from(before.position).lerp(Vec3::from(after.position), t);
+        let rotation = Quat::from_array(before.rotation).slerp(Quat::from_array(after.rotation), t);
+        Some(Transform::from_translation(position).with_rotation(rotation))
+    }
+
+    /// Run time at which the ghost first got as far as `progress`, if it did.
+    pub fn time_at_progress(&self, progress: f32) -> Option<f32> {
+        let next = self
+            .frames
+            .iter()
+            .position(|frame| frame.progress >= progress)?;
+        let after = &self.frames[next];
+        let Some(before) = next.checked_sub(1).map(|index| &self.frames[index]) else {
+            return Some(after.time);
+        };
+        let span = after.progress - before.progress;
+        let t = if span > 0.0 {
+            (progress - before.progress) / span
+        } else {
+            0.0
+        };
+        Some(before.time + (after.time - before.time) * t)
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        ron::from_str(&text)
+            .map_err(|error| warn!("Ignoring unreadable ghost {}: {}", path.display(), error))
+            .ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let text = ron::to_string(self).map_err(|error| error.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+        }
+        std::fs::write(path, text).map_err(|error| error.to_string())
+    }
+}
+
+fn personal_best_path(course: &str) -> PathBuf {
+    Path::new(GHOSTS_PATH).join(format!("{}.{}", file_stem(course), GHOST_EXTENSION))
+}
+
+/// The player's best run on a course.
+pub fn personal_best_ghost(course: &str) -> Option<GhostRun> {
+    GhostRun::load(&personal_best_path(course))
+}
+
+/// The fastest imported run on a course. Every ghost file in the shared folder counts,
+/// whatever it is called, as long as it was driven on the course.
+fn fastest_imported_ghost(course: &str) -> Option<GhostRun> {
+    let entries = std::fs::read_dir(SHARED_GHOSTS_PATH).ok()?;
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(GHOST_EXTENSION))
+        .filter_map(|path| GhostRun::load(&path))
+        .filter(|ghost| ghost.course == course)
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+/// Copies the personal best ghost of a course to the shared folder, named after the course
+/// and time so several runs can sit side by side. Returns the file written.
+pub fn export_ghost(course: &str) -> Result<PathBuf, String> {
+    let ghost = personal_best_ghost(course).ok_or("No ghost recorded on this course")?;
+    let path = Path::new(SHARED_GHOSTS_PATH).join(format!(
+        "{}_{:.0}.{}",
+        file_stem(course),
+        ghost.time * 1000.0,
+        GHOST_EXTENSION
+    ));
+    ghost.save(&path)?;
+    Ok(path)
+}
+
+/// The player's time attack run being recorded, until it leaves the track.
+#[derive(Resource, Debug, Default)]
+struct GhostRecorder {
+    clock: f32, // Seconds since the start, shared with the ghost being raced
+    frames: Vec<GhostFrame>,
+}
+
+/// Car driving a recorded run.
+#[derive(Component)]
+struct GhostCar(GhostRun);
+
+/// Time gap to the ghost shown on the HUD.
+#[derive(Component)]
+struct GhostDeltaText;
+
+/// Plugin that records the player's time attack runs and races them against a ghost car.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostSelection>()
+            .add_systems(
+                OnEnter(AppState::TimeAttackGame),
+                |mut commands: Commands| {
+                    commands.insert_resource(GhostRecorder::default());
+                },
+            )
+            .add_systems(
+                Update,
+                (ghost_recording_system, ghost_playback_system)
+                    .chain()
+                    .run_if(in_state(AppState::TimeAttackGame).and(in_state(Racing))),
+            )
+            // Saved or dropped as the run leaves the track, however it ends
+            .add_systems(OnExit(InGame), save_ghost);
+    }
+}
+
+/// Spawns the ghost picked on the settings screen for this course, if there is one, with
+/// the time gap display under the run time.
+pub fn spawn_ghost(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    asset_server: &AssetServer,
+    course: &Course,
+    selection: GhostSelection,
+) {
+    let ghost = match selection {
+        GhostSelection::PersonalBest => personal_best_ghost(&course.name),
+        GhostSelection::Imported => fastest_imported_ghost(&course.name),
+        GhostSelection::Off => None,
+    };
+    let Some(ghost) = ghost else {
+        return;
+    };
+    let Some(start) = ghost.pose_at(0.0) else {
+        return;
+    };
+    info!("Racing a {:.2}s ghost on {}", ghost.time, course.name);
+
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(2.0, 1.0, 4.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: GHOST_COLOR,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })),
+        start,
+        MinimapMarker(GHOST_COLOR.with_alpha(1.0)),
+        GhostCar(ghost),
+        GameWorld,
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                GhostDeltaText,
+            ));
+        });
+}
+
+/// Records the player's car every `FRAME_INTERVAL` until the run is over.
+fn ghost_recording_system(
+    time: Res<Time>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut recorder: ResMut<GhostRecorder>,
+    player: Query<(&Transform, &CarControls, &CarProgress), With<PlayerCar>>,
+) {
+    if session.is_game_over {
+        return;
+    }
+    let Ok((transform, controls, progress)) = player.single() else {
+        return;
+    };
+    recorder.clock += time.delta_secs();
+    if recorder
+        .frames
+        .last()
+        .is_some_and(|last| recorder.clock - last.time < FRAME_INTERVAL)
+    {
+        return;
+    }
+    let frame = GhostFrame {
+        time: recorder.clock,
+        progress: course.progress(
+            progress.distance,
+            progress.timing.laps.len() as u32,
+            progress.timing.next_gate,
+        ),
+        position: transform.translation.to_array(),
+        rotation: transform.rotation.to_array(),
+        throttle: controls.throttle,
+        brake: controls.brake,
+        steer: controls.steer,
+    };
+    recorder.frames.push(frame);
+}
+
+/// Moves the ghost along its run in step with the player's and shows the gap between them:
+/// how much longer the player took to get as far as they are than the ghost did.
+fn ghost_playback_system(
+    recorder: Res<GhostRecorder>,
+    mut ghosts: Query<(&mut Transform, &GhostCar)>,
+    mut delta_text: Query<(&mut Text, &mut TextColor), With<GhostDeltaText>>,
+) {
+    let Ok((mut transform, ghost)) = ghosts.single_mut() else {
+        return;
+    };
+    if let Some(pose) = ghost.0.pose_at(recorder.clock) {
+        *transform = pose;
+    }
+
+    let Some(frame) = recorder.frames.last() else {
+        return;
+    };
+    let Some(ghost_time) = ghost.0.time_at_progress(frame.progress) else {
+        return;
+    };
+    let delta = frame.time - ghost_time;
+    for (mut text, mut color) in &mut delta_text {
+        text.0 = format!("Ghost {:+.2}", delta);
+        color.0 = if delta <= 0.0 {
+            Color::srgb(0.2, 0.9, 0.2)
+        } else {
+            Color::srgb(0.9, 0.2, 0.2)
+        };
+    }
+}
+
+/// Keeps the finished run as the course's ghost if it's the fastest so far. Like personal
+/// bests, only time attack runs without an invalidated lap count, and test drives don't.
+/// Runs abandoned from the pause menu never reached the goal, so they're dropped.
+fn save_ghost(
+    mut commands: Commands,
+    session: Res<GameSession>,
+    mode: Res<GameMode>,
+    test_drive: Res<TestDrive>,
+    recorder: Option<Res<GhostRecorder>>,
+) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    commands.remove_resource::<GhostRecorder>();
+    if *mode != GameMode::TimeAttack
+        || session.game_over_cause != GameOverCause::GoalReached
+        || !session.run.timing.all_laps_valid()
+        || test_drive.0
+    {
+        return;
+    }
+    let course = &session.course_name;
+    if personal_best_ghost(course).is_some_and(|best| best.time <= session.run.time) {
+        return;
+    }
+    let ghost = GhostRun {
+        course: course.clone(),
+        time: session.run.time,
+        frames: recorder.frames.clone(),
+    };
+    let path = personal_best_path(course);
+    match ghost.save(&path) {
+        Ok(()) => info!(
+            "Saved the ghost of the {:.2}s run on {}",
+            ghost.time, course
+        ),
+        Err(error) => warn!("Could not save ghost to {}: {}", path.display(), error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time: f32, progress: f32, x: f32) -> GhostFrame {
+        GhostFrame {
+            time,
+            progress,
+            position: [x, 0.0, 0.0],
+            rotation: Quat::IDENTITY.to_array(),
+            throttle: true,
+            brake: false,
+            steer: 0.0,
+        }
+    }
+
+    fn ghost() -> GhostRun {
+        GhostRun {
+            course: "Course 1".to_string(),
+            time: 2.0,
+            frames: vec![
+                frame(0.0, 0.0, 0.0),
+                frame(1.0, 0.25, 10.0),
+                frame(2.0, 1.0, 40.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn ghost_moves_between_frames_and_stops_at_the_finish() {
+        let ghost = ghost();
+        assert_eq!(ghost.pose_at(0.5).unwrap().translation.x, 5.0);
+        assert_eq!(ghost.pose_at(1.5).unwrap().translation.x, 25.0);
+        assert_eq!(ghost.pose_at(10.0).unwrap().translation.x, 40.0);
+        assert!(GhostRun::default().pose_at(0.0).is_none());
+    }
+
+    #[test]
+    fn delta_compares_times_at_the_same_progress() {
+        let ghost = ghost();
+        assert_eq!(ghost.time_at_progress(0.0), Some(0.0));
+        assert_eq!(ghost.time_at_progress(0.625), Some(1.5));
+        assert_eq!(ghost.time_at_progress(1.1), None);
+
+        let text = ron::to_string(&ghost).unwrap();
+        let loaded: GhostRun = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.frames, ghost.frames);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::car::components::CarControls;
+use crate::resources::{GameSession, PcStatus};
+use crate::states::PauseState;
+use bevy::prelude::*;
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// A made-up PC for a computer driven car. Its readings change while racing: usage
+/// follows the throttle and the temperatures creep up under sustained load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HardwareProfile {
+    pub name: &'static str,
+    pub cpu: &'static str,
+    pub gpu: &'static str,
+    pub cpu_frequency: u64,   // Clock when cool (MHz)
+    pub gpu_clock: f32,       // (MHz)
+    pub total_memory: u64,    // (Bytes)
+    pub used_memory: u64,     // (Bytes)
+    pub ssd_available: u64,   // (Bytes)
+    pub usage: (f32, f32),    // CPU usage cruising and flat out (%)
+    pub cpu_temp: (f32, f32), // CPU temperature idle and fully heat-soaked (Celsius)
+    pub gpu_temp: (f32, f32), // GPU temperature idle and fully heat-soaked (Celsius)
+    pub heat_soak_time: f32,  // How long it takes to warm up under load (s)
+    pub throttle_temp: f32,   // CPU temperature above which it clocks itself down (Celsius)
+}
+
+/// Hardware the opponents race on, pole sitter first.
+pub const PROFILES: [HardwareProfile; 5] = [
+    HardwareProfile {
+        name: "Threadripper",
+        cpu: "64-core workstation CPU",
+        gpu: "Workstation GPU",
+        cpu_frequency: 4500,
+        gpu_clock: 2100.0,
+        total_memory: 32 * GB,
+        used_memory: 8 * GB,
+        ssd_available: 2000 * GB,
+        usage: (15.0, 45.0),
+        cpu_temp: (40.0, 75.0),
+        gpu_temp: (35.0, 70.0),
+        heat_soak_time: 120.0,
+        throttle_temp: 95.0,
+    },
+    HardwareProfile {
+        name: "Gaming Rig",
+        cpu: "8-core desktop CPU",
+        gpu: "High-end gaming GPU",
+        cpu_frequency: 5000,
+        gpu_clock: 2600.0,
+        total_memory: 32 * GB,
+        used_memory: 14 * GB,
+        ssd_available: 800 * GB,
+        usage: (20.0, 60.0),
+        cpu_temp: (45.0, 80.0),
+        gpu_temp: (40.0, 78.0),
+        heat_soak_time: 90.0,
+        throttle_temp: 95.0,
+    },
+    HardwareProfile {
+        name: "Overheating Laptop",
+        cpu: "Thin-and-light laptop CPU",
+        gpu: "Laptop GPU",
+        cpu_frequency: 3800,
+        gpu_clock: 1600.0,
+        total_memory: 16 * GB,
+        used_memory: 7 * GB,
+        ssd_available: 120 * GB,
+        usage: (30.0, 90.0),
+        cpu_temp: (60.0, 122.0),
+        gpu_temp: (55.0, 115.0),
+        heat_soak_time: 40.0,
+        throttle_temp: 90.0,
+    },
+    HardwareProfile {
+        name: "Office Desktop",
+        cpu: "4-core office CPU",
+        gpu: "Integrated graphics",
+        cpu_frequency: 3200,
+        gpu_clock: 1100.0,
+        total_memory: 8 * GB,
+        used_memory: 4 * GB,
+        ssd_available: 200 * GB,
+        usage: (35.0, 80.0),
+        cpu_temp: (45.0, 70.0),
+        gpu_temp: (45.0, 70.0),
+        heat_soak_time: 60.0,
+        throttle_temp: 95.0,
+    },
+    HardwareProfile {
+        name: "Retro Pentium",
+        cpu: "Pentium MMX 233 (maxed out on RAM)",
+        gpu: "2D graphics card",
+        cpu_frequency: 233,
+        gpu_clock: 100.0,
+        total_memory: 4 * GB,
+        used_memory: GB,
+        ssd_available: 8 * GB,
+        usage: (60.0, 100.0),
+        cpu_temp: (35.0, 55.0),
+        gpu_temp: (30.0, 40.0),
+        heat_soak_time: 30.0,
+        throttle_temp: 95.0,
+    },
+];
+
+impl HardwareProfile {
+    /// Readings at a share of full `load` (0 to 1) after heating up to `heat` (0 to 1),
+    /// `time` seconds into the race.
+    pub fn reading(&self, load: f32, heat: f32, time: f32) -> PcStatus {
+        let lerp = |(low, high): (f32, f32), t: f32| low + (high - low) * t;
+        // Sensors never read quite the same twice
+        let wobble = (time * 0.7 + self.cpu_frequency as f32).sin();
+        let cpu_temp = lerp(self.cpu_temp, heat) + wobble;
+        // Clocks drop as the CPU gets too hot
+        let throttling = ((cpu_temp - self.throttle_temp) / 50.0).clamp(0.0, 0.5);
+        PcStatus {
+            total_memory: self.total_memory,
+            used_memory: self.used_memory + (load * 0.5 * GB as f32) as u64,
+            cpu_usage: lerp(self.usage, load) + wobble * 2.0,
+            cpu_frequency: (self.cpu_frequency as f32 * (1.0 - throttling)) as u64,
+            gpu_clock: self.gpu_clock * (1.0 - throttling / 2.0),
+            gpu_usage: lerp(self.usage, load),
+            cpu_temp,
+            gpu_temp: lerp(self.gpu_temp, heat) + wobble,
+            ssd_available: self.ssd_available,
+            ..default()
+        }
+    }
+
+    /// Readings of the PC sitting cool on the grid.
+    pub fn at_rest(&self) -> PcStatus {
+        self.reading(0.0, 0.0, 0.0)
+    }
+}
+
+/// Made-up hardware behind a car, updating the car's `PcStatus` as it races.
+#[derive(Component, Debug, Clone)]
+pub struct SyntheticHardware {
+    pub profile: HardwareProfile,
+    load: f32, // Recent share of time on the throttle (0 to 1)
+    heat: f32, // How far it has warmed up towards its heat-soaked temperatures (0 to 1)
+    time: f32, // Seconds since the start
+}
+
+impl SyntheticHardware {
+    pub fn new(profile: HardwareProfile) -> Self {
+        Self {
+            profile,
+            load: 0.0,
+            heat: 0.0,
+            time: 0.0,
+        }
+    }
+}
+
+/// Plugin that runs the opponents' synthetic hardware.
+pub struct HardwarePlugin;
+
+impl Plugin for HardwarePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            synthetic_hardware_system.run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// How quickly usage follows the throttle (per second).
+const LOAD_RATE: f32 = 2.0;
+
+/// Works the synthetic PCs as hard as their cars are driven and reports their readings.
+fn synthetic_hardware_system(
+    time: Res<Time>,
+    session: Res<GameSession>,
+    mut cars: Query<(&mut SyntheticHardware, &CarControls, &mut PcStatus)>,
+) {
+    if session.is_game_over {
+        return;
+    }
+    let dt = time.delta_secs();
+    for (mut hardware, controls, mut pc_status) in &mut cars {
+        let demand = if controls.throttle { 1.0 } else { 0.2 };
+        hardware.load += (demand - hardware.load) * (LOAD_RATE * dt).min(1.0);
+        let soak = dt / hardware.profile.heat_soak_time;
+        let load = hardware.load;
+        hardware.heat += (load - hardware.heat) * soak.min(1.0);
+        hardware.time += dt;
+        *pc_status = hardware
+            .profile
+            .reading(hardware.load, hardware.heat, hardware.time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::BaseCarStatus;
+    use crate::setup_flow::measure_car_status;
+
+    #[test]
+    fn hardware_sets_the_cars_apart() {
+        let base = BaseCarStatus::default();
+        let status = |name: &str| {
+            let profile = PROFILES.iter().find(|p| p.name == name).unwrap();
+            measure_car_status(&base, &profile.at_rest())
+        };
+        let threadripper = status("Threadripper");
+        let pentium = status("Retro Pentium");
+        assert!(threadripper.max_speed > pentium.max_speed);
+        assert!(threadripper.acceleration > pentium.acceleration);
+        assert!(threadripper.handling > pentium.handling);
+    }
+
+    #[test]
+    fn laptop_heats_up_and_throttles() {
+        let laptop = PROFILES
+            .iter()
+            .find(|p| p.name == "Overheating Laptop")
+            .unwrap();
+        let cool = laptop.reading(1.0, 0.0, 0.0);
+        let soaked = laptop.reading(1.0, 1.0, 0.0);
+        assert!(soaked.cpu_temp + soaked.gpu_temp > cool.cpu_temp + cool.gpu_temp + 100.0);
+        assert!(soaked.cpu_frequency < cool.cpu_frequency);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::replay::{WatchedReplay, latest_saved_replay};
 use crate::states::AppState;
 use crate::ui::styles::{
     HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
@@ -11,6 +12,9 @@ struct HomeUi;
 #[derive(Component)]
 struct StartButton;
 
+#[derive(Component)]
+struct ReplaysButton;
+
 #[derive(Component)]
 struct SettingsButton;
 
@@ -78,6 +82,28 @@ fn setup_home(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ));
                 });
 
+            // Replays Button (watches the most recent saved replay)
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(220.0),
+                        height: Val::Px(60.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(NORMAL_BUTTON),
+                    ReplaysButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Replays"),
+                        get_button_text_font(&asset_server),
+                        get_button_text_color(),
+                    ));
+                });
+
             // Settings Button
             parent
                 .spawn((
@@ -155,12 +181,14 @@ fn cleanup_home(mut commands: Commands, query: Query<Entity, With<HomeUi>>) {
 
 #[allow(clippy::type_complexity)]
 fn interact_home_buttons(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
     mut queries: ParamSet<(
         Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<StartButton>)>,
         Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<SettingsButton>)>,
         Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<CalcButton>)>,
         Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<ExitButton>)>,
+        Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<ReplaysButton>)>,
     )>,
 ) {
     // Start
@@ -174,12 +202,32 @@ fn interact_home_buttons(
             Interaction::None => *color = NORMAL_BUTTON.into(),
         }
     }
+    // Replays
+    for (interaction, mut color) in queries.p4().iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match latest_saved_replay() {
+                    Some(replay) => {
+                        commands.insert_resource(WatchedReplay {
+                            replay,
+                            back_to: AppState::Home,
+                        });
+                        next_state.set(AppState::Replay);
+                    }
+                    None => info!("No saved replays to watch"),
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
     // Settings
     for (interaction, mut color) in queries.p1().iter_mut() {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                println!("Go to Settings");
+                info!("Go to Settings");
                 next_state.set(AppState::Settings);
             }
             Interaction::Hovered => *color = HOVERED_BUTTON.into(),
@@ -191,7 +239,7 @@ fn interact_home_buttons(
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                println!("Go to Calculations");
+                info!("Go to Calculations");
                 next_state.set(AppState::CalcInfo);
             }
             Interaction::Hovered => *color = HOVERED_BUTTON.into(),
@@ -204,7 +252,7 @@ fn interact_home_buttons(
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
                 // exit.send(AppExit::Success);
-                println!("Exit Pressed (Logic disabled for build fix)");
+                info!("Exit Pressed (Logic disabled for build fix)");
             }
             Interaction::Hovered => *color = HOVERED_BUTTON.into(),
             Interaction::None => *color = NORMAL_BUTTON.into(),
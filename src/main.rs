@@ -1,27 +1,62 @@
 use bevy::prelude::*;
 
+mod ai;
 mod calc_info;
 mod car;
+mod countdown;
+mod course;
+mod editor;
+mod events;
 mod game;
+mod ghost;
+mod hardware;
 mod home;
+mod minimap;
 mod mode_select;
+mod pause;
+mod race;
+mod race_grid;
+mod records;
+mod replay;
 mod resources;
 mod result;
+mod scenery;
 mod settings;
 mod setup_flow;
 mod states;
+mod timing;
+mod track_limits;
 mod ui;
+mod weather;
 
+use ai::AiPlugin;
 use calc_info::CalcInfoPlugin;
+use countdown::CountdownPlugin;
+use course::loader::CoursePlugin;
+use course::streaming::CourseStreamingPlugin;
+use editor::EditorPlugin;
+use events::GameEventsPlugin;
 use game::GamePlugin;
+use ghost::GhostPlugin;
+use hardware::HardwarePlugin;
 use home::HomePlugin;
+use minimap::MinimapPlugin;
 use mode_select::ModeSelectPlugin;
+use pause::PausePlugin;
+use race::RacePlugin;
+use race_grid::RaceGridPlugin;
+use records::RecordsPlugin;
+use replay::ReplayPlugin;
 use resources::{BaseCarStatus, CarStatus, PcMonitor, PcStatus};
 use result::ResultPlugin;
+use scenery::SceneryPlugin;
 use settings::SettingsPlugin;
 use setup_flow::SetupFlowPlugin;
-use states::AppState;
+use states::{AppState, GameMode, InGame, OnTrack, PauseState, RacePhase, Racing};
+use timing::TimingPlugin;
+use track_limits::TrackLimitsPlugin;
 use ui::styles::UiStylesPlugin;
+use weather::WeatherPlugin;
 
 fn main() {
     App::new()
@@ -30,6 +65,12 @@ fn main() {
         // 2. State & Resource Initialization
         // Initializing the application state machine
         .init_state::<AppState>()
+        .add_computed_state::<InGame>()
+        .add_computed_state::<OnTrack>()
+        .add_sub_state::<RacePhase>()
+        .add_sub_state::<PauseState>()
+        .add_computed_state::<Racing>()
+        .init_resource::<GameMode>()
         // Persistent hardware monitoring and car status resources
         .init_resource::<PcMonitor>()
         .init_resource::<PcStatus>()
@@ -40,12 +81,30 @@ fn main() {
         .add_plugins(HomePlugin)
         .add_plugins(ModeSelectPlugin)
         .add_plugins(SetupFlowPlugin)
+        .add_plugins(RaceGridPlugin)
         .add_plugins(ResultPlugin)
         .add_plugins(SettingsPlugin)
         .add_plugins(CalcInfoPlugin)
+        .add_plugins(EditorPlugin)
         // 4. Gameplay Logic Plugins
+        .add_plugins(GameEventsPlugin)
+        .add_plugins(CoursePlugin)
+        .add_plugins(CourseStreamingPlugin)
         .add_plugins(GamePlugin)
         .add_plugins(car::CarPlugin)
+        .add_plugins(WeatherPlugin)
+        .add_plugins(RecordsPlugin)
+        .add_plugins(TimingPlugin)
+        .add_plugins(TrackLimitsPlugin)
+        .add_plugins(MinimapPlugin)
+        .add_plugins(SceneryPlugin)
+        .add_plugins(RacePlugin)
+        .add_plugins(AiPlugin)
+        .add_plugins(HardwarePlugin)
+        .add_plugins(GhostPlugin)
+        .add_plugins(ReplayPlugin)
+        .add_plugins(CountdownPlugin)
+        .add_plugins(PausePlugin)
         // 5. Global Systems
         .add_systems(Startup, setup_camera)
         .run();
@@ -0,0 +1,166 @@
+use crate::car::components::PlayerCar;
+use crate::course::Course;
+use crate::course::preview::{MapProjection, map_image};
+use crate::game::GameWorld;
+use crate::resources::CarProgress;
+use crate::states::InGame;
+use bevy::prelude::*;
+
+/// Resolution of the minimap image (pixels).
+const MAP_RESOLUTION: u32 = 256;
+/// Size the minimap is shown at on screen (pixels).
+const MAP_DISPLAY_SIZE: f32 = 200.0;
+/// Diameter of a car marker on the minimap (pixels).
+const MARKER_SIZE: f32 = 10.0;
+
+/// Colour of the player's marker on the minimap.
+pub const PLAYER_MARKER_COLOR: Color = Color::srgb(1.0, 0.85, 0.1);
+
+/// Shows an entity (the player, an opponent, a ghost) as a coloured dot on the minimap.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MinimapMarker(pub Color);
+
+/// The minimap's map image, which the markers are placed over.
+#[derive(Component)]
+struct Minimap(MapProjection);
+
+/// Dot on the minimap following the entity it marks.
+#[derive(Component)]
+struct MinimapDot(Entity);
+
+/// Filled part of the progress bar.
+#[derive(Component)]
+struct ProgressFill;
+
+/// Plugin that keeps the minimap markers and the progress bar up to date while driving.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (minimap_marker_system, progress_bar_system).run_if(in_state(InGame)),
+        );
+    }
+}
+
+/// Spawns the minimap of the course and the progress bar below it (bottom left).
+/// Both are drawn from the course description, so every course gets them for free.
+pub fn spawn_minimap(commands: &mut Commands, images: &mut Assets<Image>, course: &Course) {
+    let map = images.add(map_image(course, MAP_RESOLUTION));
+    let projection = MapProjection::new(course, MAP_RESOLUTION);
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ImageNode::new(map),
+                Node {
+                    width: Val::Px(MAP_DISPLAY_SIZE),
+                    height: Val::Px(MAP_DISPLAY_SIZE),
+                    ..default()
+                },
+                Minimap(projection),
+            ));
+
+            // Progress bar: share of the whole run driven
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(MAP_DISPLAY_SIZE),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.12, 0.8)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(PLAYER_MARKER_COLOR),
+                        ProgressFill,
+                    ));
+                });
+        });
+}
+
+/// Moves every marker's dot to where its entity is on the map. Dots are added for newly
+/// marked entities and removed once their entity is gone.
+fn minimap_marker_system(
+    mut commands: Commands,
+    minimap: Query<(Entity, &Minimap)>,
+    markers: Query<(Entity, &Transform, &MinimapMarker)>,
+    mut dots: Query<(Entity, &MinimapDot, &mut Node)>,
+) {
+    let Ok((map_entity, minimap)) = minimap.single() else {
+        return;
+    };
+    let place = |position: Vec3, node: &mut Node| {
+        let pixel = minimap.0.to_pixel(position) / MAP_RESOLUTION as f32 * 100.0;
+        node.left = Val::Percent(pixel.x);
+        node.top = Val::Percent(pixel.y);
+    };
+
+    for (dot_entity, dot, mut node) in &mut dots {
+        match markers.get(dot.0) {
+            Ok((_, transform, _)) => place(transform.translation, &mut node),
+            Err(_) => commands.entity(dot_entity).despawn(),
+        }
+    }
+
+    for (entity, transform, marker) in &markers {
+        if dots.iter().any(|(_, dot, _)| dot.0 == entity) {
+            continue;
+        }
+        let mut node = Node {
+            position_type: PositionType::Absolute,
+            width: Val::Px(MARKER_SIZE),
+            height: Val::Px(MARKER_SIZE),
+            // Centre the dot on the position
+            margin: UiRect {
+                left: Val::Px(-MARKER_SIZE / 2.0),
+                top: Val::Px(-MARKER_SIZE / 2.0),
+                ..default()
+            },
+            border_radius: BorderRadius::MAX,
+            ..default()
+        };
+        place(transform.translation, &mut node);
+        let dot = commands
+            .spawn((node, BackgroundColor(marker.0), MinimapDot(entity)))
+            .id();
+        commands.entity(map_entity).add_child(dot);
+    }
+}
+
+/// Fills the progress bar up to the share of the run driven, laps included.
+fn progress_bar_system(
+    course: Res<Course>,
+    car: Query<&CarProgress, With<PlayerCar>>,
+    mut fill: Query<&mut Node, With<ProgressFill>>,
+) {
+    let Ok(run) = car.single() else {
+        return;
+    };
+    let progress = course.progress(
+        run.distance,
+        run.timing.laps.len() as u32,
+        run.timing.next_gate,
+    );
+    for mut node in &mut fill {
+        node.width = Val::Percent(progress * 100.0);
+    }
+}
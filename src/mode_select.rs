@@ -1,4 +1,4 @@
-use crate::states::AppState;
+use crate::states::{AppState, GameMode};
 use crate::ui::styles::{
     HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
     get_title_text_color, get_title_text_font,
@@ -12,6 +12,7 @@ struct ModeSelectUi;
 enum ModeButton {
     TimeAttack,
     Race,
+    TrackEditor,
     Back,
 }
 
@@ -91,6 +92,22 @@ fn setup_mode_select(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ));
                 });
 
+            // Track Editor Button
+            parent
+                .spawn((
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    ModeButton::TrackEditor,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Track Editor"),
+                        get_button_text_font(&asset_server),
+                        get_button_text_color(),
+                    ));
+                });
+
             // Back Button
             parent
                 .spawn((
@@ -115,11 +132,13 @@ fn cleanup_mode_select(mut commands: Commands, query: Query<Entity, With<ModeSel
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn interact_mode_buttons(
     mut query: Query<
         (&Interaction, &mut BackgroundColor, &ModeButton),
         (Changed<Interaction>, With<Button>),
     >,
+    mut mode: ResMut<GameMode>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     for (interaction, mut color, button_type) in &mut query {
@@ -128,11 +147,17 @@ fn interact_mode_buttons(
                 *color = PRESSED_BUTTON.into();
                 match button_type {
                     ModeButton::TimeAttack => {
-                        println!("Time Attack Selected");
+                        info!("Time Attack Selected");
+                        *mode = GameMode::TimeAttack;
                         next_state.set(AppState::CourseSelect);
                     }
                     ModeButton::Race => {
-                        println!("Race Mode Selected (Not Implemented)");
+                        info!("Race Mode Selected");
+                        *mode = GameMode::Race;
+                        next_state.set(AppState::CourseSelect);
+                    }
+                    ModeButton::TrackEditor => {
+                        next_state.set(AppState::TrackEditor);
                     }
                     ModeButton::Back => {
                         next_state.set(AppState::Home);
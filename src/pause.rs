@@ -0,0 +1,254 @@
+use crate::editor::TestDrive;
+use crate::states::{AppState, InGame, PauseState};
+use crate::ui::styles::{
+    HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
+    get_title_text_color, get_title_text_font,
+};
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct PauseUi;
+
+#[derive(Component)]
+enum PauseButton {
+    Resume,
+    Restart,
+    CarSelect,
+    Quit,
+}
+
+/// Game state a restarted run goes back into, kept while passing through
+/// `AppState::Restarting`.
+#[derive(Resource)]
+struct RestartInto(AppState);
+
+/// Plugin for the pause menu: Esc pauses the run and offers to resume, restart the same
+/// course and car, pick another car or quit.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseState::Paused), setup_pause_menu)
+            .add_systems(OnExit(PauseState::Paused), cleanup_pause_menu)
+            .add_systems(OnEnter(AppState::Restarting), restart_run)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(in_state(InGame)),
+                    interact_pause_buttons.run_if(in_state(PauseState::Paused)),
+                ),
+            );
+    }
+}
+
+fn toggle_pause(
+    input: Res<ButtonInput<KeyCode>>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_pause.set(match pause.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    test_drive: Res<TestDrive>,
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(10),
+            PauseUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Paused"),
+                get_title_text_font(&asset_server),
+                get_title_text_color(),
+            ));
+
+            let quit = if test_drive.0 {
+                "Back to Editor"
+            } else {
+                "Quit to Home"
+            };
+            for (label, button) in [
+                ("Resume", PauseButton::Resume),
+                ("Restart", PauseButton::Restart),
+                ("Car Select", PauseButton::CarSelect),
+                (quit, PauseButton::Quit),
+            ] {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(300.0),
+                            height: Val::Px(80.0),
+                            margin: UiRect::top(Val::Px(20.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(NORMAL_BUTTON),
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(label),
+                            get_button_text_font(&asset_server),
+                            get_button_text_color(),
+                        ));
+                    });
+            }
+        });
+}
+
+fn cleanup_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseUi>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn interact_pause_buttons(
+    mut commands: Commands,
+    mut query: Query<
+        (&Interaction, &mut BackgroundColor, &PauseButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    test_drive: Res<TestDrive>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    for (interaction, mut color, button) in &mut query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match button {
+                    PauseButton::Resume => next_pause.set(PauseState::Running),
+                    // Same course and car, without measuring the PC again
+                    PauseButton::Restart => {
+                        commands.insert_resource(RestartInto(*state.get()));
+                        next_state.set(AppState::Restarting);
+                    }
+                    // A test drive's car is picked in the editor, so it goes back there
+                    PauseButton::CarSelect => next_state.set(if test_drive.0 {
+                        AppState::TrackEditor
+                    } else {
+                        AppState::CarSelect
+                    }),
+                    PauseButton::Quit => next_state.set(if test_drive.0 {
+                        AppState::TrackEditor
+                    } else {
+                        AppState::Home
+                    }),
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+/// Leaving the game state tears the run down; going straight back in sets up a new one.
+fn restart_run(
+    mut commands: Commands,
+    restart: Option<Res<RestartInto>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let target = restart.map_or(AppState::Home, |restart| restart.0);
+    commands.remove_resource::<RestartInto>();
+    next_state.set(target);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::states::{RacePhase, Racing};
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::state::app::StatesPlugin;
+
+    #[test]
+    fn pausing_stops_the_race() {
+        assert!(Racing::compute((RacePhase::Racing, PauseState::Running)).is_some());
+        assert!(Racing::compute((RacePhase::Racing, PauseState::Paused)).is_none());
+        assert!(Racing::compute((RacePhase::Countdown, PauseState::Running)).is_none());
+    }
+
+    /// State the pause menu leads to when `button` is pressed.
+    fn after_pressing(button: PauseButton, test_drive: bool) -> NextState<AppState> {
+        let mut app = App::new();
+        app.insert_resource(TestDrive(test_drive))
+            .insert_resource(State::new(AppState::TimeAttackGame))
+            .init_resource::<NextState<AppState>>()
+            .init_resource::<NextState<PauseState>>();
+        app.world_mut().spawn((
+            Button,
+            Interaction::Pressed,
+            BackgroundColor(NORMAL_BUTTON),
+            button,
+        ));
+        app.world_mut()
+            .run_system_once(interact_pause_buttons)
+            .unwrap();
+        app.world_mut()
+            .remove_resource::<NextState<AppState>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_drives_leave_for_the_editor() {
+        assert!(matches!(
+            after_pressing(PauseButton::CarSelect, false),
+            NextState::Pending(AppState::CarSelect)
+        ));
+        assert!(matches!(
+            after_pressing(PauseButton::CarSelect, true),
+            NextState::Pending(AppState::TrackEditor)
+        ));
+        assert!(matches!(
+            after_pressing(PauseButton::Quit, true),
+            NextState::Pending(AppState::TrackEditor)
+        ));
+    }
+
+    #[test]
+    fn restarting_goes_back_into_the_same_mode() {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .insert_state(AppState::RaceGame)
+            .add_systems(OnEnter(AppState::Restarting), restart_run);
+        app.update();
+
+        app.insert_resource(RestartInto(AppState::RaceGame));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Restarting);
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::Restarting
+        );
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::RaceGame
+        );
+        assert!(!app.world().contains_resource::<RestartInto>());
+    }
+}
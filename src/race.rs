@@ -0,0 +1,247 @@
+use crate::ai::{AiDriver, RacingLine};
+use crate::car::components::{HardwareSource, PlayerCar};
+use crate::car::{CarSpec, car_bundle};
+use crate::course::Course;
+use crate::game::GameWorld;
+use crate::hardware::{PROFILES, SyntheticHardware};
+use crate::minimap::MinimapMarker;
+use crate::resources::{BaseCarStatus, CarProgress, GameOverCause};
+use crate::setup_flow::measure_car_status;
+use crate::states::AppState;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// Most opponents lined up on the grid, if the course has the slots for them.
+const MAX_OPPONENTS: usize = 5;
+/// Opponents' names, pole sitter first.
+pub const OPPONENT_NAMES: [&str; MAX_OPPONENTS] = ["Ada", "Grace", "Alan", "Linus", "Margaret"];
+/// Opponents' car and minimap marker colours.
+pub const OPPONENT_COLORS: [Color; MAX_OPPONENTS] = [
+    Color::srgb(0.1, 0.4, 0.9),
+    Color::srgb(0.1, 0.7, 0.3),
+    Color::srgb(0.6, 0.2, 0.8),
+    Color::srgb(0.95, 0.5, 0.1),
+    Color::srgb(0.2, 0.8, 0.8),
+];
+/// Share of its top speed each opponent drives at, pole sitter first.
+const OPPONENT_PACE: [f32; MAX_OPPONENTS] = [0.97, 0.94, 0.91, 0.88, 0.85];
+
+/// Where a car stands in the race.
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub player: bool,
+    pub progress: f32,            // Share of the race distance covered (0 to 1)
+    pub finish_time: Option<f32>, // Race time at the finish (s)
+    pub retired: bool,            // Out of the race (fuel, crash, overheat)
+}
+
+/// Running order of the race, leader first. Kept up to date while racing and read by the
+/// result screen afterwards.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct RaceStandings(pub Vec<Standing>);
+
+impl RaceStandings {
+    /// The player's race position (1 = leading).
+    pub fn player_position(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|standing| standing.player)
+            .map(|index| index + 1)
+    }
+}
+
+/// Race position shown on the HUD.
+#[derive(Component)]
+struct RacePositionText;
+
+/// Plugin that keeps the running order of a race.
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RaceStandings>()
+            .add_systems(Update, race_hud_system.run_if(in_state(AppState::RaceGame)))
+            // After the rules have run, so the order at the finish is the final one
+            .add_systems(
+                PostUpdate,
+                race_standings_system.run_if(in_state(AppState::RaceGame)),
+            );
+    }
+}
+
+/// Number of opponents lined up on a course's grid: one less than its slots, so the
+/// player has one too.
+pub fn opponent_count(course: &Course) -> usize {
+    course
+        .start_grid
+        .slots
+        .len()
+        .saturating_sub(1)
+        .min(MAX_OPPONENTS)
+}
+
+/// The car an opponent races: built to the same design as the player's, running on the
+/// opponent's synthetic hardware, with its stats worked out as on the measuring screen.
+pub fn opponent_spec(index: usize, base: &BaseCarStatus) -> CarSpec {
+    let hardware = PROFILES[index].at_rest();
+    CarSpec {
+        base: base.clone(),
+        status: measure_car_status(base, &hardware),
+        hardware,
+        source: HardwareSource::Synthetic,
+    }
+}
+
+/// Lines the computer driven opponents up on the grid, front to back, works out the
+/// racing line they follow and adds the race position display. Opponents drive cars built
+/// like the player's `spec` on their own synthetic hardware. Returns where the player
+/// starts: the slot behind the last opponent.
+pub fn spawn_race(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    asset_server: &AssetServer,
+    course: &Course,
+    spec: &CarSpec,
+) -> Transform {
+    let slots = &course.start_grid.slots;
+    let opponents = opponent_count(course);
+    let body = meshes.add(Cuboid::new(2.0, 1.0, 4.0));
+    for (index, slot) in slots.iter().take(opponents).enumerate() {
+        let color = OPPONENT_COLORS[index];
+        let transform = course.grid_transform(slot);
+        commands.spawn((
+            Mesh3d(body.clone()),
+            MeshMaterial3d(materials.add(color)),
+            car_bundle(
+                opponent_spec(index, &spec.base),
+                transform,
+                course.project(transform.translation).distance,
+            ),
+            SyntheticHardware::new(PROFILES[index]),
+            Name::new(OPPONENT_NAMES[index]),
+            MinimapMarker(color),
+            AiDriver::new(OPPONENT_PACE[index], index as f32 * 2.0),
+            GameWorld,
+        ));
+    }
+    info!("Race on {} against {} opponents", course.name, opponents);
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                RacePositionText,
+            ));
+        });
+    commands.insert_resource(RaceStandings::default());
+    commands.insert_resource(RacingLine::new(course));
+
+    course.grid_transform(&slots[opponents])
+}
+
+/// Works out the running order from how far every car has got.
+fn race_standings_system(
+    course: Res<Course>,
+    cars: Query<(&Name, &CarProgress, Has<PlayerCar>)>,
+    mut standings: ResMut<RaceStandings>,
+) {
+    let mut order: Vec<Standing> = cars
+        .iter()
+        .map(|(name, progress, player)| {
+            let finished = progress.ended == Some(GameOverCause::GoalReached);
+            Standing {
+                name: name.to_string(),
+                player,
+                progress: course.progress(
+                    progress.distance,
+                    progress.timing.laps.len() as u32,
+                    progress.timing.next_gate,
+                ),
+                finish_time: finished.then_some(progress.time),
+                retired: progress.ended.is_some() && !finished,
+            }
+        })
+        .collect();
+    sort_standings(&mut order);
+    standings.0 = order;
+}
+
+/// Finishers first in the order they finished, then the cars still racing by how far
+/// they've got, then the cars out of the race.
+fn sort_standings(standings: &mut [Standing]) {
+    standings.sort_by(|a, b| {
+        a.retired
+            .cmp(&b.retired)
+            .then_with(|| match (a.finish_time, b.finish_time) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => b.progress.total_cmp(&a.progress),
+            })
+    });
+}
+
+fn race_hud_system(
+    standings: Res<RaceStandings>,
+    mut text: Query<&mut Text, With<RacePositionText>>,
+) {
+    let Some(position) = standings.player_position() else {
+        return;
+    };
+    for mut text in &mut text {
+        text.0 = format!("P{}/{}", position, standings.0.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(name: &str, progress: f32, finish_time: Option<f32>) -> Standing {
+        Standing {
+            name: name.to_string(),
+            player: false,
+            progress,
+            finish_time,
+            retired: false,
+        }
+    }
+
+    #[test]
+    fn finishers_lead_the_running_order() {
+        let mut order = vec![
+            standing("racing", 0.9, None),
+            standing("second", 1.0, Some(95.0)),
+            Standing {
+                retired: true,
+                ..standing("retired", 0.95, None)
+            },
+            standing("winner", 1.0, Some(90.0)),
+            standing("leading the rest", 0.93, None),
+        ];
+        sort_standings(&mut order);
+        let names: Vec<&str> = order.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["winner", "second", "leading the rest", "racing", "retired"]
+        );
+    }
+}
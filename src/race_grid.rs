@@ -0,0 +1,142 @@
+use crate::course::Course;
+use crate::course::loader::{CourseLibrary, SelectedCourse};
+use crate::hardware::PROFILES;
+use crate::race::{OPPONENT_COLORS, OPPONENT_NAMES, opponent_count, opponent_spec};
+use crate::resources::{BaseCarStatus, CarStatus, PcStatus};
+use crate::states::{AppState, GameMode};
+use crate::ui::styles::{get_title_text_color, get_title_text_font};
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct RaceGridUi;
+
+/// Plugin for the starting grid shown before a race: who the player races against and
+/// the hardware behind every car.
+pub struct RaceGridPlugin;
+
+impl Plugin for RaceGridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::RaceGrid), setup_race_grid)
+            .add_systems(OnExit(AppState::RaceGrid), cleanup_race_grid)
+            .add_systems(
+                Update,
+                update_race_grid.run_if(in_state(AppState::RaceGrid)),
+            );
+    }
+}
+
+/// One line of the grid: position, driver, hardware and the car stats it makes for.
+fn grid_line(
+    position: usize,
+    name: &str,
+    hardware: &str,
+    pc: &PcStatus,
+    car: &CarStatus,
+) -> String {
+    format!(
+        "P{}  {}  {}\n     CPU {} MHz ({:.0}%) | GPU {:.0} MHz | RAM {} / {} GB | {:.0}C + {:.0}C\n     Top {:.0} km/h | Accel {:.1} | Handling {:.2} | Braking {:.0}",
+        position,
+        name,
+        hardware,
+        pc.cpu_frequency,
+        pc.cpu_usage,
+        pc.gpu_clock,
+        pc.used_memory / 1024 / 1024 / 1024,
+        pc.total_memory / 1024 / 1024 / 1024,
+        pc.cpu_temp,
+        pc.gpu_temp,
+        car.max_speed,
+        car.acceleration,
+        car.handling,
+        car.braking,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_race_grid(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<CourseLibrary>,
+    selected: Res<SelectedCourse>,
+    courses: Res<Assets<Course>>,
+    base_car: Res<BaseCarStatus>,
+    car_status: Res<CarStatus>,
+    pc_status: Res<PcStatus>,
+) {
+    let opponents = selected
+        .0
+        .as_ref()
+        .or(library.courses.first())
+        .and_then(|handle| courses.get(handle))
+        .map_or(0, opponent_count);
+    let font = TextFont {
+        font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+        font_size: 18.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            RaceGridUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Starting Grid - Press Enter to Race"),
+                get_title_text_font(&asset_server),
+                get_title_text_color(),
+            ));
+
+            for index in 0..opponents {
+                let spec = opponent_spec(index, &base_car);
+                let profile = &PROFILES[index];
+                parent.spawn((
+                    Text::new(grid_line(
+                        index + 1,
+                        OPPONENT_NAMES[index],
+                        &format!("{} ({}, {})", profile.name, profile.cpu, profile.gpu),
+                        &spec.hardware,
+                        &spec.status,
+                    )),
+                    font.clone(),
+                    TextColor(OPPONENT_COLORS[index]),
+                ));
+            }
+            parent.spawn((
+                Text::new(grid_line(
+                    opponents + 1,
+                    "You",
+                    "Your PC",
+                    &pc_status,
+                    &car_status,
+                )),
+                font.clone(),
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn cleanup_race_grid(mut commands: Commands, query: Query<Entity, With<RaceGridUi>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_race_grid(
+    input: Res<ButtonInput<KeyCode>>,
+    mode: Res<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::Enter) {
+        next_state.set(mode.game_state());
+    }
+}
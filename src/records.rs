@@ -0,0 +1,210 @@
+use crate::editor::TestDrive;
+use crate::resources::{GameOverCause, GameSession};
+use crate::states::{AppState, GameMode};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File the player's personal bests are kept in, relative to the working directory.
+const RECORDS_PATH: &str = "save/personal_bests.ron";
+
+/// The player's best result on one course.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseRecord {
+    pub time: f32, // Best finishing time (seconds)
+    #[serde(default)]
+    pub splits: Vec<f32>, // Run time at every gate passed on the best run (seconds)
+    #[serde(default)]
+    pub best_lap: Option<f32>, // Fastest single lap, from any run (seconds)
+}
+
+/// Resource holding the player's personal bests, keyed by course name.
+/// Loaded at startup and written back whenever a new best is set.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersonalBests {
+    pub courses: HashMap<String, CourseRecord>,
+}
+
+impl PersonalBests {
+    pub fn best_time(&self, course: &str) -> Option<f32> {
+        self.courses.get(course).map(|record| record.time)
+    }
+
+    pub fn best_lap(&self, course: &str) -> Option<f32> {
+        self.courses.get(course).and_then(|record| record.best_lap)
+    }
+
+    /// Split times of the best run, to compare a run in progress against.
+    pub fn best_splits(&self, course: &str) -> &[f32] {
+        self.courses
+            .get(course)
+            .map(|record| record.splits.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Records a finishing time with its splits and returns true if it beats the previous best.
+    pub fn submit(&mut self, course: &str, time: f32, splits: &[f32]) -> bool {
+        if self.best_time(course).is_some_and(|best| best <= time) {
+            return false;
+        }
+        let best_lap = self.best_lap(course);
+        self.courses.insert(
+            course.to_string(),
+            CourseRecord {
+                time,
+                splits: splits.to_vec(),
+                best_lap,
+            },
+        );
+        true
+    }
+
+    /// Records a lap time and returns true if it's the fastest lap on the course.
+    /// Only courses with a finished run keep a lap record.
+    pub fn submit_lap(&mut self, course: &str, lap: f32) -> bool {
+        let Some(record) = self.courses.get_mut(course) else {
+            return false;
+        };
+        if record.best_lap.is_some_and(|best| best <= lap) {
+            return false;
+        }
+        record.best_lap = Some(lap);
+        true
+    }
+
+    fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|error| {
+                warn!(
+                    "Ignoring unreadable personal bests {}: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|text| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                std::fs::write(path, text).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!(
+                "Could not save personal bests to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Plugin that keeps the player's personal bests across runs and sessions.
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PersonalBests::load(Path::new(RECORDS_PATH)))
+            .add_systems(OnEnter(AppState::Result), record_personal_best);
+    }
+}
+
+/// Keeps a finished run's time and fastest lap if they beat the course's records. Only
+/// time attacks count: races start from the grid and have contact, and test drives may
+/// not be on the layout the records are for.
+fn record_personal_best(
+    session: Res<GameSession>,
+    mode: Res<GameMode>,
+    test_drive: Res<TestDrive>,
+    mut bests: ResMut<PersonalBests>,
+) {
+    if session.game_over_cause != GameOverCause::GoalReached
+        || *mode != GameMode::TimeAttack
+        || test_drive.0
+    {
+        return;
+    }
+    let course = &session.course_name;
+    // A run with an invalidated lap can still set a lap record, but not a best time
+    let new_best = session.run.timing.all_laps_valid()
+        && bests.submit(course, session.run.time, &session.run.timing.splits);
+    if new_best {
+        info!("New personal best on {}: {:.2}s", course, session.run.time);
+    }
+    // Laps only matter on circuits; a point-to-point run is a single "lap"
+    let new_lap = session.total_laps > 1
+        && session
+            .run
+            .timing
+            .best_lap()
+            .is_some_and(|lap| bests.submit_lap(course, lap));
+    if new_lap {
+        info!("New lap record on {}", course);
+    }
+    if new_best || new_lap {
+        bests.save(Path::new(RECORDS_PATH));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn only_faster_times_replace_the_best() {
+        let mut bests = PersonalBests::default();
+        assert!(bests.submit("Course 1", 90.0, &[40.0, 90.0]));
+        assert!(!bests.submit("Course 1", 95.0, &[30.0, 95.0]));
+        assert!(bests.submit("Course 1", 85.0, &[42.0, 85.0]));
+        assert_eq!(bests.best_time("Course 1"), Some(85.0));
+        assert_eq!(bests.best_splits("Course 1"), &[42.0, 85.0]);
+        assert_eq!(bests.best_time("Course 2"), None);
+    }
+
+    /// Personal bests after a finished run on Course 1 in `mode`.
+    fn bests_after_finish(mode: GameMode, test_drive: bool) -> PersonalBests {
+        let mut app = App::new();
+        app.insert_resource(mode)
+            .insert_resource(TestDrive(test_drive))
+            .init_resource::<PersonalBests>()
+            .insert_resource(GameSession {
+                course_name: "Course 1".to_string(),
+                game_over_cause: GameOverCause::GoalReached,
+                ..default()
+            });
+        app.world_mut()
+            .run_system_once(record_personal_best)
+            .unwrap();
+        app.world_mut().remove_resource::<PersonalBests>().unwrap()
+    }
+
+    #[test]
+    fn test_drives_set_no_records() {
+        let bests = bests_after_finish(GameMode::TimeAttack, true);
+        assert!(bests.courses.is_empty());
+    }
+
+    #[test]
+    fn races_set_no_records() {
+        let bests = bests_after_finish(GameMode::Race, false);
+        assert!(bests.courses.is_empty());
+    }
+
+    #[test]
+    fn lap_record_survives_slower_best_runs() {
+        let mut bests = PersonalBests::default();
+        bests.submit("Circuit 1", 120.0, &[]);
+        assert!(bests.submit_lap("Circuit 1", 38.0));
+        assert!(!bests.submit_lap("Circuit 1", 39.0));
+        bests.submit("Circuit 1", 118.0, &[]);
+        assert_eq!(bests.best_lap("Circuit 1"), Some(38.0));
+    }
+}
@@ -0,0 +1,276 @@
+use super::{CarSample, Replay, ReplayCar, ReplayEvent, ReplayEventKind, ReplayFrame};
+use crate::resources::GameOverCause;
+use bevy::prelude::*;
+use thiserror::Error;
+
+/// First bytes of every replay file.
+const MAGIC: &[u8; 4] = b"PCRP";
+/// Version of the replay format written by this build. Bump it whenever the layout changes.
+pub const REPLAY_FORMAT_VERSION: u16 = 2;
+/// Oldest version still read: version 1 is version 2 without penalty events.
+const OLDEST_READABLE_VERSION: u16 = 1;
+
+/// Reasons a replay file can't be read.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error(
+        "unsupported replay format version {0} (expected {OLDEST_READABLE_VERSION} to {REPLAY_FORMAT_VERSION})"
+    )]
+    UnsupportedVersion(u16),
+    #[error("replay file ends early")]
+    Truncated,
+    #[error("replay file is corrupt: {0}")]
+    Corrupt(&'static str),
+}
+
+// Layout (little endian), version 2:
+//   magic, version: u16, course: str
+//   car count: u8, then per car: name: str, colour: [u8; 3], player: u8
+//   frame count: u32, then per frame: time: f32, then one sample per car
+//   event count: u32, then per event: time: f32, car: u8, kind: u8, payload
+// Strings are a u16 byte length followed by UTF-8. Samples are quantised to keep files
+// small: see `write_sample`.
+
+impl Replay {
+    /// Encodes the replay in the binary replay format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&REPLAY_FORMAT_VERSION.to_le_bytes());
+        write_str(&mut out, &self.course);
+
+        out.push(self.cars.len() as u8);
+        for car in &self.cars {
+            write_str(&mut out, &car.name);
+            out.extend_from_slice(&car.color);
+            out.push(car.player as u8);
+        }
+
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for frame in &self.frames {
+            out.extend_from_slice(&frame.time.to_le_bytes());
+            for index in 0..self.cars.len() {
+                write_sample(
+                    &mut out,
+                    &frame.cars.get(index).cloned().unwrap_or_default(),
+                );
+            }
+        }
+
+        out.extend_from_slice(&(self.events.len() as u32).to_le_bytes());
+        for event in &self.events {
+            out.extend_from_slice(&event.time.to_le_bytes());
+            out.push(event.car as u8);
+            match &event.kind {
+                ReplayEventKind::LapCompleted(lap) => {
+                    out.push(0);
+                    out.extend_from_slice(&lap.to_le_bytes());
+                }
+                ReplayEventKind::Incident(summary) => {
+                    out.push(1);
+                    write_str(&mut out, summary);
+                }
+                ReplayEventKind::RunEnded(cause) => {
+                    out.push(2);
+                    out.push(cause_code(*cause));
+                }
+                ReplayEventKind::Penalty(summary) => {
+                    out.push(3);
+                    write_str(&mut out, summary);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decodes a replay written by `encode`.
+    pub fn decode(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = reader.u16()?;
+        if !(OLDEST_READABLE_VERSION..=REPLAY_FORMAT_VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let course = reader.str()?;
+
+        let car_count = reader.u8()? as usize;
+        let mut cars = Vec::with_capacity(car_count);
+        for _ in 0..car_count {
+            cars.push(ReplayCar {
+                name: reader.str()?,
+                color: [reader.u8()?, reader.u8()?, reader.u8()?],
+                player: reader.u8()? != 0,
+            });
+        }
+
+        let frame_count = reader.u32()? as usize;
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let time = reader.f32()?;
+            let samples = (0..car_count)
+                .map(|_| read_sample(&mut reader))
+                .collect::<Result<_, _>>()?;
+            frames.push(ReplayFrame {
+                time,
+                cars: samples,
+            });
+        }
+
+        let event_count = reader.u32()? as usize;
+        let mut events = Vec::new();
+        for _ in 0..event_count {
+            let time = reader.f32()?;
+            let car = reader.u8()? as usize;
+            let kind = match reader.u8()? {
+                0 => ReplayEventKind::LapCompleted(reader.f32()?),
+                1 => ReplayEventKind::Incident(reader.str()?),
+                2 => ReplayEventKind::RunEnded(cause_from_code(reader.u8()?)?),
+                3 => ReplayEventKind::Penalty(reader.str()?),
+                _ => return Err(ReplayError::Corrupt("unknown event kind")),
+            };
+            events.push(ReplayEvent { time, car, kind });
+        }
+
+        Ok(Replay {
+            course,
+            cars,
+            frames,
+            events,
+        })
+    }
+}
+
+/// Writes one car sample in 40 bytes: the position in full, the rest in fixed point.
+fn write_sample(out: &mut Vec<u8>, sample: &CarSample) {
+    for value in sample.position.to_array() {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in sample.rotation.normalize().to_array() {
+        out.extend_from_slice(&((value * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    out.extend_from_slice(&tenths(sample.speed).to_le_bytes());
+    out.extend_from_slice(&sample.fuel.to_le_bytes());
+    out.extend_from_slice(&tenths(sample.temp).to_le_bytes());
+    out.push(sample.gear as i8 as u8);
+    let flags = sample.throttle as u8 | (sample.brake as u8) << 1 | (sample.drs as u8) << 2;
+    out.push(flags);
+    out.push((sample.steer.clamp(-1.0, 1.0) * i8::MAX as f32) as i8 as u8);
+    out.extend_from_slice(&tenths(sample.cpu_temp).to_le_bytes());
+    out.extend_from_slice(&tenths(sample.gpu_temp).to_le_bytes());
+    out.push(sample.cpu_usage.clamp(0.0, 100.0).round() as u8);
+    out.extend_from_slice(&(sample.cpu_frequency.clamp(0.0, u16::MAX as f32) as u16).to_le_bytes());
+    out.extend_from_slice(&(sample.gpu_clock.clamp(0.0, u16::MAX as f32) as u16).to_le_bytes());
+}
+
+fn read_sample(reader: &mut Reader) -> Result<CarSample, ReplayError> {
+    let position = Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?);
+    let mut rotation = [0.0; 4];
+    for value in &mut rotation {
+        *value = reader.i16()? as f32 / i16::MAX as f32;
+    }
+    let speed = reader.i16()? as f32 / 10.0;
+    let fuel = reader.f32()?;
+    let temp = reader.i16()? as f32 / 10.0;
+    let gear = reader.u8()? as i8 as i32;
+    let flags = reader.u8()?;
+    let steer = reader.u8()? as i8 as f32 / i8::MAX as f32;
+    Ok(CarSample {
+        position,
+        rotation: Quat::from_array(rotation).normalize(),
+        speed,
+        fuel,
+        temp,
+        gear,
+        throttle: flags & 1 != 0,
+        brake: flags & 2 != 0,
+        drs: flags & 4 != 0,
+        steer,
+        cpu_temp: reader.i16()? as f32 / 10.0,
+        gpu_temp: reader.i16()? as f32 / 10.0,
+        cpu_usage: reader.u8()? as f32,
+        cpu_frequency: reader.u16()? as f32,
+        gpu_clock: reader.u16()? as f32,
+    })
+}
+
+/// A value in tenths, as far as an i16 goes.
+fn tenths(value: f32) -> i16 {
+    (value * 10.0)
+        .round()
+        .clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    let bytes = &text.as_bytes()[..text.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn cause_code(cause: GameOverCause) -> u8 {
+    match cause {
+        GameOverCause::None => 0,
+        GameOverCause::GoalReached => 1,
+        GameOverCause::FuelEmpty => 2,
+        GameOverCause::Crash => 3,
+        GameOverCause::Overheat => 4,
+    }
+}
+
+fn cause_from_code(code: u8) -> Result<GameOverCause, ReplayError> {
+    Ok(match code {
+        0 => GameOverCause::None,
+        1 => GameOverCause::GoalReached,
+        2 => GameOverCause::FuelEmpty,
+        3 => GameOverCause::Crash,
+        4 => GameOverCause::Overheat,
+        _ => return Err(ReplayError::Corrupt("unknown run end")),
+    })
+}
+
+/// Reads values off the front of the remaining bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.0.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn i16(&mut self) -> Result<i16, ReplayError> {
+        Ok(i16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn str(&mut self) -> Result<String, ReplayError> {
+        let length = self.u16()? as usize;
+        String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|_| ReplayError::Corrupt("text is not UTF-8"))
+    }
+}
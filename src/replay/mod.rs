@@ -0,0 +1,495 @@
+// Replay Module Definition
+// This module records every run (all cars, their inputs, telemetry and what happened
+// to them), keeps it in a compact binary file and plays it back in the replay viewer.
+pub mod format;
+pub mod viewer;
+
+use crate::car::components::{CarControls, PlayerCar};
+use crate::course::file::file_stem;
+use crate::events::{LapCompleted, PenaltyApplied, RunEnded, TrackLimitsBreached};
+use crate::resources::{CarState, GameOverCause, GameSession, PcStatus};
+use crate::states::{AppState, InGame, Racing};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use viewer::ReplayViewerPlugin;
+
+/// Folder replays are saved in, relative to the working directory.
+const REPLAYS_PATH: &str = "save/replays";
+/// Seconds between recorded frames. Playback interpolates in between.
+const SAMPLE_INTERVAL: f32 = 0.05;
+
+/// A car taking part in a recorded run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayCar {
+    pub name: String,
+    pub color: [u8; 3], // Body colour (sRGB)
+    pub player: bool,
+}
+
+/// Everything recorded about one car at one moment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CarSample {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub speed: f32, // (km/h)
+    pub fuel: f32,
+    pub temp: f32, // Engine temperature (Celsius)
+    pub gear: i32,
+    pub drs: bool,
+    pub throttle: bool,
+    pub brake: bool,
+    pub steer: f32,
+    pub cpu_temp: f32,      // (Celsius)
+    pub gpu_temp: f32,      // (Celsius)
+    pub cpu_usage: f32,     // (%)
+    pub cpu_frequency: f32, // (MHz)
+    pub gpu_clock: f32,     // (MHz)
+}
+
+/// All cars at one moment of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayFrame {
+    pub time: f32,            // Seconds since the start
+    pub cars: Vec<CarSample>, // One per car, in the order of `Replay::cars`
+}
+
+/// Something that happened to a car during the run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEventKind {
+    LapCompleted(f32), // Lap time (s)
+    Incident(String),  // Track limits breach that adds no time, as shown on the HUD
+    RunEnded(GameOverCause),
+    Penalty(String), // Time penalty, as listed on the result screen
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEvent {
+    pub time: f32,  // Seconds since the start
+    pub car: usize, // Index into `Replay::cars`
+    pub kind: ReplayEventKind,
+}
+
+impl ReplayEvent {
+    /// Description for the replay viewer's event log.
+    pub fn describe(&self, replay: &Replay) -> String {
+        let name = replay
+            .cars
+            .get(self.car)
+            .map_or("?", |car| car.name.as_str());
+        let what = match &self.kind {
+            ReplayEventKind::LapCompleted(lap) => format!("lap {:.2}s", lap),
+            ReplayEventKind::Incident(summary) | ReplayEventKind::Penalty(summary) => {
+                summary.clone()
+            }
+            ReplayEventKind::RunEnded(cause) => format!("{:?}", cause),
+        };
+        format!("{:.2}s  {}: {}", self.time, name, what)
+    }
+}
+
+/// A complete recorded run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub course: String, // Name of the course it was driven on
+    pub cars: Vec<ReplayCar>,
+    pub frames: Vec<ReplayFrame>,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Length of the recording (seconds).
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    /// State of a car `time` seconds into the run, between the frames around it.
+    pub fn sample_at(&self, car: usize, time: f32) -> Option<CarSample> {
+        let next = self.frames.partition_point(|frame| frame.time <= time);
+        let after = self.frames.get(next).or(self.frames.last())?;
+        let before = next
+            .checked_sub(1)
+            .map_or(after, |index| &self.frames[index]);
+        let (a, b) = (before.cars.get(car)?, after.cars.get(car)?);
+        let span = after.time - before.time;
+        let t = if span > 0.0 {
+            ((time - before.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Some(CarSample {
+            position: a.position.lerp(b.position, t),
+            rotation: a.rotation.slerp(b.rotation, t),
+            speed: lerp(a.speed, b.speed),
+            fuel: lerp(a.fuel, b.fuel),
+            temp: lerp(a.temp, b.temp),
+            steer: lerp(a.steer, b.steer),
+            cpu_temp: lerp(a.cpu_temp, b.cpu_temp),
+            gpu_temp: lerp(a.gpu_temp, b.gpu_temp),
+            cpu_usage: lerp(a.cpu_usage, b.cpu_usage),
+            cpu_frequency: lerp(a.cpu_frequency, b.cpu_frequency),
+            gpu_clock: lerp(a.gpu_clock, b.gpu_clock),
+            // Discrete values switch over at the later frame
+            ..if t < 1.0 { a.clone() } else { b.clone() }
+        })
+    }
+}
+
+/// The replay shown in the replay viewer: the last run, watched from the result screen,
+/// or a saved one picked from the home screen.
+#[derive(Resource, Debug, Clone)]
+pub struct WatchedReplay {
+    pub replay: Replay,
+    pub back_to: AppState, // Screen the viewer returns to
+}
+
+/// The most recently saved replay, if any can be read.
+pub fn latest_saved_replay() -> Option<Replay> {
+    let path = std::fs::read_dir(REPLAYS_PATH)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "replay"))
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())?
+        .path();
+    let bytes = std::fs::read(&path).ok()?;
+    Replay::decode(&bytes)
+        .map_err(|error| warn!("Ignoring unreadable replay {}: {}", path.display(), error))
+        .ok()
+}
+
+/// The run being recorded, kept between leaving the track and the result screen.
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    clock: f32, // Seconds since the start
+    replay: Replay,
+    index: HashMap<Entity, usize>, // Car entity to its place in `replay.cars`
+    finished: bool,                // The run is over and its last frame recorded
+}
+
+/// Plugin that records every run and lets the player watch it back.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ReplayViewerPlugin)
+            // A fresh recording for every run, restarts included
+            .add_systems(OnEnter(InGame), |mut commands: Commands| {
+                commands.insert_resource(ReplayRecorder::default());
+            })
+            // Events from the countdown on, so a jump start is recorded too
+            .add_systems(
+                PostUpdate,
+                (
+                    record_replay_events.run_if(in_state(InGame)),
+                    replay_recording_system.run_if(in_state(Racing)),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(AppState::Result), save_replay);
+    }
+}
+
+impl ReplayRecorder {
+    /// Place of a car in the recording, adding the car the first time it is seen.
+    fn car(
+        &mut self,
+        entity: Entity,
+        name: &Name,
+        material: &MeshMaterial3d<StandardMaterial>,
+        player: bool,
+        materials: &Assets<StandardMaterial>,
+    ) -> usize {
+        *self.index.entry(entity).or_insert_with(|| {
+            let color = materials
+                .get(&material.0)
+                .map_or(Color::WHITE, |material| material.base_color)
+                .to_srgba()
+                .to_u8_array_no_alpha();
+            self.replay.cars.push(ReplayCar {
+                name: name.to_string(),
+                color,
+                player,
+            });
+            self.replay.cars.len() - 1
+        })
+    }
+}
+
+/// Records what happens to every car from the game events: laps, penalties, track limits
+/// breaches and the end of its run.
+#[allow(clippy::type_complexity)]
+fn record_replay_events(
+    materials: Res<Assets<StandardMaterial>>,
+    mut recorder: ResMut<ReplayRecorder>,
+    cars: Query<(&Name, &MeshMaterial3d<StandardMaterial>, Has<PlayerCar>)>,
+    mut laps: MessageReader<LapCompleted>,
+    mut penalties: MessageReader<PenaltyApplied>,
+    mut breaches: MessageReader<TrackLimitsBreached>,
+    mut run_ends: MessageReader<RunEnded>,
+) {
+    let events = laps
+        .read()
+        .map(|lap| (lap.car, ReplayEventKind::LapCompleted(lap.time)))
+        .chain(
+            penalties
+                .read()
+                .map(|event| (event.car, ReplayEventKind::Penalty(event.penalty.summary()))),
+        )
+        .chain(breaches.read().map(|event| {
+            (
+                event.car,
+                ReplayEventKind::Incident(event.incident.summary()),
+            )
+        }))
+        .chain(
+            run_ends
+                .read()
+                .map(|end| (end.car, ReplayEventKind::RunEnded(end.cause))),
+        );
+    for (entity, kind) in events {
+        let Ok((name, material, player)) = cars.get(entity) else {
+            continue;
+        };
+        let car = recorder.car(entity, name, material, player, &materials);
+        let time = recorder.clock;
+        recorder.replay.events.push(ReplayEvent { time, car, kind });
+    }
+}
+
+/// Records a sample of every car each `SAMPLE_INTERVAL`, and one last frame when the run
+/// is over.
+#[allow(clippy::type_complexity)]
+fn replay_recording_system(
+    time: Res<Time>,
+    session: Res<GameSession>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut recorder: ResMut<ReplayRecorder>,
+    cars: Query<(
+        Entity,
+        &Name,
+        &Transform,
+        &CarState,
+        &CarControls,
+        &PcStatus,
+        &MeshMaterial3d<StandardMaterial>,
+        Has<PlayerCar>,
+    )>,
+) {
+    if recorder.finished {
+        return;
+    }
+    // One last frame with how the run ended
+    recorder.finished = session.is_game_over;
+    recorder.replay.course.clone_from(&session.course_name);
+    recorder.clock += time.delta_secs();
+    let clock = recorder.clock;
+    let sample = recorder
+        .replay
+        .frames
+        .last()
+        .is_none_or(|last| clock - last.time >= SAMPLE_INTERVAL || session.is_game_over);
+    let mut samples = vec![CarSample::default(); recorder.replay.cars.len()];
+
+    for (entity, name, transform, state, controls, pc, material, player) in &cars {
+        let car = recorder.car(entity, name, material, player, &materials);
+        if samples.len() <= car {
+            samples.resize(car + 1, CarSample::default());
+        }
+        samples[car] = CarSample {
+            position: transform.translation,
+            rotation: transform.rotation,
+            speed: state.speed,
+            fuel: state.fuel,
+            temp: state.temp,
+            gear: state.gear,
+            drs: state.drs_enabled,
+            throttle: controls.throttle,
+            brake: controls.brake,
+            steer: controls.steer,
+            cpu_temp: pc.cpu_temp,
+            gpu_temp: pc.gpu_temp,
+            cpu_usage: pc.cpu_usage,
+            cpu_frequency: pc.cpu_frequency as f32,
+            gpu_clock: pc.gpu_clock,
+        };
+    }
+
+    if sample {
+        recorder.replay.frames.push(ReplayFrame {
+            time: clock,
+            cars: samples,
+        });
+    }
+}
+
+/// Saves the finished run's replay, named after the course and the millisecond it was
+/// saved so no two runs share a file, and keeps it for the replay viewer.
+fn save_replay(mut commands: Commands, recorder: Option<Res<ReplayRecorder>>) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    commands.remove_resource::<ReplayRecorder>();
+    let replay = recorder.replay.clone();
+    let driven_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+    let path = Path::new(REPLAYS_PATH).join(format!(
+        "{}_{}.replay",
+        file_stem(&replay.course),
+        driven_at
+    ));
+    let bytes = replay.encode();
+    let result = std::fs::create_dir_all(REPLAYS_PATH).and_then(|_| std::fs::write(&path, &bytes));
+    match result {
+        Ok(()) => info!(
+            "Saved replay to {} ({} KB)",
+            path.display(),
+            bytes.len() / 1024
+        ),
+        Err(error) => warn!("Could not save replay to {}: {}", path.display(), error),
+    }
+    commands.insert_resource(WatchedReplay {
+        replay,
+        back_to: AppState::Result,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{CarProgress, PenaltyKind};
+
+    fn sample(x: f32, gear: i32) -> CarSample {
+        CarSample {
+            position: Vec3::new(x, 1.0, -2.5),
+            rotation: Quat::from_rotation_y(0.5),
+            speed: 123.4,
+            fuel: 42.25,
+            temp: 180.3,
+            gear,
+            drs: true,
+            throttle: true,
+            brake: false,
+            steer: -1.0,
+            cpu_temp: 65.2,
+            gpu_temp: 58.9,
+            cpu_usage: 37.0,
+            cpu_frequency: 4200.0,
+            gpu_clock: 1800.0,
+        }
+    }
+
+    fn replay() -> Replay {
+        Replay {
+            course: "Course 1".to_string(),
+            cars: vec![ReplayCar {
+                name: "You".to_string(),
+                color: [230, 25, 25],
+                player: true,
+            }],
+            frames: vec![
+                ReplayFrame {
+                    time: 0.0,
+                    cars: vec![sample(0.0, 1)],
+                },
+                ReplayFrame {
+                    time: 1.0,
+                    cars: vec![sample(10.0, 2)],
+                },
+            ],
+            events: vec![
+                ReplayEvent {
+                    time: 0.0,
+                    car: 0,
+                    kind: ReplayEventKind::Penalty("Jump start at 0.00s: +5.0s".to_string()),
+                },
+                ReplayEvent {
+                    time: 0.6,
+                    car: 0,
+                    kind: ReplayEventKind::Incident("Wrong way".to_string()),
+                },
+                ReplayEvent {
+                    time: 1.0,
+                    car: 0,
+                    kind: ReplayEventKind::RunEnded(GameOverCause::GoalReached),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn playback_interpolates_between_frames() {
+        let replay = replay();
+        let middle = replay.sample_at(0, 0.25).unwrap();
+        assert_eq!(middle.position.x, 2.5);
+        assert_eq!(middle.gear, 1);
+        assert_eq!(replay.sample_at(0, 5.0).unwrap().gear, 2);
+        assert!(replay.sample_at(1, 0.5).is_none());
+        assert_eq!(replay.duration(), 1.0);
+    }
+
+    #[test]
+    fn replays_survive_the_binary_format() {
+        let replay = replay();
+        let bytes = replay.encode();
+        let loaded = Replay::decode(&bytes).unwrap();
+        assert_eq!(loaded.course, replay.course);
+        assert_eq!(loaded.cars, replay.cars);
+        assert_eq!(loaded.events, replay.events);
+        let (a, b) = (&replay.frames[1].cars[0], &loaded.frames[1].cars[0]);
+        assert_eq!(b.position, a.position);
+        assert!(b.rotation.angle_between(a.rotation) < 0.001);
+        assert!((b.speed - a.speed).abs() < 0.1 && (b.temp - a.temp).abs() < 0.1);
+        assert_eq!((b.gear, b.drs, b.throttle, b.brake), (2, true, true, false));
+        assert_eq!(b.steer, -1.0);
+
+        assert!(matches!(
+            Replay::decode(&bytes[..bytes.len() - 1]),
+            Err(format::ReplayError::Truncated)
+        ));
+        let mut newer = bytes.clone();
+        newer[4] = 9;
+        assert!(matches!(
+            Replay::decode(&newer),
+            Err(format::ReplayError::UnsupportedVersion(9))
+        ));
+    }
+
+    #[test]
+    fn penalties_before_the_start_are_recorded() {
+        let mut app = App::new();
+        app.add_message::<LapCompleted>()
+            .add_message::<PenaltyApplied>()
+            .add_message::<TrackLimitsBreached>()
+            .add_message::<RunEnded>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .init_resource::<ReplayRecorder>()
+            .add_systems(Update, record_replay_events);
+        let car = app
+            .world_mut()
+            .spawn((
+                Name::new("You"),
+                MeshMaterial3d::<StandardMaterial>::default(),
+                PlayerCar,
+            ))
+            .id();
+        let mut progress = CarProgress::default();
+        let penalty = progress.penalise(PenaltyKind::JumpStart, 5.0);
+        app.world_mut()
+            .write_message(PenaltyApplied { car, penalty });
+        app.update();
+
+        let replay = &app.world().resource::<ReplayRecorder>().replay;
+        assert!(replay.cars[0].player);
+        assert_eq!(
+            replay.events,
+            [ReplayEvent {
+                time: 0.0,
+                car: 0,
+                kind: ReplayEventKind::Penalty(penalty.summary()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use super::{CarSample, WatchedReplay};
+use crate::course::Course;
+use crate::game::GameWorld;
+use crate::resources::REVERSE_GEAR;
+use crate::scenery::{GraphicsQuality, spawn_scenery};
+use crate::states::AppState;
+use bevy::prelude::*;
+
+/// Playback speeds, cycled with the up and down arrows: real time, then slow motion.
+const SPEEDS: [f32; 4] = [1.0, 0.5, 0.25, 0.1];
+/// Replay seconds skipped per second the scrub keys are held.
+const SCRUB_RATE: f32 = 10.0;
+/// Spacing of the trackside cameras along the course (m).
+const TRACKSIDE_SPACING: f32 = 120.0;
+/// How far beyond the edge of the run-off and how high the trackside cameras stand (m).
+const TRACKSIDE_OFFSET: Vec2 = Vec2::new(5.0, 6.0);
+/// Events listed under the telemetry, most recent last.
+const EVENT_LOG_LINES: usize = 5;
+
+/// Where the replay is watched from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplayCamera {
+    Chase,     // Behind the followed car, like when driving
+    Trackside, // The nearest camera beside the track, turning to follow the car
+}
+
+/// State of the replay viewer.
+#[derive(Resource)]
+struct ReplayPlayback {
+    time: f32, // Seconds into the replay
+    playing: bool,
+    speed: usize, // Index into `SPEEDS`
+    camera: ReplayCamera,
+    followed: usize,      // Car the camera and telemetry follow
+    trackside: Vec<Vec3>, // Trackside camera positions
+}
+
+/// A car in the replay, with its index in the replay's cars.
+#[derive(Component)]
+struct ReplayCarBody(usize);
+
+/// Time, telemetry and events of the followed car.
+#[derive(Component)]
+struct ReplayInfoText;
+
+/// Filled part of the timeline bar.
+#[derive(Component)]
+struct TimelineFill;
+
+/// Plugin for the replay viewer: play, pause, scrub and slow down a recorded run, watched
+/// from behind any car or from beside the track.
+pub struct ReplayViewerPlugin;
+
+impl Plugin for ReplayViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Replay), setup_replay_viewer)
+            .add_systems(
+                Update,
+                (
+                    replay_controls_system,
+                    replay_playback_system,
+                    replay_hud_system,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Replay).and(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(
+                PostUpdate,
+                replay_camera_system
+                    .run_if(in_state(AppState::Replay).and(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(OnExit(AppState::Replay), |mut commands: Commands| {
+                commands.remove_resource::<ReplayPlayback>();
+            });
+    }
+}
+
+/// Puts the course and the recorded cars back on screen. Everything is `GameWorld`, so it
+/// goes when the viewer is left.
+#[allow(clippy::too_many_arguments)]
+fn setup_replay_viewer(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    replay: Option<Res<WatchedReplay>>,
+    courses: Res<Assets<Course>>,
+    quality: Res<GraphicsQuality>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(watched) = replay else {
+        next_state.set(AppState::Home);
+        return;
+    };
+    let replay = &watched.replay;
+    let Some(course) = courses
+        .iter()
+        .map(|(_, course)| course)
+        .find(|course| course.name == replay.course)
+        .cloned()
+    else {
+        warn!("Course {} of the replay is not loaded", replay.course);
+        next_state.set(watched.back_to);
+        return;
+    };
+
+    spawn_scenery(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &course,
+        *quality,
+    );
+    let body = meshes.add(Cuboid::new(2.0, 1.0, 4.0));
+    for (index, car) in replay.cars.iter().enumerate() {
+        let [r, g, b] = car.color;
+        let start = replay.sample_at(index, 0.0).unwrap_or_default();
+        commands.spawn((
+            Mesh3d(body.clone()),
+            MeshMaterial3d(materials.add(Color::srgb_u8(r, g, b))),
+            Transform::from_translation(start.position).with_rotation(start.rotation),
+            ReplayCarBody(index),
+            GameWorld,
+        ));
+    }
+
+    // Trackside cameras on alternating sides of the course
+    let side_offset = course.half_width() + TRACKSIDE_OFFSET.x;
+    let count = (course.length() / TRACKSIDE_SPACING).ceil() as usize;
+    let trackside = (0..count)
+        .map(|index| {
+            let frame = course.frame_at(index as f32 * TRACKSIDE_SPACING);
+            let side = if index % 2 == 0 { 1.0 } else { -1.0 };
+            frame.point(side * side_offset) + frame.normal * TRACKSIDE_OFFSET.y
+        })
+        .collect();
+
+    commands.insert_resource(ReplayPlayback {
+        time: 0.0,
+        playing: true,
+        speed: 0,
+        camera: ReplayCamera::Chase,
+        followed: replay.cars.iter().position(|car| car.player).unwrap_or(0),
+        trackside,
+    });
+    commands.insert_resource(course);
+    setup_replay_hud(&mut commands, &asset_server);
+}
+
+fn setup_replay_hud(commands: &mut Commands, asset_server: &AssetServer) {
+    let font = TextFont {
+        font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+
+    // Top left - time, telemetry and events
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                font.clone(),
+                TextColor(Color::WHITE),
+                ReplayInfoText,
+            ));
+        });
+
+    // Bottom - timeline and controls
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            GameWorld,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(80.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.12, 0.8)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.9, 0.9, 0.9)),
+                        TimelineFill,
+                    ));
+                });
+            parent.spawn((
+                Text::new(
+                    "Space Play/Pause | Left/Right Scrub | Up/Down Slow Motion | C Camera | Tab Next Car | Esc Back",
+                ),
+                font,
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn replay_controls_system(
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    watched: Res<WatchedReplay>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let replay = &watched.replay;
+    let duration = replay.duration();
+    if input.just_pressed(KeyCode::Space) {
+        // Playing again from the end starts over
+        if !playback.playing && playback.time >= duration {
+            playback.time = 0.0;
+        }
+        playback.playing = !playback.playing;
+    }
+    let scrub = SCRUB_RATE * time.delta_secs();
+    if input.pressed(KeyCode::ArrowLeft) {
+        playback.time = (playback.time - scrub).max(0.0);
+    }
+    if input.pressed(KeyCode::ArrowRight) {
+        playback.time = (playback.time + scrub).min(duration);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        playback.speed = (playback.speed + 1).min(SPEEDS.len() - 1);
+    }
+    if input.just_pressed(KeyCode::ArrowUp) {
+        playback.speed = playback.speed.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::KeyC) {
+        playback.camera = match playback.camera {
+            ReplayCamera::Chase => ReplayCamera::Trackside,
+            ReplayCamera::Trackside => ReplayCamera::Chase,
+        };
+    }
+    if input.just_pressed(KeyCode::Tab) && !replay.cars.is_empty() {
+        playback.followed = (playback.followed + 1) % replay.cars.len();
+    }
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(watched.back_to);
+    }
+}
+
+/// Moves the replay on and puts every car where it was at that moment.
+fn replay_playback_system(
+    time: Res<Time>,
+    watched: Res<WatchedReplay>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut cars: Query<(&mut Transform, &ReplayCarBody)>,
+) {
+    let replay = &watched.replay;
+    let duration = replay.duration();
+    if playback.playing {
+        playback.time += time.delta_secs() * SPEEDS[playback.speed];
+        if playback.time >= duration {
+            playback.time = duration;
+            playback.playing = false;
+        }
+    }
+    for (mut transform, car) in &mut cars {
+        if let Some(sample) = replay.sample_at(car.0, playback.time) {
+            transform.translation = sample.position;
+            transform.rotation = sample.rotation;
+        }
+    }
+}
+
+fn replay_hud_system(
+    watched: Res<WatchedReplay>,
+    playback: Res<ReplayPlayback>,
+    mut info: Query<&mut Text, With<ReplayInfoText>>,
+    mut timeline: Query<&mut Node, With<TimelineFill>>,
+) {
+    let replay = &watched.replay;
+    let duration = replay.duration();
+    for mut node in &mut timeline {
+        node.width = Val::Percent(if duration > 0.0 {
+            playback.time / duration * 100.0
+        } else {
+            0.0
+        });
+    }
+
+    let Some(car) = replay.cars.get(playback.followed) else {
+        return;
+    };
+    let sample = replay
+        .sample_at(playback.followed, playback.time)
+        .unwrap_or_default();
+    let status = if playback.playing { "" } else { "  PAUSED" };
+    let camera = match playback.camera {
+        ReplayCamera::Chase => "Chase",
+        ReplayCamera::Trackside => "Trackside",
+    };
+    let events: Vec<String> = replay
+        .events
+        .iter()
+        .filter(|event| event.time <= playback.time)
+        .rev()
+        .take(EVENT_LOG_LINES)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .map(|event| event.describe(replay))
+        .collect();
+    for mut text in &mut info {
+        text.0 = format!(
+            "REPLAY {:.2} / {:.2}  x{}{}\n{} ({} camera)\n{}\n\n{}",
+            playback.time,
+            duration,
+            SPEEDS[playback.speed],
+            status,
+            car.name,
+            camera,
+            telemetry(&sample),
+            events.join("\n")
+        );
+    }
+}
+
+/// Dashboard and hardware readings of a car, as recorded.
+fn telemetry(sample: &CarSample) -> String {
+    format!(
+        "Speed: {:.1} km/h | Gear: {} | DRS: {}\nFuel: {:.1} | Temp: {:.1} C\nThrottle: {} | Brake: {} | Steer: {:+.2}\nCPU: {:.0} MHz {:.0}% {:.1} C | GPU: {:.0} MHz {:.1} C",
+        sample.speed,
+        if sample.gear == REVERSE_GEAR {
+            "R".to_string()
+        } else {
+            sample.gear.to_string()
+        },
+        if sample.drs { "ON" } else { "OFF" },
+        sample.fuel,
+        sample.temp,
+        if sample.throttle { "ON" } else { "OFF" },
+        if sample.brake { "ON" } else { "OFF" },
+        sample.steer,
+        sample.cpu_frequency,
+        sample.cpu_usage,
+        sample.cpu_temp,
+        sample.gpu_clock,
+        sample.gpu_temp,
+    )
+}
+
+/// Points the camera at the followed car, from behind it or from the nearest trackside
+/// camera. Runs in PostUpdate, after the cars have moved.
+fn replay_camera_system(
+    time: Res<Time>,
+    playback: Res<ReplayPlayback>,
+    mut camera: Query<&mut Transform, (With<Camera3d>, Without<ReplayCarBody>)>,
+    cars: Query<(&Transform, &ReplayCarBody)>,
+) {
+    let Ok(mut camera) = camera.single_mut() else {
+        return;
+    };
+    let Some((car, _)) = cars.iter().find(|(_, car)| car.0 == playback.followed) else {
+        return;
+    };
+    let target = car.translation + Vec3::Y;
+    match playback.camera {
+        ReplayCamera::Chase => {
+            // Same view and smoothing as when driving
+            let position = car.translation + car.rotation * Vec3::new(0.0, 5.0, 15.0);
+            let lerp_factor = 1.0 - (-8.0 * time.delta_secs()).exp();
+            camera.translation = camera.translation.lerp(position, lerp_factor);
+        }
+        ReplayCamera::Trackside => {
+            if let Some(position) = playback.trackside.iter().min_by(|a, b| {
+                a.distance_squared(car.translation)
+                    .total_cmp(&b.distance_squared(car.translation))
+            }) {
+                camera.translation = *position;
+            }
+        }
+    }
+    camera.look_at(target, Vec3::Y);
+}
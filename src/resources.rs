@@ -16,8 +16,9 @@ impl Default for PcMonitor {
 }
 
 /// Defines the various states the game can end in.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum GameOverCause {
+    #[default]
     None,
     GoalReached, // Victory
     FuelEmpty,   // Defeat
@@ -25,46 +26,279 @@ pub enum GameOverCause {
     Overheat,    // Defeat
 }
 
-impl Default for GameOverCause {
-    fn default() -> Self {
-        Self::None
+/// Gear number used for reverse. Forward gears are 1 to 6.
+pub const REVERSE_GEAR: i32 = 0;
+
+/// Accumulated crash damage of the car.
+/// Each part ranges from 0.0 (intact) to 1.0 (destroyed) and only affects the stats it
+/// is responsible for. Whether the car is wrecked is decided by `impact`, the uncapped
+/// sum of every hit, so the outcome doesn't depend on which parts the hits landed on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CarDamage {
+    pub aero: f32,     // Reduces the DRS boost and grip
+    pub steering: f32, // Reduces handling
+    pub engine: f32,   // Reduces acceleration and max speed
+    pub impact: f32,   // Raw accumulated impact damage (not capped)
+}
+
+/// Time taken for one completed lap.
+#[derive(Debug, Clone, Default)]
+pub struct LapTime {
+    pub time: f32,         // Lap time (seconds)
+    pub sectors: Vec<f32>, // Time of every sector of the lap (seconds)
+    pub valid: bool,       // False if the lap was invalidated by a track limits breach
+}
+
+/// Lap and sector timing of a run. A lap is split into sectors by timing gates (the
+/// course's checkpoints, then the finish line) which have to be passed in order.
+#[derive(Debug, Clone, Default)]
+pub struct LapTiming {
+    pub next_gate: usize,            // Index of the next gate to pass this lap
+    pub lap_started: f32,            // Run time at which the current lap started (s)
+    pub sector_started: f32,         // Run time at which the current sector started (s)
+    pub current_sectors: Vec<f32>,   // Sector times of the lap in progress (s)
+    pub laps: Vec<LapTime>,          // Completed laps, in order
+    pub splits: Vec<f32>,            // Run time at every gate passed so far (s)
+    pub missed_gate: Option<usize>,  // Gate skipped this lap, until it's driven through
+    pub lap_invalid: bool,           // The lap in progress has been invalidated
+    pub last_position: Option<Vec3>, // Car position when the gates were last checked
+}
+
+impl LapTiming {
+    /// Records passing the next gate at run time `time`, out of `gate_count` gates a lap.
+    /// Returns the lap if this gate was the finish line and completed it.
+    pub fn pass_gate(&mut self, time: f32, gate_count: usize) -> Option<&LapTime> {
+        self.current_sectors.push(time - self.sector_started);
+        self.splits.push(time);
+        self.sector_started = time;
+        self.missed_gate = None;
+        self.next_gate += 1;
+        if self.next_gate < gate_count {
+            return None;
+        }
+        self.laps.push(LapTime {
+            time: time - self.lap_started,
+            sectors: std::mem::take(&mut self.current_sectors),
+            valid: !self.lap_invalid,
+        });
+        self.lap_started = time;
+        self.lap_invalid = false;
+        self.next_gate = 0;
+        self.laps.last()
+    }
+
+    /// Fastest valid lap (seconds).
+    pub fn best_lap(&self) -> Option<f32> {
+        self.laps
+            .iter()
+            .filter(|lap| lap.valid)
+            .map(|lap| lap.time)
+            .reduce(f32::min)
+    }
+
+    /// True if no lap of the run has been invalidated.
+    pub fn all_laps_valid(&self) -> bool {
+        !self.lap_invalid && self.laps.iter().all(|lap| lap.valid)
     }
 }
 
-/// Resource storing the state of the current racing session.
-#[derive(Resource)]
-pub struct GameSession {
-    pub play_time: f32,         // Total time elapsed (seconds)
-    pub current_speed: f32,     // Current speed in km/h
-    pub current_fuel: f32,      // Current fuel remaining (absolute units)
-    pub current_gear: i32,      // Current manual gear (1-6)
-    pub current_temp: f32,      // Accumulated CPU + GPU temperature (Celsius)
-    pub drs_enabled: bool,      // Whether the Drag Reduction System (DRS) is active
-    pub distance_traveled: f32, // Total distance driven in meters
-    pub course_length: f32,     // Target distance to reach the goal
-    pub is_game_over: bool,     // Flag to pause logic when game ends
-    pub game_over_cause: GameOverCause,
+/// Kind of track rule breach.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IncidentKind {
+    Cut { gained: f32 }, // Left the track and came back further along (m gained)
+    WrongWay,            // Drove against the direction of the course
 }
 
-impl Default for GameSession {
-    fn default() -> Self {
+/// A breach of the track rules that adds no time, kept for the result screen. Breaches
+/// that cost time are recorded in `Penalties`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackIncident {
+    pub time: f32, // Run time when it happened (seconds)
+    pub lap: u32,  // Lap it happened on (1-based)
+    pub kind: IncidentKind,
+    pub lap_invalidated: bool, // Whether it cost the lap
+}
+
+impl TrackIncident {
+    /// Short description for the HUD and the result screen.
+    pub fn summary(&self) -> String {
+        let what = match self.kind {
+            IncidentKind::Cut { gained } => format!("Cut ({:.0} m gained)", gained),
+            IncidentKind::WrongWay => "Wrong way".to_string(),
+        };
+        if self.lap_invalidated {
+            format!("{} - lap invalidated", what)
+        } else {
+            what
+        }
+    }
+}
+
+/// Kind of infraction that costs time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PenaltyKind {
+    CourseOut, // Off the course with no wheel on the track, charged while it lasts
+    Cut,       // Gained track distance by cutting a corner
+    JumpStart, // Went before the start lights turned green
+    Reset,     // Put back on the track at the last checkpoint (R)
+}
+
+impl PenaltyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PenaltyKind::CourseOut => "Course out",
+            PenaltyKind::Cut => "Corner cut",
+            PenaltyKind::JumpStart => "Jump start",
+            PenaltyKind::Reset => "Reset",
+        }
+    }
+}
+
+/// One infraction and the time it cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Penalty {
+    pub kind: PenaltyKind,
+    pub start: f32,    // Run time when the infraction started (seconds)
+    pub duration: f32, // How long it lasted (seconds, 0 for a single event)
+    pub seconds: f32,  // Seconds added to the run time
+}
+
+impl Penalty {
+    /// Line for the result screen.
+    pub fn summary(&self) -> String {
+        let lasted = if self.duration > 0.0 {
+            format!(" for {:.1}s", self.duration)
+        } else {
+            String::new()
+        };
+        format!(
+            "{} at {:.2}s{}: +{:.1}s",
+            self.kind.label(),
+            self.start,
+            lasted,
+            self.seconds
+        )
+    }
+}
+
+/// Every penalty of a run. A car that runs up too much of it crashes out (Specification
+/// rule 94).
+#[derive(Debug, Clone, Default)]
+pub struct Penalties {
+    pub list: Vec<Penalty>,
+    course_out: Option<usize>, // Index of the course out penalty still being charged
+}
+
+impl Penalties {
+    /// Records an infraction charged at once.
+    pub fn add(&mut self, kind: PenaltyKind, time: f32, seconds: f32) -> Penalty {
+        let penalty = Penalty {
+            kind,
+            start: time,
+            duration: 0.0,
+            seconds,
+        };
+        self.list.push(penalty);
+        penalty
+    }
+
+    /// Charges another `dt` seconds off the course, carrying on the course out penalty
+    /// already running or starting one at `time`.
+    pub fn course_out(&mut self, time: f32, dt: f32, seconds: f32) {
+        let index = *self.course_out.get_or_insert_with(|| {
+            self.list.push(Penalty {
+                kind: PenaltyKind::CourseOut,
+                start: time,
+                duration: 0.0,
+                seconds: 0.0,
+            });
+            self.list.len() - 1
+        });
+        let penalty = &mut self.list[index];
+        penalty.duration += dt;
+        penalty.seconds += seconds;
+    }
+
+    /// The car is back on the track: the next course out is a new penalty. Returns the
+    /// course out penalty this ends, if one was running.
+    pub fn back_on_track(&mut self) -> Option<Penalty> {
+        self.course_out.take().map(|index| self.list[index])
+    }
+
+    /// Seconds added to the run time so far.
+    pub fn total(&self) -> f32 {
+        self.list.iter().map(|penalty| penalty.seconds).sum()
+    }
+}
+
+/// Running state of a car: what its dashboard shows.
+#[derive(Component, Debug, Clone)]
+pub struct CarState {
+    pub speed: f32,        // Current speed in km/h
+    pub fuel: f32,         // Fuel remaining (absolute units)
+    pub gear: i32,         // Current manual gear (REVERSE_GEAR or 1-6)
+    pub temp: f32,         // Accumulated CPU + GPU temperature (Celsius)
+    pub drs_enabled: bool, // Whether the Drag Reduction System (DRS) is active
+    pub damage: CarDamage, // Damage taken from collisions this run
+}
+
+impl CarState {
+    /// A car on the grid with a full tank of `fuel`.
+    pub fn new(fuel: f32) -> Self {
         Self {
-            play_time: 0.0,
-            current_speed: 0.0,
-            current_fuel: 100.0,
-            current_gear: 1,
-            current_temp: 60.0,
+            speed: 0.0,
+            fuel,
+            gear: 1,
+            temp: 60.0,
             drs_enabled: false,
-            distance_traveled: 0.0,
-            course_length: 5000.0,
-            is_game_over: false,
-            game_over_cause: GameOverCause::None,
+            damage: CarDamage::default(),
+        }
+    }
+}
+
+/// How far a car has got through its run and what happened on the way.
+#[derive(Component, Debug, Clone, Default)]
+pub struct CarProgress {
+    pub time: f32,                     // Run time of this car, penalties included (seconds)
+    pub distance: f32,                 // Distance along the course centreline reached (m)
+    pub timing: LapTiming,             // Lap and sector times of this run
+    pub wrong_way: bool,               // True while the car is driving against the course direction
+    pub incidents: Vec<TrackIncident>, // Track rule breaches this run
+    pub penalties: Penalties,          // Time penalties this run, all included in `time`
+    pub ended: Option<GameOverCause>,  // Why the car's run is over (finished, out of fuel, ...)
+}
+
+impl CarProgress {
+    /// A car about to start from a distance along the track.
+    pub fn at(distance: f32) -> Self {
+        Self {
+            distance,
+            ..default()
         }
     }
+
+    /// Adds a penalty to the run time and records it.
+    pub fn penalise(&mut self, kind: PenaltyKind, seconds: f32) -> Penalty {
+        let penalty = self.penalties.add(kind, self.time, seconds);
+        self.time += seconds;
+        penalty
+    }
+}
+
+/// Resource storing the state of the current racing session. Everything about a single
+/// car lives on the car entity (`CarState`, `CarProgress`, ...).
+#[derive(Resource, Default)]
+pub struct GameSession {
+    pub course_name: String, // Name of the course being driven
+    pub total_laps: u32,     // Laps to complete for the goal (1 on point-to-point courses)
+    pub run: CarProgress, // The player's run, copied from their car when it ends (for the result)
+    pub is_game_over: bool, // Flag to pause logic when game ends
+    pub game_over_cause: GameOverCause,
 }
 
 /// Resource storing raw telemetry data captured from the PC hardware.
-#[derive(Resource, Default, Debug, Clone)]
+/// Every car also carries its own copy as a component: the hardware driving that car.
+#[derive(Resource, Component, Default, Debug, Clone)]
 pub struct PcStatus {
     pub total_memory: u64,  // Total RAM (Bytes)
     pub used_memory: u64,   // Currently used RAM (Bytes)
@@ -87,9 +321,10 @@ pub struct PcStatus {
     pub ssd_available: u64,
 }
 
-/// Resource storing the dynamic attributes of the player car.
-/// These values are re-calculated every frame based on PcStatus.
-#[derive(Resource, Default, Debug, Clone)]
+/// Dynamic attributes of a car, re-calculated every frame from its `PcStatus`.
+/// The resource holds the player's car as measured on the setup screens; every car
+/// entity carries its own as a component.
+#[derive(Resource, Component, Default, Debug, Clone)]
 pub struct CarStatus {
     pub max_speed: f32,        // Top speed limit (km/h)
     pub fuel_capacity: f32,    // Fuel tank size
@@ -104,9 +339,9 @@ pub struct CarStatus {
     pub drs_max_speed: f32,    // Enhanced top speed when DRS is ON
 }
 
-/// Resource storing the base design specs of the car.
-/// The formulas in specification.md use these as the reference points.
-#[derive(Resource, Debug, Clone)]
+/// Base design specs of a car (the resource is the player's pick, each car entity
+/// carries its own). The formulas in specification.md use these as the reference points.
+#[derive(Resource, Component, Debug, Clone)]
 pub struct BaseCarStatus {
     // Impact Rates (Sensitivity to hardware stats, sum to 100 as per Spec)
     pub cpu_impact: f32,
@@ -147,3 +382,34 @@ impl Default for BaseCarStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn course_out_is_one_penalty_until_back_on_track() {
+        let mut run = CarProgress::default();
+        for _ in 0..10 {
+            run.penalties.course_out(run.time, 0.1, 0.2);
+            run.time += 0.3;
+        }
+        assert!(run.penalties.back_on_track().is_some());
+        run.penalise(PenaltyKind::Cut, 5.0);
+        run.penalties.course_out(run.time, 0.1, 0.2);
+
+        let kinds: Vec<_> = run.penalties.list.iter().map(|p| p.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                PenaltyKind::CourseOut,
+                PenaltyKind::Cut,
+                PenaltyKind::CourseOut
+            ]
+        );
+        let first = run.penalties.list[0];
+        assert_eq!(first.start, 0.0);
+        assert!((first.duration - 1.0).abs() < 1e-4);
+        assert!((run.penalties.total() - 7.2).abs() < 1e-4);
+    }
+}
@@ -1,5 +1,9 @@
+use crate::editor::TestDrive;
+use crate::game::PENALTY_CRASH_THRESHOLD;
+use crate::ghost::export_ghost;
+use crate::race::RaceStandings;
 use crate::resources::{GameOverCause, GameSession};
-use crate::states::AppState;
+use crate::states::{AppState, GameMode};
 use crate::ui::styles::{
     HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
     get_title_text_font,
@@ -12,6 +16,15 @@ struct ResultUi;
 #[derive(Component)]
 struct HomeButton;
 
+#[derive(Component)]
+struct ReplayButton;
+
+#[derive(Component)]
+struct ExportGhostButton;
+
+#[derive(Component)]
+struct ExportGhostLabel;
+
 pub struct ResultPlugin;
 
 impl Plugin for ResultPlugin {
@@ -20,22 +33,96 @@ impl Plugin for ResultPlugin {
             .add_systems(OnExit(AppState::Result), cleanup_result)
             .add_systems(
                 Update,
-                interact_home_button.run_if(in_state(AppState::Result)),
+                (
+                    interact_home_button,
+                    interact_replay_button,
+                    interact_export_ghost,
+                )
+                    .run_if(in_state(AppState::Result)),
             );
     }
 }
 
-fn setup_result(mut commands: Commands, asset_server: Res<AssetServer>, session: Res<GameSession>) {
+fn setup_result(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    session: Res<GameSession>,
+    test_drive: Res<TestDrive>,
+    mode: Res<GameMode>,
+    standings: Res<RaceStandings>,
+) {
+    let race = *mode == GameMode::Race;
     let result_text = match session.game_over_cause {
+        GameOverCause::GoalReached if race => format!(
+            "Finished P{} of {}! Time: {:.2}s",
+            standings.player_position().unwrap_or_default(),
+            standings.0.len(),
+            session.run.time
+        ),
         GameOverCause::GoalReached => {
-            format!("Example! Time: {:.2}s", session.play_time)
+            format!("Example! Time: {:.2}s", session.run.time)
         }
         GameOverCause::FuelEmpty => "Game Over: Out of Fuel".to_string(),
-        GameOverCause::Crash => "Game Over: Crashed (Course Out)".to_string(),
+        GameOverCause::Crash if session.run.penalties.total() > PENALTY_CRASH_THRESHOLD => {
+            "Game Over: Crashed (Too Many Penalties)".to_string()
+        }
+        GameOverCause::Crash => "Game Over: Crashed (Car Wrecked)".to_string(),
         GameOverCause::Overheat => "Game Over: Engine Meltdown".to_string(),
         GameOverCause::None => "Game Over: Unknown".to_string(),
     };
 
+    // In a race the finishing order comes first: finish times for the cars that made it,
+    // how far the others got
+    let mut report_lines: Vec<String> = Vec::new();
+    if race {
+        report_lines.extend(standings.0.iter().enumerate().map(|(index, standing)| {
+            let result = match standing.finish_time {
+                Some(time) => format!("{:.2}s", time),
+                None if standing.retired => "DNF".to_string(),
+                None => format!("{:.0}%", standing.progress * 100.0),
+            };
+            format!("{}. {}  {}", index + 1, standing.name, result)
+        }));
+    }
+
+    // One line per completed lap with its sector times, then the track limits incidents
+    // that cost no time, then every penalty
+    report_lines.extend(
+        session
+            .run
+            .timing
+            .laps
+            .iter()
+            .enumerate()
+            .map(|(index, lap)| {
+                let sectors: Vec<String> = lap
+                    .sectors
+                    .iter()
+                    .map(|sector| format!("{:.2}", sector))
+                    .collect();
+                format!(
+                    "Lap {}: {:.2}s  ({}){}",
+                    index + 1,
+                    lap.time,
+                    sectors.join(" | "),
+                    if lap.valid { "" } else { "  INVALID" }
+                )
+            })
+            .chain(session.run.incidents.iter().map(|incident| {
+                format!(
+                    "Lap {} at {:.2}s: {}",
+                    incident.lap,
+                    incident.time,
+                    incident.summary()
+                )
+            })),
+    );
+    let penalties = &session.run.penalties;
+    if !penalties.list.is_empty() {
+        report_lines.extend(penalties.list.iter().map(|penalty| penalty.summary()));
+        report_lines.push(format!("Penalties: +{:.1}s", penalties.total()));
+    }
+
     let color = if session.game_over_cause == GameOverCause::GoalReached {
         Color::srgb(0.2, 0.8, 0.2)
     } else {
@@ -62,6 +149,70 @@ fn setup_result(mut commands: Commands, asset_server: Res<AssetServer>, session:
                 TextColor(color),
             ));
 
+            if !report_lines.is_empty() {
+                parent.spawn((
+                    Text::new(report_lines.join("\n")),
+                    TextFont {
+                        font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                        font_size: 22.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                ));
+            }
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(250.0),
+                        height: Val::Px(80.0),
+                        margin: UiRect::top(Val::Px(20.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(NORMAL_BUTTON),
+                    ReplayButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Watch Replay"),
+                        get_button_text_font(&asset_server),
+                        get_button_text_color(),
+                    ));
+                });
+
+            // Time attack ghosts can be shared with other players
+            if !race {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(250.0),
+                            height: Val::Px(80.0),
+                            margin: UiRect::top(Val::Px(20.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(NORMAL_BUTTON),
+                        ExportGhostButton,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new("Export Ghost"),
+                            get_button_text_font(&asset_server),
+                            get_button_text_color(),
+                            ExportGhostLabel,
+                        ));
+                    });
+            }
+
             parent
                 .spawn((
                     Button,
@@ -78,7 +229,11 @@ fn setup_result(mut commands: Commands, asset_server: Res<AssetServer>, session:
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Back to Home"),
+                        Text::new(if test_drive.0 {
+                            "Back to Editor"
+                        } else {
+                            "Back to Home"
+                        }),
                         get_button_text_font(&asset_server),
                         get_button_text_color(),
                     ));
@@ -93,18 +248,79 @@ fn cleanup_result(mut commands: Commands, query: Query<Entity, With<ResultUi>>)
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn interact_home_button(
     mut query: Query<
         (&Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<HomeButton>),
     >,
+    test_drive: Res<TestDrive>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     for (interaction, mut color) in &mut query {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::Home);
+                // Test drives go back to the course being edited
+                next_state.set(if test_drive.0 {
+                    AppState::TrackEditor
+                } else {
+                    AppState::Home
+                });
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn interact_replay_button(
+    mut query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ReplayButton>),
+    >,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, mut color) in &mut query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                next_state.set(AppState::Replay);
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+/// Writes the course's personal best ghost to the shared ghosts folder.
+#[allow(clippy::type_complexity)]
+fn interact_export_ghost(
+    mut query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ExportGhostButton>),
+    >,
+    mut label_query: Query<&mut Text, With<ExportGhostLabel>>,
+    session: Res<GameSession>,
+) {
+    for (interaction, mut color) in &mut query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                let label = match export_ghost(&session.course_name) {
+                    Ok(path) => {
+                        info!("Exported ghost to {}", path.display());
+                        "Ghost Exported"
+                    }
+                    Err(error) => {
+                        warn!("Could not export ghost: {}", error);
+                        "No Ghost to Export"
+                    }
+                };
+                for mut text in &mut label_query {
+                    text.0 = label.to_string();
+                }
             }
             Interaction::Hovered => *color = HOVERED_BUTTON.into(),
             Interaction::None => *color = NORMAL_BUTTON.into(),
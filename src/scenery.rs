@@ -0,0 +1,266 @@
+use crate::course::generator::SeedRng;
+use crate::course::mesh;
+use crate::course::{Course, SceneryKind, SceneryPlacement};
+use crate::game::GameWorld;
+use crate::settings::CycleSetting;
+use bevy::prelude::*;
+
+/// Free space kept between scattered scenery and the outer edge of the run-off (m).
+const SCENERY_CLEARANCE: f32 = 8.0;
+/// Depth of the band beside the track that trees are scattered in (m).
+const TREE_BAND: f32 = 60.0;
+/// Buildings stand further back, in a band starting where the trees thin out (m).
+const BUILDING_BAND: (f32, f32) = (50.0, 200.0);
+/// Grandstands this close to the grid or the finish make the automatic ones unnecessary (m).
+const GRANDSTAND_SPAN: f32 = 150.0;
+/// Extra ground beyond the course on every side (m).
+const GROUND_MARGIN: f32 = 2000.0;
+
+/// How much is drawn around the track, picked on the settings screen. Denser scenery
+/// and shadows put more load on the GPU, which the game feels through its temperature.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphicsQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl CycleSetting for GraphicsQuality {
+    const NAME: &'static str = "Graphics";
+
+    fn next(&self) -> Self {
+        match self {
+            GraphicsQuality::Low => GraphicsQuality::Medium,
+            GraphicsQuality::Medium => GraphicsQuality::High,
+            GraphicsQuality::High => GraphicsQuality::Low,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            GraphicsQuality::Low => "Low",
+            GraphicsQuality::Medium => "Medium",
+            GraphicsQuality::High => "High",
+        }
+        .to_string()
+    }
+}
+
+impl GraphicsQuality {
+    /// Scattered trees per 100 m on each side of the track.
+    fn trees_per_100m(&self) -> f32 {
+        match self {
+            GraphicsQuality::Low => 0.5,
+            GraphicsQuality::Medium => 2.0,
+            GraphicsQuality::High => 5.0,
+        }
+    }
+
+    /// Scattered buildings per km on each side of the track.
+    fn buildings_per_km(&self) -> f32 {
+        match self {
+            GraphicsQuality::Low => 1.0,
+            GraphicsQuality::Medium => 3.0,
+            GraphicsQuality::High => 8.0,
+        }
+    }
+
+    fn shadows(&self) -> bool {
+        *self == GraphicsQuality::High
+    }
+}
+
+/// Plugin that registers the graphics quality used to dress the course.
+pub struct SceneryPlugin;
+
+impl Plugin for SceneryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphicsQuality>();
+    }
+}
+
+/// Spawns the environment around a course: the sun, a ground plane under the whole
+/// course, the course's own scenery and procedurally scattered trees, buildings and
+/// grandstands. Every piece of a kind shares one mesh and material, so Bevy draws them
+/// in instanced batches.
+pub fn spawn_scenery(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    course: &Course,
+    quality: GraphicsQuality,
+) {
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: quality.shadows(),
+            ..default()
+        },
+        Transform::from_xyz(50.0, 100.0, 50.0).looking_at(Vec3::ZERO, Vec3::Y),
+        GameWorld,
+    ));
+
+    // Ground just under the lowest point of the road
+    let (start, end) = (mesh::geometry_start(course), course.length());
+    let steps = ((end - start) / 50.0).ceil() as usize;
+    let positions: Vec<Vec3> = (0..=steps)
+        .map(|i| {
+            course
+                .frame_at(start + (end - start) * i as f32 / steps as f32)
+                .position
+        })
+        .collect();
+    let min = positions.iter().fold(Vec3::MAX, |acc, p| acc.min(*p));
+    let max = positions.iter().fold(Vec3::MIN, |acc, p| acc.max(*p));
+    let size = (max - min).xz() + Vec2::splat(GROUND_MARGIN * 2.0);
+    let centre = (min + max) / 2.0;
+    commands.spawn((
+        Mesh3d(meshes.add(Plane3d::new(Vec3::Y, size / 2.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: course.surfaces.fallback.color().darker(0.05),
+            perceptual_roughness: 1.0,
+            ..default()
+        })),
+        Transform::from_xyz(centre.x, min.y - 0.5, centre.z),
+        GameWorld,
+    ));
+
+    let tree_mesh = meshes.add(Cone::new(2.5, 8.0));
+    let building_mesh = meshes.add(Cuboid::new(12.0, 16.0, 12.0));
+    let grandstand_mesh = meshes.add(Cuboid::new(30.0, 6.0, 10.0));
+    let tree_material = materials.add(Color::srgb(0.15, 0.35, 0.15));
+    let building_material = materials.add(Color::srgb(0.55, 0.55, 0.6));
+    let grandstand_material = materials.add(Color::srgb(0.3, 0.35, 0.6));
+    let scattered = scatter_scenery(course, quality);
+    for placement in course.scenery.iter().chain(&scattered) {
+        let (mesh, material, height) = match placement.kind {
+            SceneryKind::Tree => (tree_mesh.clone(), tree_material.clone(), 8.0),
+            SceneryKind::Building => (building_mesh.clone(), building_material.clone(), 16.0),
+            SceneryKind::Grandstand => (grandstand_mesh.clone(), grandstand_material.clone(), 6.0),
+        };
+        // Scenery stands upright on the ground beside the track, facing down the track
+        let frame = course.frame_at(placement.distance);
+        commands.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(
+                ground_position(course, placement) + Vec3::Y * height * placement.scale / 2.0,
+            )
+            .looking_to(frame.tangent.with_y(0.0), Vec3::Y)
+            .with_scale(Vec3::splat(placement.scale)),
+            GameWorld,
+        ));
+    }
+    info!(
+        "Dressed {} with {} scattered scenery pieces ({:?} quality)",
+        course.name,
+        scattered.len(),
+        quality
+    );
+}
+
+/// Where a placement stands: level with the road, `lateral` metres out to the side.
+fn ground_position(course: &Course, placement: &SceneryPlacement) -> Vec3 {
+    let frame = course.frame_at(placement.distance);
+    frame.position + frame.right.with_y(0.0).normalize() * placement.lateral
+}
+
+/// Scatters trees and buildings along the whole course and puts grandstands beside the
+/// grid and the finish. The layout only depends on the course and the quality, so a
+/// course looks the same on every run. Pieces that would end up on or next to any part of
+/// the track (the inside of a hairpin, another part of a loop) are left out.
+pub fn scatter_scenery(course: &Course, quality: GraphicsQuality) -> Vec<SceneryPlacement> {
+    let mut rng = SeedRng(course_seed(course));
+    let (start, end) = (mesh::geometry_start(course), course.length());
+    let edge = course.half_width() + SCENERY_CLEARANCE;
+    let mut placements = Vec::new();
+
+    let mut scatter = |kind: SceneryKind, per_metre: f32, band: (f32, f32), scale: (f32, f32)| {
+        let count = ((end - start) * per_metre).round() as usize;
+        for _ in 0..count {
+            let side = if rng.unit() < 0.5 { -1.0 } else { 1.0 };
+            placements.push(SceneryPlacement {
+                kind,
+                distance: rng.range(start, end),
+                lateral: side * (edge + rng.range(band.0, band.1)),
+                scale: rng.range(scale.0, scale.1),
+            });
+        }
+    };
+    // Nothing grows without air
+    let trees_per_100m = if course.air_density > 0.0 {
+        quality.trees_per_100m()
+    } else {
+        0.0
+    };
+    scatter(
+        SceneryKind::Tree,
+        trees_per_100m * 2.0 / 100.0,
+        (0.0, TREE_BAND),
+        (0.7, 1.4),
+    );
+    scatter(
+        SceneryKind::Building,
+        quality.buildings_per_km() * 2.0 / 1000.0,
+        BUILDING_BAND,
+        (0.8, 1.5),
+    );
+
+    // Grandstands overlooking the grid and the finish, unless the course has its own
+    for distance in [course.start_grid.distance, course.finish] {
+        let has_grandstand = course.scenery.iter().any(|placement| {
+            placement.kind == SceneryKind::Grandstand
+                && (placement.distance - distance).abs() < GRANDSTAND_SPAN
+        });
+        if !has_grandstand {
+            placements.push(SceneryPlacement {
+                kind: SceneryKind::Grandstand,
+                distance,
+                lateral: -(edge + 10.0),
+                scale: 1.0,
+            });
+        }
+    }
+
+    placements.retain(|placement| {
+        course
+            .project(ground_position(course, placement))
+            .lateral
+            .abs()
+            >= course.half_width() + SCENERY_CLEARANCE / 2.0
+    });
+    placements
+}
+
+/// Seed for a course's scenery, from its name (FNV-1a).
+fn course_seed(course: &Course) -> u64 {
+    course
+        .name
+        .bytes()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::file::CourseFile;
+
+    #[test]
+    fn scattered_scenery_stays_clear_of_the_track() {
+        let circuit =
+            CourseFile::parse(include_bytes!("../assets/courses/circuit_1.course.ron")).unwrap();
+        let low = scatter_scenery(&circuit, GraphicsQuality::Low);
+        let high = scatter_scenery(&circuit, GraphicsQuality::High);
+        assert!(high.len() > low.len() * 4);
+        for placement in &high {
+            let projection = circuit.project(ground_position(&circuit, placement));
+            assert!(projection.lateral.abs() > circuit.half_width());
+        }
+        // The same course is always dressed the same way
+        let again = scatter_scenery(&circuit, GraphicsQuality::High);
+        assert_eq!(again.len(), high.len());
+        assert_eq!(again[0].distance, high[0].distance);
+    }
+}
@@ -1,9 +1,15 @@
+use crate::ai::AiDifficulty;
+use crate::countdown::LaunchControl;
+use crate::ghost::GhostSelection;
+use crate::scenery::GraphicsQuality;
 use crate::states::AppState;
+use crate::track_limits::CutPenalty;
 use crate::ui::styles::{
     HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
     get_title_text_color, get_title_text_font,
 };
 use bevy::prelude::*;
+use std::marker::PhantomData;
 
 #[derive(Component)]
 struct SettingsUi;
@@ -11,6 +17,25 @@ struct SettingsUi;
 #[derive(Component)]
 struct BackButton;
 
+/// A setting picked on the settings screen by clicking through its options.
+pub trait CycleSetting: Resource + Sized {
+    /// Name shown on the setting's button.
+    const NAME: &'static str;
+
+    /// Next option when cycling through the options.
+    fn next(&self) -> Self;
+
+    fn label(&self) -> String;
+}
+
+/// Button cycling the setting `S`.
+#[derive(Component)]
+struct CycleButton<S: CycleSetting>(PhantomData<S>);
+
+/// Text on the button of the setting `S`.
+#[derive(Component)]
+struct CycleLabel<S: CycleSetting>(PhantomData<S>);
+
 pub struct SettingsPlugin;
 
 impl Plugin for SettingsPlugin {
@@ -19,12 +44,28 @@ impl Plugin for SettingsPlugin {
             .add_systems(OnExit(AppState::Settings), cleanup_settings)
             .add_systems(
                 Update,
-                interact_settings.run_if(in_state(AppState::Settings)),
+                (
+                    interact_settings,
+                    interact_cycle_setting::<CutPenalty>,
+                    interact_cycle_setting::<GraphicsQuality>,
+                    interact_cycle_setting::<AiDifficulty>,
+                    interact_cycle_setting::<GhostSelection>,
+                    interact_cycle_setting::<LaunchControl>,
+                )
+                    .run_if(in_state(AppState::Settings)),
             );
     }
 }
 
-fn setup_settings(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cut_penalty: Res<CutPenalty>,
+    quality: Res<GraphicsQuality>,
+    difficulty: Res<AiDifficulty>,
+    ghost: Res<GhostSelection>,
+    launch_control: Res<LaunchControl>,
+) {
     commands
         .spawn((
             Node {
@@ -45,11 +86,13 @@ fn setup_settings(mut commands: Commands, asset_server: Res<AssetServer>) {
                 get_title_text_font(&asset_server),
                 get_title_text_color(),
             ));
-            parent.spawn((
-                Text::new("(Settings Not Imeplented Yet - Stub)"),
-                get_button_text_font(&asset_server),
-                TextColor(Color::WHITE),
-            ));
+
+            // Every setting cycles through its options on click
+            spawn_cycle_button(parent, &asset_server, &*cut_penalty);
+            spawn_cycle_button(parent, &asset_server, &*quality);
+            spawn_cycle_button(parent, &asset_server, &*difficulty);
+            spawn_cycle_button(parent, &asset_server, &*ghost);
+            spawn_cycle_button(parent, &asset_server, &*launch_control);
 
             // Back Button
             parent
@@ -75,12 +118,42 @@ fn setup_settings(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
+/// Button showing a setting's name and current option.
+fn spawn_cycle_button<S: CycleSetting>(
+    parent: &mut ChildSpawnerCommands,
+    asset_server: &AssetServer,
+    setting: &S,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(500.0),
+                height: Val::Px(60.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(NORMAL_BUTTON),
+            CycleButton::<S>(PhantomData),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("{}: {}", S::NAME, setting.label())),
+                get_button_text_font(asset_server),
+                get_button_text_color(),
+                CycleLabel::<S>(PhantomData),
+            ));
+        });
+}
+
 fn cleanup_settings(mut commands: Commands, query: Query<Entity, With<SettingsUi>>) {
     for entity in &query {
         commands.entity(entity).despawn();
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn interact_settings(
     mut query: Query<
         (&Interaction, &mut BackgroundColor),
@@ -99,3 +172,28 @@ fn interact_settings(
         }
     }
 }
+
+/// Moves the setting `S` on to its next option when its button is clicked.
+#[allow(clippy::type_complexity)]
+fn interact_cycle_setting<S: CycleSetting>(
+    mut query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<CycleButton<S>>),
+    >,
+    mut label_query: Query<&mut Text, With<CycleLabel<S>>>,
+    mut setting: ResMut<S>,
+) {
+    for (interaction, mut color) in &mut query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                *setting = setting.next();
+                for mut text in &mut label_query {
+                    text.0 = format!("{}: {}", S::NAME, setting.label());
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
@@ -1,9 +1,15 @@
+use crate::course::Course;
+use crate::course::generator::GeneratorParams;
+use crate::course::loader::{CourseLibrary, SelectedCourse};
+use crate::course::preview::{elevation_image, map_image};
+use crate::records::PersonalBests;
 use crate::resources::{BaseCarStatus, CarStatus, PcStatus};
-use crate::states::AppState;
+use crate::states::{AppState, GameMode};
 use crate::ui::styles::{
     HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON, get_button_text_color, get_button_text_font,
     get_title_text_color, get_title_text_font,
 };
+use crate::weather::WeatherSelection;
 use bevy::prelude::*;
 use bevy::render::renderer::RenderAdapterInfo;
 use sysinfo::System as SysinfoSystem;
@@ -22,9 +28,29 @@ struct CarSelectUi;
 #[derive(Component)]
 struct MeasureUi;
 
-/// Marker for the single "Select" button on the course screen.
+/// Component for course list buttons, storing the course's index in the `CourseLibrary`.
 #[derive(Component)]
-struct CourseButton;
+struct CourseButton(usize);
+
+/// Marker for the column listing every loaded course.
+#[derive(Component)]
+struct CourseListPanel;
+
+/// Marker for the panel previewing the highlighted course.
+#[derive(Component)]
+struct CoursePreviewPanel;
+
+/// Index of the course highlighted on the course select screen.
+#[derive(Resource, Default)]
+struct CourseCursor(usize);
+
+/// Marker for the button cycling through the weather options.
+#[derive(Component)]
+struct WeatherButton;
+
+/// Marker for the text showing the currently selected weather.
+#[derive(Component)]
+struct WeatherLabel;
 
 /// Component for car selection buttons, storing the ID of the car type.
 #[derive(Component)]
@@ -45,7 +71,16 @@ impl Plugin for SetupFlowPlugin {
             .add_systems(OnExit(AppState::CourseSelect), cleanup_course_select)
             .add_systems(
                 Update,
-                interact_course_select.run_if(in_state(AppState::CourseSelect)),
+                (
+                    keyboard_course_select,
+                    interact_course_select,
+                    interact_weather_select,
+                    refresh_course_select.run_if(
+                        resource_changed::<CourseCursor>.or(resource_changed::<CourseLibrary>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::CourseSelect)),
             )
             // Car Select
             .add_systems(OnEnter(AppState::CarSelect), setup_car_select)
@@ -72,7 +107,21 @@ impl Plugin for SetupFlowPlugin {
 
 // --- Course Select ---
 
-fn setup_course_select(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_course_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    weather: Res<WeatherSelection>,
+    library: Res<CourseLibrary>,
+    selected: Res<SelectedCourse>,
+) {
+    // Start on the course picked last time
+    let cursor = selected
+        .0
+        .as_ref()
+        .and_then(|handle| library.courses.iter().position(|h| h == handle))
+        .unwrap_or(0);
+    commands.insert_resource(CourseCursor(cursor));
+
     commands
         .spawn((
             Node {
@@ -92,27 +141,74 @@ fn setup_course_select(mut commands: Commands, asset_server: Res<AssetServer>) {
                 get_title_text_font(&asset_server),
                 get_title_text_color(),
             ));
+
+            // Course list on the left, preview of the highlighted course on the right
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::top(Val::Px(20.0)),
+                    column_gap: Val::Px(30.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        CourseListPanel,
+                    ));
+                    parent.spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        CoursePreviewPanel,
+                    ));
+                });
+
+            // Weather picker (cycles through the options on click)
             parent
                 .spawn((
                     Button,
                     Node {
-                        width: Val::Px(250.0),
-                        height: Val::Px(80.0),
+                        width: Val::Px(500.0),
+                        height: Val::Px(60.0),
                         margin: UiRect::top(Val::Px(20.0)),
                         justify_content: JustifyContent::Center,
                         align_items: AlignItems::Center,
                         ..default()
                     },
                     BackgroundColor(NORMAL_BUTTON),
-                    CourseButton,
+                    WeatherButton,
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Course 1"),
+                        Text::new(format!("Weather: {}", weather.label())),
                         get_button_text_font(&asset_server),
                         get_button_text_color(),
+                        WeatherLabel,
                     ));
                 });
+
+            parent.spawn((
+                Text::new(
+                    "Arrow Up / Down: choose course    Enter: confirm    G: generate a new course",
+                ),
+                TextFont {
+                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                    font_size: 20.0,
+                    ..default()
+                },
+                get_button_text_color(),
+                Node {
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
         });
 }
 
@@ -121,20 +217,227 @@ fn cleanup_course_select(mut commands: Commands, query: Query<Entity, With<Cours
     for entity in &query {
         commands.entity(entity).despawn();
     }
+    commands.remove_resource::<CourseCursor>();
+}
+
+/// Rebuilds the course list and the preview of the highlighted course.
+#[allow(clippy::too_many_arguments)]
+fn refresh_course_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<CourseLibrary>,
+    courses: Res<Assets<Course>>,
+    bests: Res<PersonalBests>,
+    cursor: Res<CourseCursor>,
+    mut images: ResMut<Assets<Image>>,
+    list_query: Query<Entity, With<CourseListPanel>>,
+    preview_query: Query<Entity, With<CoursePreviewPanel>>,
+) {
+    let (Ok(list), Ok(preview)) = (list_query.single(), preview_query.single()) else {
+        return;
+    };
+    let small_font = TextFont {
+        font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+        font_size: 24.0,
+        ..default()
+    };
+
+    commands
+        .entity(list)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            if library.courses.is_empty() {
+                parent.spawn((
+                    Text::new("No courses found"),
+                    get_button_text_font(&asset_server),
+                    get_button_text_color(),
+                ));
+            }
+            for (index, handle) in library.courses.iter().enumerate() {
+                let Some(course) = courses.get(handle) else {
+                    continue;
+                };
+                // The highlighted course gets a bright border
+                let border = if index == cursor.0 {
+                    Color::WHITE
+                } else {
+                    NORMAL_BUTTON
+                };
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(300.0),
+                            height: Val::Px(70.0),
+                            border: UiRect::all(Val::Px(3.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(NORMAL_BUTTON),
+                        BorderColor::all(border),
+                        CourseButton(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(course.name.clone()),
+                            get_button_text_font(&asset_server),
+                            get_button_text_color(),
+                        ));
+                    });
+            }
+        });
+
+    commands.entity(preview).despawn_related::<Children>();
+    let Some(course) = library
+        .courses
+        .get(cursor.0)
+        .and_then(|handle| courses.get(handle))
+    else {
+        return;
+    };
+
+    let (low, high) = course.elevation_range();
+    let best = bests
+        .best_time(&course.name)
+        .map(|time| format!("{:.2}s", time))
+        .unwrap_or_else(|| "--".to_string());
+    let length = if course.is_circuit() {
+        format!(
+            "{:.2} km x {} laps",
+            course.lap_length() / 1000.0,
+            course.laps
+        )
+    } else {
+        format!("{:.2} km", course.lap_length() / 1000.0)
+    };
+    let info = format!(
+        "Length: {}\nElevation: {:.0} m ({:.0} m to {:.0} m)\nGravity: {:.2} m/s^2\nAir: {:.2} kg/m^3 at {:.0} C\nDifficulty: {}\nBest Time: {}",
+        length,
+        high - low,
+        low,
+        high,
+        course.gravity,
+        course.air_density,
+        course.ambient_temperature,
+        "*".repeat(course.difficulty() as usize),
+        best
+    );
+    let map = images.add(map_image(course, 256));
+    let profile = images.add(elevation_image(course, 256, 64));
+
+    commands.entity(preview).with_children(|parent| {
+        parent.spawn((
+            ImageNode::new(map),
+            Node {
+                width: Val::Px(256.0),
+                height: Val::Px(256.0),
+                ..default()
+            },
+        ));
+        parent.spawn((
+            ImageNode::new(profile),
+            Node {
+                width: Val::Px(256.0),
+                height: Val::Px(64.0),
+                ..default()
+            },
+        ));
+        parent.spawn((Text::new(info), small_font, get_button_text_color()));
+    });
+}
+
+/// Arrow keys move the highlight through the course list, Enter picks the highlighted course
+/// and G generates a new course from a fresh seed.
+fn keyboard_course_select(
+    input: Res<ButtonInput<KeyCode>>,
+    mut library: ResMut<CourseLibrary>,
+    mut courses: ResMut<Assets<Course>>,
+    mut cursor: ResMut<CourseCursor>,
+    mut selected: ResMut<SelectedCourse>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::KeyG) {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        let params = GeneratorParams { seed, ..default() };
+        match params.generate() {
+            Ok(course) => {
+                info!("Generated course with seed {}", seed);
+                cursor.0 = library.add_generated(courses.add(course));
+            }
+            Err(error) => warn!("Could not generate a course: {}", error),
+        }
+    }
+
+    let count = library.courses.len();
+    if count == 0 {
+        return;
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+    if input.just_pressed(KeyCode::ArrowUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+    if input.just_pressed(KeyCode::Enter) {
+        confirm_course(&library, cursor.0, &mut selected, &mut next_state);
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn interact_course_select(
+    mut query: Query<(&Interaction, &mut BackgroundColor, &CourseButton), Changed<Interaction>>,
+    library: Res<CourseLibrary>,
+    mut cursor: ResMut<CourseCursor>,
+    mut selected: ResMut<SelectedCourse>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, mut color, button) in &mut query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                cursor.0 = button.0;
+                confirm_course(&library, button.0, &mut selected, &mut next_state);
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+/// Makes the course at `index` the one the next run is built from and moves on.
+fn confirm_course(
+    library: &CourseLibrary,
+    index: usize,
+    selected: &mut SelectedCourse,
+    next_state: &mut NextState<AppState>,
+) {
+    if let Some(handle) = library.courses.get(index) {
+        selected.0 = Some(handle.clone());
+        next_state.set(AppState::CarSelect);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn interact_weather_select(
     mut query: Query<
         (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<CourseButton>),
+        (Changed<Interaction>, With<WeatherButton>),
     >,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut label_query: Query<&mut Text, With<WeatherLabel>>,
+    mut weather: ResMut<WeatherSelection>,
 ) {
     for (interaction, mut color) in &mut query {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::CarSelect);
+                *weather = weather.next();
+                for mut text in &mut label_query {
+                    text.0 = format!("Weather: {}", weather.label());
+                }
             }
             Interaction::Hovered => *color = HOVERED_BUTTON.into(),
             Interaction::None => *color = NORMAL_BUTTON.into(),
@@ -287,14 +590,13 @@ fn setup_measure_performance(
 
     for component in &components {
         let label = component.label().to_lowercase();
-        if let Some(t) = component.temperature() {
-            if t > 0.0
-                && (label.contains("cpu") || label.contains("core") || label.contains("package"))
-            {
-                cpu_temp_sum += t;
-                cpu_temp_count += 1;
-                found_cpu_temp = true;
-            }
+        if let Some(t) = component.temperature()
+            && t > 0.0
+            && (label.contains("cpu") || label.contains("core") || label.contains("package"))
+        {
+            cpu_temp_sum += t;
+            cpu_temp_count += 1;
+            found_cpu_temp = true;
         }
     }
     let cpu_temp = if cpu_temp_count > 0 {
@@ -344,7 +646,7 @@ fn setup_measure_performance(
     let ssd_available = disks.iter().map(|d| d.available_space()).sum::<u64>();
 
     // --- Update resources ---
-    pc_status.cpu_frequency = cpu_freq as u64;
+    pc_status.cpu_frequency = cpu_freq;
     pc_status.cpu_usage = cpu_usage;
     pc_status.total_memory = total_memory;
     pc_status.used_memory = used_memory;
@@ -353,6 +655,97 @@ fn setup_measure_performance(
     pc_status.gpu_temp = gpu_temp;
     pc_status.cpu_temp = cpu_temp;
 
+    pc_status.ssd_available = ssd_available;
+    *car_status = measure_car_status(&base_car, &pc_status);
+
+    // --- Performance Results UI ---
+    // Displays the detected hardware specs and resulting car attributes to the player.
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            MeasureUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Measuring PC Performance... Press Enter to Continue"),
+                get_title_text_font(&asset_server),
+                get_title_text_color(),
+            ));
+
+            let stat_style = (
+                TextFont {
+                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            );
+
+            parent.spawn((
+                Text::new(format!("CPU: {} @ {} MHz", cpu_name, cpu_freq)),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!(
+                    "RAM: {} / {} GB",
+                    used_memory / 1024 / 1024 / 1024,
+                    total_memory / 1024 / 1024 / 1024
+                )),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!(
+                    "GPU: {} | {}% | {:.0} MHz",
+                    gpu_name, gpu_usage, gpu_clock
+                )),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!("Temp: CPU {:.0}C + GPU {:.0}C", cpu_temp, gpu_temp)),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+
+            parent.spawn((
+                Text::new("--- Car Status ---"),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!("Max Speed: {:.1}", car_status.max_speed)),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!("Acceleration: {:.1}", car_status.acceleration)),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+            parent.spawn((
+                Text::new(format!("Handling: {:.1}", car_status.handling)),
+                stat_style.clone().0.clone(),
+                stat_style.clone().1,
+            ));
+        });
+}
+
+/// Car stats of a car built to `base_car` on the hardware described by `pc_status`,
+/// following the formulas in specification.md. Used for the player's PC and for the
+/// opponents' synthetic hardware alike.
+pub fn measure_car_status(base_car: &BaseCarStatus, pc_status: &PcStatus) -> CarStatus {
+    let mut car_status = CarStatus::default();
     let const_val = 1.0;
 
     // --- Physics Constants & Scaling (MHz Recalibration) ---
@@ -368,12 +761,14 @@ fn setup_measure_performance(
     const BEVY_BRAKING_SCALE: f32 = 3000.0;
 
     // Data values
-    let cpu_clock = cpu_freq as f32;
-    let ram_used = used_memory as f32;
-    let ram_avail = (total_memory - used_memory) as f32;
-    let ssd_avail = ssd_available as f32;
-    let cpu_u = cpu_usage / 100.0;
-    let gpu_u = gpu_usage / 100.0;
+    let cpu_clock = pc_status.cpu_frequency as f32;
+    let ram_used = pc_status.used_memory as f32;
+    let ram_avail = (pc_status.total_memory - pc_status.used_memory) as f32;
+    let ssd_avail = pc_status.ssd_available as f32;
+    let cpu_u = pc_status.cpu_usage / 100.0;
+    let gpu_u = pc_status.gpu_usage / 100.0;
+    let (cpu_temp, gpu_temp) = (pc_status.cpu_temp, pc_status.gpu_temp);
+    let gpu_clock = pc_status.gpu_clock;
 
     // 1. Max Speed
     // max speed = base max speed * (1 + CPU Impact rate * CPU clock *(1 + CPU usage rate)) * const
@@ -442,87 +837,7 @@ fn setup_measure_performance(
         * const_val
         * BEVY_BRAKING_SCALE;
 
-    // --- Performance Results UI ---
-    // Displays the detected hardware specs and resulting car attributes to the player.
-    commands
-        .spawn((
-            Node {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                flex_direction: FlexDirection::Column,
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                row_gap: Val::Px(10.0),
-                ..default()
-            },
-            BackgroundColor(Color::BLACK),
-            MeasureUi,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                Text::new("Measuring PC Performance... Press Enter to Continue"),
-                get_title_text_font(&asset_server),
-                get_title_text_color(),
-            ));
-
-            let stat_style = (
-                TextFont {
-                    font: asset_server.load("fonts/NotoSansJP-Bold.ttf"),
-                    font_size: 20.0,
-                    ..default()
-                },
-                TextColor(Color::srgb(0.8, 0.8, 0.8)),
-            );
-
-            parent.spawn((
-                Text::new(format!("CPU: {} @ {} MHz", cpu_name, cpu_freq)),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!(
-                    "RAM: {} / {} GB",
-                    used_memory / 1024 / 1024 / 1024,
-                    total_memory / 1024 / 1024 / 1024
-                )),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!(
-                    "GPU: {} | {}% | {:.0} MHz",
-                    gpu_name, gpu_usage, gpu_clock
-                )),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!("Temp: CPU {:.0}C + GPU {:.0}C", cpu_temp, gpu_temp)),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-
-            parent.spawn((
-                Text::new("--- Car Status ---"),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!("Max Speed: {:.1}", car_status.max_speed)),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!("Acceleration: {:.1}", car_status.acceleration)),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-            parent.spawn((
-                Text::new(format!("Handling: {:.1}", car_status.handling)),
-                stat_style.clone().0.clone(),
-                stat_style.clone().1,
-            ));
-        });
+    car_status
 }
 
 fn cleanup_measure_performance(mut commands: Commands, query: Query<Entity, With<MeasureUi>>) {
@@ -533,9 +848,10 @@ fn cleanup_measure_performance(mut commands: Commands, query: Query<Entity, With
 
 fn update_measure_performance(
     input: Res<ButtonInput<KeyCode>>,
+    mode: Res<GameMode>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     if input.just_pressed(KeyCode::Enter) {
-        next_state.set(AppState::TimeAttackGame);
+        next_state.set(mode.after_setup());
     }
 }
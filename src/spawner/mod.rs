@@ -1 +0,0 @@
-pub mod spawner;
\ No newline at end of file
@@ -1,2 +0,0 @@
-use bevy::prelude::*;
-
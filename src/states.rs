@@ -8,8 +8,104 @@ pub enum AppState {
     CourseSelect,
     CarSelect,
     MeasurePerformance,
+    RaceGrid,
     TimeAttackGame,
+    RaceGame,
     Result,
+    /// Passes straight back into the game, so a restarted run is set up from scratch.
+    Restarting,
+    Replay,
     Settings,
     CalcInfo,
+    TrackEditor,
+}
+
+/// Active while a car is on track, in any game mode. The course, car and rule systems
+/// run in this state so every mode shares them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = AppState;
+
+    fn compute(state: AppState) -> Option<Self> {
+        matches!(state, AppState::TimeAttackGame | AppState::RaceGame).then_some(InGame)
+    }
+}
+
+/// Phase of a run while in game: the start lights counting down, then racing. The car
+/// rules, physics and timing only run once the race is on.
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(InGame = InGame)]
+pub enum RacePhase {
+    #[default]
+    Countdown,
+    Racing,
+}
+
+/// Whether the game is paused (Esc) while in game. Physics, the clocks and fuel burn
+/// stop while paused; the hardware sensors are still read.
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(InGame = InGame)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Active while the race is on and not paused: the car rules, physics and timing run in
+/// this state.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Racing;
+
+impl ComputedStates for Racing {
+    type SourceStates = (RacePhase, PauseState);
+
+    fn compute((phase, pause): (RacePhase, PauseState)) -> Option<Self> {
+        (phase == RacePhase::Racing && pause == PauseState::Running).then_some(Racing)
+    }
+}
+
+/// Active while a course is on screen: when driving it, or watching a replay of it. The
+/// course's visuals are built and torn down with this state.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct OnTrack;
+
+impl ComputedStates for OnTrack {
+    type SourceStates = AppState;
+
+    fn compute(state: AppState) -> Option<Self> {
+        matches!(
+            state,
+            AppState::TimeAttackGame | AppState::RaceGame | AppState::Replay
+        )
+        .then_some(OnTrack)
+    }
+}
+
+/// Game mode picked on the mode select screen, which decides where the setup flow leads.
+#[derive(Resource, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum GameMode {
+    #[default]
+    TimeAttack,
+    Race,
+}
+
+impl GameMode {
+    /// State the setup flow hands over to: straight into a time attack, or the starting
+    /// grid before a race.
+    pub fn after_setup(&self) -> AppState {
+        match self {
+            GameMode::TimeAttack => AppState::TimeAttackGame,
+            GameMode::Race => AppState::RaceGrid,
+        }
+    }
+
+    /// State the game is played in once the car is set up.
+    pub fn game_state(&self) -> AppState {
+        match self {
+            GameMode::TimeAttack => AppState::TimeAttackGame,
+            GameMode::Race => AppState::RaceGame,
+        }
+    }
 }
@@ -0,0 +1,103 @@
+use crate::course::Course;
+use crate::events::{CheckpointPassed, LapCompleted};
+use crate::resources::{CarProgress, GameSession, LapTiming};
+use crate::states::Racing;
+use bevy::prelude::*;
+
+/// Plugin that times laps and sectors as the car drives through the course's gates.
+pub struct TimingPlugin;
+
+impl Plugin for TimingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, gate_timing_system.run_if(in_state(Racing)));
+    }
+}
+
+/// Checks every car's movement this frame against the timing gates.
+fn gate_timing_system(
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut cars: Query<(Entity, &Transform, &mut CarProgress)>,
+    mut checkpoints: MessageWriter<CheckpointPassed>,
+    mut laps: MessageWriter<LapCompleted>,
+) {
+    if session.is_game_over {
+        return;
+    }
+    let gate_count = course.sector_count();
+    for (car, transform, mut progress) in &mut cars {
+        if progress.ended.is_some() {
+            continue;
+        }
+        let CarProgress { time, timing, .. } = &mut *progress;
+        let passed = timing.splits.len();
+        let lap = track_gates(&course, timing, *time, transform.translation);
+        // Gates are passed in order from the start, so the split count gives the gate
+        for (index, split) in timing.splits.iter().enumerate().skip(passed) {
+            checkpoints.write(CheckpointPassed {
+                car,
+                gate: index % gate_count,
+                time: *split,
+            });
+        }
+        if let Some(time) = lap {
+            laps.write(LapCompleted {
+                car,
+                lap: timing.laps.len() as u32,
+                time,
+            });
+        }
+    }
+}
+
+/// Moves a car's timing on to `position` at run time `time`, passing the gates it drove
+/// through on the way. Only the next gate in order counts. Driving through a later one
+/// means the car skipped a checkpoint (cut a corner), and the lap can't be completed until
+/// it goes back for it. Returns the lap time if the car completed a lap.
+pub fn track_gates(
+    course: &Course,
+    timing: &mut LapTiming,
+    time: f32,
+    position: Vec3,
+) -> Option<f32> {
+    let previous = timing.last_position.replace(position)?;
+
+    let gate_count = course.sector_count();
+    let mut completed = None;
+    for (index, gate) in course.gates().enumerate() {
+        if !course.crosses_gate(gate, previous, position) {
+            continue;
+        }
+        let next = timing.next_gate;
+        if index == next {
+            completed = timing.pass_gate(time, gate_count).map(|lap| lap.time);
+        } else if index > next && timing.missed_gate.is_none() {
+            warn!("Missed checkpoint {} of {}", next + 1, course.name);
+            timing.missed_gate = Some(next);
+        }
+    }
+    completed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::LapTiming;
+
+    #[test]
+    fn gates_split_laps_into_sectors() {
+        let mut timing = LapTiming::default();
+        assert!(timing.pass_gate(10.0, 3).is_none());
+        assert!(timing.pass_gate(25.0, 3).is_none());
+        let lap = timing.pass_gate(40.0, 3).unwrap();
+        assert_eq!(lap.time, 40.0);
+        assert_eq!(lap.sectors, vec![10.0, 15.0, 15.0]);
+
+        timing.pass_gate(48.0, 3);
+        timing.pass_gate(60.0, 3);
+        timing.pass_gate(75.0, 3);
+        assert_eq!(timing.laps[1].time, 35.0);
+        assert_eq!(timing.best_lap(), Some(35.0));
+        assert_eq!(timing.splits.len(), 6);
+        assert_eq!(timing.next_gate, 0);
+    }
+}
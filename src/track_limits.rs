@@ -0,0 +1,377 @@
+use crate::car::components::*;
+use crate::course::Course;
+use crate::events::{PenaltyApplied, TrackLimitsBreached};
+use crate::resources::{
+    CarProgress, GameSession, IncidentKind, Penalty, PenaltyKind, TrackIncident,
+};
+use crate::settings::CycleSetting;
+use crate::states::Racing;
+use bevy::prelude::*;
+
+/// Track distance a car must gain while off the track before it counts as a cut (m).
+/// Running wide or dipping a wheel over the kerb gains nothing; only shortcuts do.
+const CUT_THRESHOLD: f32 = 10.0;
+/// Seconds added to the run time for a cut under `CutPenalty::TimePenalty`.
+pub const CUT_TIME_PENALTY: f32 = 5.0;
+/// Speed against the course direction (m/s) that counts as driving the wrong way.
+const WRONG_WAY_SPEED: f32 = 5.0;
+/// Seconds of driving the wrong way before the warning shows, so short reversing is fine.
+const WRONG_WAY_TIME: f32 = 2.0;
+/// Movement in one frame beyond which the car was reset, not driven (m).
+const TELEPORT_DISTANCE: f32 = 50.0;
+
+/// How corner cuts are punished, picked on the settings screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutPenalty {
+    #[default]
+    TimePenalty, // CUT_TIME_PENALTY seconds added to the run
+    InvalidateLap, // The lap doesn't count for records
+    WarningOnly,   // Logged, but no penalty
+}
+
+impl CycleSetting for CutPenalty {
+    const NAME: &'static str = "Corner Cuts";
+
+    fn next(&self) -> Self {
+        match self {
+            CutPenalty::TimePenalty => CutPenalty::InvalidateLap,
+            CutPenalty::InvalidateLap => CutPenalty::WarningOnly,
+            CutPenalty::WarningOnly => CutPenalty::TimePenalty,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            CutPenalty::TimePenalty => format!("+{:.0}s Penalty", CUT_TIME_PENALTY),
+            CutPenalty::InvalidateLap => "Invalidate Lap".to_string(),
+            CutPenalty::WarningOnly => "Warning Only".to_string(),
+        }
+    }
+}
+
+/// Per-car bookkeeping for the track limits checks.
+#[derive(Component, Default, Debug)]
+pub struct TrackLimitState {
+    last_position: Option<Vec3>,
+    last_distance: f32,
+    cut_gain: f32,       // Track distance gained since all wheels left the track (m)
+    wrong_way_time: f32, // Seconds spent driving against the course direction
+}
+
+/// Plugin that watches every car on track, the player's and the opponents', for driving
+/// the wrong way and for cutting the track.
+pub struct TrackLimitsPlugin;
+
+impl Plugin for TrackLimitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CutPenalty>()
+            .add_systems(Update, track_limits_system.run_if(in_state(Racing)));
+    }
+}
+
+/// Detects wrong-way driving and cuts. A cut is track distance gained beyond what the car
+/// actually drove while no wheel was on the track, so it catches shortcuts across grass
+/// or gravel wherever they are, not just past checkpoints.
+fn track_limits_system(
+    time: Res<Time>,
+    penalty: Res<CutPenalty>,
+    session: Res<GameSession>,
+    course: Res<Course>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &mut TrackLimitState,
+        &mut CarProgress,
+    )>,
+    mut penalties: MessageWriter<PenaltyApplied>,
+    mut breaches: MessageWriter<TrackLimitsBreached>,
+) {
+    if session.is_game_over {
+        return;
+    }
+    for (car, transform, velocity, mut state, mut progress) in &mut query {
+        if progress.ended.is_some() {
+            continue;
+        }
+        let recorded = progress.incidents.len();
+        if let Some(penalty) = check_track_limits(
+            &time,
+            *penalty,
+            &course,
+            transform,
+            velocity,
+            &mut state,
+            &mut progress,
+        ) {
+            penalties.write(PenaltyApplied { car, penalty });
+        }
+        for incident in &progress.incidents[recorded..] {
+            breaches.write(TrackLimitsBreached {
+                car,
+                incident: *incident,
+            });
+        }
+    }
+}
+
+/// Runs the track limits checks of one car for this frame. Returns the time penalty it
+/// was given, if any.
+fn check_track_limits(
+    time: &Time,
+    penalty: CutPenalty,
+    course: &Course,
+    transform: &Transform,
+    velocity: &Velocity,
+    state: &mut TrackLimitState,
+    run: &mut CarProgress,
+) -> Option<Penalty> {
+    let position = transform.translation;
+    let projection = course.project(position);
+    let frame = course.frame_at(projection.distance);
+
+    // --- Wrong way ---
+    if velocity.0.dot(frame.tangent) < -WRONG_WAY_SPEED {
+        state.wrong_way_time += time.delta_secs();
+    } else {
+        state.wrong_way_time = 0.0;
+    }
+    let wrong_way = state.wrong_way_time >= WRONG_WAY_TIME;
+    if wrong_way && !run.wrong_way {
+        warn!("Driving the wrong way on {}", course.name);
+        record_incident(run, IncidentKind::WrongWay, false);
+    }
+    run.wrong_way = wrong_way;
+
+    // --- Cuts ---
+    let Some(last_position) = state.last_position.replace(position) else {
+        state.last_distance = projection.distance;
+        return None;
+    };
+    let travelled = last_position.distance(position);
+    let progress = track_progress(course, state.last_distance, projection.distance);
+    state.last_distance = projection.distance;
+    if travelled > TELEPORT_DISTANCE {
+        state.cut_gain = 0.0;
+        return None;
+    }
+
+    let on_track = WHEEL_OFFSETS.iter().any(|offset| {
+        let wheel = course.project(transform.transform_point(*offset));
+        course.surface_at(&wheel).is_track()
+    });
+    if !on_track {
+        state.cut_gain += (progress - travelled).max(0.0);
+        return None;
+    }
+    let gained = std::mem::take(&mut state.cut_gain);
+    if gained < CUT_THRESHOLD {
+        return None;
+    }
+
+    warn!(
+        "Track limits: cut gaining {:.0} m on {}",
+        gained, course.name
+    );
+    let kind = IncidentKind::Cut { gained };
+    match penalty {
+        CutPenalty::TimePenalty => {
+            return Some(run.penalise(PenaltyKind::Cut, CUT_TIME_PENALTY));
+        }
+        CutPenalty::InvalidateLap => {
+            run.timing.lap_invalid = true;
+            record_incident(run, kind, true);
+        }
+        CutPenalty::WarningOnly => record_incident(run, kind, false),
+    }
+    None
+}
+
+/// Distance moved along the track between two projections, wrapping around circuits.
+fn track_progress(course: &Course, from: f32, to: f32) -> f32 {
+    let progress = to - from;
+    if !course.is_circuit() {
+        return progress;
+    }
+    let length = course.length();
+    (progress + length / 2.0).rem_euclid(length) - length / 2.0
+}
+
+fn record_incident(run: &mut CarProgress, kind: IncidentKind, lap_invalidated: bool) {
+    let incident = TrackIncident {
+        time: run.time,
+        lap: run.timing.laps.len() as u32 + 1,
+        kind,
+        lap_invalidated,
+    };
+    run.incidents.push(incident);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::file::CourseFile;
+    use std::time::Duration;
+
+    /// A car driven by hand through the track limits checks.
+    struct Drive {
+        course: Course,
+        rule: CutPenalty,
+        time: Time,
+        position: Vec3,
+        state: TrackLimitState,
+        run: CarProgress,
+        penalties: Vec<Penalty>,
+    }
+
+    impl Drive {
+        /// A car standing at `lateral` from the centreline, `distance` along `course`.
+        fn from(course: Course, rule: CutPenalty, distance: f32, lateral: f32) -> Self {
+            let mut time = Time::default();
+            time.advance_by(Duration::from_millis(100));
+            let mut drive = Self {
+                position: course.frame_at(distance).point(lateral),
+                course,
+                rule,
+                time,
+                state: TrackLimitState::default(),
+                run: CarProgress::default(),
+                penalties: Vec::new(),
+            };
+            drive.step(drive.position, Vec3::ZERO);
+            drive
+        }
+
+        /// Drives in a straight line to `lateral`, `distance` along the course, at 10 m/s.
+        fn to(&mut self, distance: f32, lateral: f32) {
+            let target = self.course.frame_at(distance).point(lateral);
+            let direction = (target - self.position).normalize();
+            let steps = self.position.distance(target).ceil() as usize;
+            let start = self.position;
+            for step in 1..=steps {
+                let position = start.lerp(target, step as f32 / steps as f32);
+                self.step(position, direction * 10.0);
+            }
+        }
+
+        /// Puts the car down at `lateral`, `distance` along the course, as a reset does.
+        fn jump(&mut self, distance: f32, lateral: f32) {
+            let target = self.course.frame_at(distance).point(lateral);
+            self.step(target, Vec3::ZERO);
+        }
+
+        fn step(&mut self, position: Vec3, velocity: Vec3) {
+            let facing = if velocity == Vec3::ZERO {
+                Transform::from_translation(position)
+            } else {
+                Transform::from_translation(position).looking_to(velocity, Vec3::Y)
+            };
+            self.position = position;
+            self.run.time += self.time.delta_secs();
+            self.penalties.extend(check_track_limits(
+                &self.time,
+                self.rule,
+                &self.course,
+                &facing,
+                &Velocity(velocity),
+                &mut self.state,
+                &mut self.run,
+            ));
+        }
+    }
+
+    fn circuit() -> Course {
+        CourseFile::parse(include_bytes!("../assets/courses/circuit_1.course.ron")).unwrap()
+    }
+
+    /// Straight across the inside of the first hairpin, kerb to kerb.
+    fn cut_the_hairpin(rule: CutPenalty) -> Drive {
+        let mut drive = Drive::from(circuit(), rule, 380.0, 9.0);
+        drive.to(580.0, 9.0);
+        drive
+    }
+
+    #[test]
+    fn cuts_get_the_chosen_penalty() {
+        let drive = cut_the_hairpin(CutPenalty::TimePenalty);
+        assert_eq!(drive.penalties.len(), 1);
+        assert_eq!(drive.penalties[0].kind, PenaltyKind::Cut);
+        assert_eq!(drive.run.penalties.total(), CUT_TIME_PENALTY);
+        assert!(drive.run.incidents.is_empty());
+        assert!(!drive.run.timing.lap_invalid);
+
+        let drive = cut_the_hairpin(CutPenalty::InvalidateLap);
+        assert!(drive.penalties.is_empty());
+        assert!(drive.run.timing.lap_invalid);
+        assert_eq!(drive.run.incidents.len(), 1);
+        assert!(drive.run.incidents[0].lap_invalidated);
+
+        let drive = cut_the_hairpin(CutPenalty::WarningOnly);
+        assert!(drive.penalties.is_empty());
+        assert!(!drive.run.timing.lap_invalid);
+        assert!(matches!(
+            drive.run.incidents[..],
+            [TrackIncident {
+                kind: IncidentKind::Cut { gained },
+                lap_invalidated: false,
+                ..
+            }] if gained >= CUT_THRESHOLD
+        ));
+    }
+
+    #[test]
+    fn running_wide_is_no_cut() {
+        let mut drive = Drive::from(circuit(), CutPenalty::TimePenalty, 380.0, 0.0);
+        // Round the outside of the hairpin on the grass, then back onto the track
+        for distance in (400..=560).step_by(10) {
+            drive.to(distance as f32, -16.0);
+        }
+        drive.to(580.0, 0.0);
+        assert!(drive.penalties.is_empty());
+        assert!(drive.run.incidents.is_empty());
+    }
+
+    #[test]
+    fn resets_forget_the_distance_gained() {
+        let mut drive = Drive::from(circuit(), CutPenalty::TimePenalty, 380.0, 9.0);
+        drive.to(480.0, 36.0);
+        assert!(drive.state.cut_gain > 0.0);
+        drive.jump(580.0, 0.0);
+        drive.to(600.0, 0.0);
+        assert!(drive.penalties.is_empty());
+        assert!(drive.run.incidents.is_empty());
+    }
+
+    #[test]
+    fn driving_backwards_is_the_wrong_way() {
+        let course =
+            CourseFile::parse(include_bytes!("../assets/courses/course_1.course.ron")).unwrap();
+        let mut drive = Drive::from(course, CutPenalty::TimePenalty, 500.0, 0.0);
+        // A second of reversing is fine
+        drive.to(490.0, 0.0);
+        assert!(!drive.run.wrong_way);
+        drive.to(400.0, 0.0);
+        assert!(drive.run.wrong_way);
+        drive.to(450.0, 0.0);
+        assert!(!drive.run.wrong_way);
+        assert!(matches!(
+            drive.run.incidents[..],
+            [TrackIncident {
+                kind: IncidentKind::WrongWay,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn progress_wraps_around_circuits() {
+        let circuit = circuit();
+        let length = circuit.length();
+        assert!((track_progress(&circuit, length - 5.0, 5.0) - 10.0).abs() < 1e-3);
+        assert!((track_progress(&circuit, 5.0, length - 5.0) + 10.0).abs() < 1e-3);
+
+        let sprint =
+            CourseFile::parse(include_bytes!("../assets/courses/course_1.course.ron")).unwrap();
+        assert_eq!(track_progress(&sprint, 100.0, 120.0), 20.0);
+    }
+}
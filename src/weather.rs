@@ -0,0 +1,314 @@
+use crate::car::components::*;
+use crate::course::Course;
+use crate::course::streaming::VIEW_DISTANCE;
+use crate::resources::{CarState, PcStatus};
+use crate::states::{InGame, PauseState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Length (along the track) of one wetness cell in metres.
+const CELL_LENGTH: f32 = 20.0;
+/// Width (across the track) of one wetness cell in metres.
+const CELL_WIDTH: f32 = 4.0;
+/// Wetness added per second at full rain intensity.
+const RAIN_WET_RATE: f32 = 0.05;
+/// Wetness removed per second by natural evaporation when it isn't raining.
+const EVAPORATION_RATE: f32 = 0.002;
+/// Wetness removed per second from a cell for every m/s of a wheel rolling over it.
+const TYRE_DRY_RATE: f32 = 0.004;
+/// Grip lost on a fully wet surface.
+const WET_GRIP_LOSS: f32 = 0.35;
+/// Braking force lost on a fully wet surface.
+const WET_BRAKING_LOSS: f32 = 0.45;
+
+/// Weather type of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Dry,
+    Damp,
+    Rain,
+    Fog,
+}
+
+impl Weather {
+    /// Wetness of the whole track at the start of a run.
+    pub fn initial_wetness(&self) -> f32 {
+        match self {
+            Weather::Dry => 0.0,
+            Weather::Damp => 0.4,
+            Weather::Rain => 0.8,
+            Weather::Fog => 0.2,
+        }
+    }
+
+    /// How hard it rains (0.0 - 1.0).
+    pub fn rain_intensity(&self) -> f32 {
+        match self {
+            Weather::Rain => 0.8,
+            _ => 0.0,
+        }
+    }
+
+    /// Distance (m) the driver can see, or `None` for clear air.
+    pub fn visibility(&self) -> Option<f32> {
+        match self {
+            Weather::Dry => None,
+            Weather::Damp => Some(1500.0),
+            Weather::Rain => Some(400.0),
+            Weather::Fog => Some(120.0),
+        }
+    }
+
+    /// Colour of the sky, which the distance fog fades into.
+    pub fn sky_color(&self) -> Color {
+        match self {
+            Weather::Dry => Color::srgb(0.55, 0.72, 0.9),
+            Weather::Damp => Color::srgb(0.62, 0.68, 0.75),
+            Weather::Rain => Color::srgb(0.45, 0.48, 0.52),
+            Weather::Fog => Color::srgb(0.6, 0.62, 0.66),
+        }
+    }
+}
+
+/// Weather picked on the course select screen for the next run.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeatherSelection {
+    #[default]
+    CourseDefault,
+    Fixed(Weather),
+    /// Rain follows the PC's RAM pressure: the fuller the memory, the harder it rains.
+    Hardware,
+}
+
+impl WeatherSelection {
+    /// Next option when cycling through the selection on the course select screen.
+    pub fn next(&self) -> Self {
+        match self {
+            WeatherSelection::CourseDefault => WeatherSelection::Fixed(Weather::Dry),
+            WeatherSelection::Fixed(Weather::Dry) => WeatherSelection::Fixed(Weather::Damp),
+            WeatherSelection::Fixed(Weather::Damp) => WeatherSelection::Fixed(Weather::Rain),
+            WeatherSelection::Fixed(Weather::Rain) => WeatherSelection::Fixed(Weather::Fog),
+            WeatherSelection::Fixed(Weather::Fog) => WeatherSelection::Hardware,
+            WeatherSelection::Hardware => WeatherSelection::CourseDefault,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            WeatherSelection::CourseDefault => "Course Default".to_string(),
+            WeatherSelection::Fixed(weather) => format!("{:?}", weather),
+            WeatherSelection::Hardware => "PC Linked (RAM)".to_string(),
+        }
+    }
+}
+
+/// Resource describing the state of the track surface and the air for the current run.
+/// Shared by the game world (fog) and `car_physics_system` (grip and braking).
+#[derive(Resource, Debug, Clone)]
+pub struct TrackConditions {
+    pub weather: Weather,
+    pub rain_intensity: f32,          // 0.0 - 1.0
+    pub hardware_linked: bool,        // Rain intensity follows RAM pressure
+    pub cells_along: usize,           // Number of wetness cells along the track
+    pub cells_across: usize,          // Number of wetness cells across the track
+    pub half_width: f32,              // Lateral extent covered by the grid on each side
+    pub wetness: Vec<f32>,            // 0.0 (dry) - 1.0 (soaked), row-major along the track
+    applied_visuals: Option<Weather>, // Weather the camera fog was last set up for
+}
+
+impl TrackConditions {
+    pub fn new(course: &Course, weather: Weather, hardware_linked: bool) -> Self {
+        let half_width = course.half_width();
+        let cells_along = (course.length() / CELL_LENGTH).ceil() as usize;
+        let cells_across = (half_width * 2.0 / CELL_WIDTH).ceil() as usize;
+        Self {
+            weather,
+            rain_intensity: weather.rain_intensity(),
+            hardware_linked,
+            cells_along,
+            cells_across,
+            half_width,
+            wetness: vec![weather.initial_wetness(); cells_along * cells_across],
+            applied_visuals: None,
+        }
+    }
+
+    fn cell_index(&self, distance: f32, lateral: f32) -> Option<usize> {
+        if distance < 0.0 || lateral.abs() > self.half_width {
+            return None;
+        }
+        let along = (distance / CELL_LENGTH) as usize;
+        let across =
+            (((lateral + self.half_width) / CELL_WIDTH) as usize).min(self.cells_across - 1);
+        (along < self.cells_along).then_some(along * self.cells_across + across)
+    }
+
+    /// Wetness at course coordinates. Outside the grid the track is as wet as the weather says.
+    pub fn wetness_at(&self, distance: f32, lateral: f32) -> f32 {
+        self.cell_index(distance, lateral)
+            .map(|i| self.wetness[i])
+            .unwrap_or(self.weather.initial_wetness())
+    }
+
+    /// Grip multiplier at course coordinates.
+    pub fn grip_at(&self, distance: f32, lateral: f32) -> f32 {
+        1.0 - self.wetness_at(distance, lateral) * WET_GRIP_LOSS
+    }
+
+    /// Braking force multiplier at course coordinates.
+    pub fn braking_at(&self, distance: f32, lateral: f32) -> f32 {
+        1.0 - self.wetness_at(distance, lateral) * WET_BRAKING_LOSS
+    }
+
+    /// Dries the cell at course coordinates by `amount`.
+    pub fn dry(&mut self, distance: f32, lateral: f32, amount: f32) {
+        if let Some(i) = self.cell_index(distance, lateral) {
+            self.wetness[i] = (self.wetness[i] - amount).max(0.0);
+        }
+    }
+
+    /// Average wetness over the whole track, shown on the HUD.
+    pub fn average_wetness(&self) -> f32 {
+        if self.wetness.is_empty() {
+            return 0.0;
+        }
+        self.wetness.iter().sum::<f32>() / self.wetness.len() as f32
+    }
+}
+
+/// Plugin that evolves track conditions during a run and applies them to the game world.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherSelection>()
+            .add_systems(OnExit(InGame), clear_weather_visuals)
+            .add_systems(
+                Update,
+                (
+                    hardware_rain_system,
+                    track_wetness_system,
+                    weather_visuals_system,
+                )
+                    .chain()
+                    .run_if(in_state(PauseState::Running))
+                    .run_if(resource_exists::<TrackConditions>),
+            );
+    }
+}
+
+/// When the weather is linked to the PC, rain intensity follows RAM pressure.
+/// Memory below half full keeps it dry; a completely full RAM means a downpour.
+fn hardware_rain_system(pc_status: Res<PcStatus>, mut conditions: ResMut<TrackConditions>) {
+    if !conditions.hardware_linked || pc_status.total_memory == 0 {
+        return;
+    }
+    let pressure = pc_status.used_memory as f32 / pc_status.total_memory as f32;
+    let intensity = ((pressure - 0.5) / 0.5).clamp(0.0, 1.0);
+    if (intensity - conditions.rain_intensity).abs() > f32::EPSILON {
+        conditions.rain_intensity = intensity;
+        conditions.weather = if intensity > 0.5 {
+            Weather::Rain
+        } else if intensity > 0.1 {
+            Weather::Damp
+        } else {
+            Weather::Dry
+        };
+    }
+}
+
+/// Rain wets the whole track while tyres dry out the lines the cars drive.
+fn track_wetness_system(
+    time: Res<Time>,
+    course: Res<Course>,
+    mut conditions: ResMut<TrackConditions>,
+    query: Query<(&Transform, &Velocity), With<CarState>>,
+) {
+    let dt = time.delta_secs();
+
+    let change = if conditions.rain_intensity > 0.0 {
+        conditions.rain_intensity * RAIN_WET_RATE * dt
+    } else {
+        -EVAPORATION_RATE * dt
+    };
+    for wetness in conditions.wetness.iter_mut() {
+        *wetness = (*wetness + change).clamp(0.0, 1.0);
+    }
+
+    for (transform, velocity) in &query {
+        let amount = velocity.0.length() * TYRE_DRY_RATE * dt;
+        for offset in WHEEL_OFFSETS {
+            let wheel = course.project(transform.transform_point(offset));
+            conditions.dry(wheel.distance, wheel.lateral, amount);
+        }
+    }
+}
+
+/// Keeps the sky colour and the camera's distance fog in sync with the current visibility.
+/// Clear air still gets a light haze ending at the streaming view distance, so course
+/// chunks fade in instead of popping up at the horizon.
+fn weather_visuals_system(
+    mut commands: Commands,
+    mut conditions: ResMut<TrackConditions>,
+    camera_query: Query<Entity, With<Camera3d>>,
+) {
+    if conditions.applied_visuals == Some(conditions.weather) {
+        return;
+    }
+    let Some(camera) = camera_query.iter().next() else {
+        return;
+    };
+    let sky = conditions.weather.sky_color();
+    let visibility = conditions
+        .weather
+        .visibility()
+        .unwrap_or(VIEW_DISTANCE)
+        .min(VIEW_DISTANCE);
+    commands.entity(camera).insert(DistanceFog {
+        color: sky,
+        falloff: FogFalloff::from_visibility(visibility),
+        ..default()
+    });
+    commands.insert_resource(ClearColor(sky));
+    conditions.applied_visuals = Some(conditions.weather);
+}
+
+fn clear_weather_visuals(mut commands: Commands, camera_query: Query<Entity, With<Camera3d>>) {
+    for camera in &camera_query {
+        commands.entity(camera).remove::<DistanceFog>();
+    }
+    commands.insert_resource(ClearColor::default());
+    commands.remove_resource::<TrackConditions>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::file::CourseFile;
+
+    fn course_1() -> Course {
+        CourseFile::parse(include_bytes!("../assets/courses/course_1.course.ron")).unwrap()
+    }
+
+    #[test]
+    fn driving_dries_only_the_line_taken() {
+        let course = course_1();
+        let mut conditions = TrackConditions::new(&course, Weather::Damp, false);
+        let before = conditions.wetness_at(100.0, 0.0);
+
+        conditions.dry(100.0, 0.0, 0.1);
+
+        assert!(conditions.wetness_at(100.0, 0.0) < before);
+        assert_eq!(conditions.wetness_at(100.0, 15.0), before);
+        assert!(conditions.grip_at(100.0, 0.0) > conditions.grip_at(100.0, 15.0));
+    }
+
+    #[test]
+    fn dry_weather_has_full_grip() {
+        let course = course_1();
+        let conditions = TrackConditions::new(&course, Weather::Dry, false);
+        assert_eq!(conditions.grip_at(10.0, 0.0), 1.0);
+        assert_eq!(conditions.braking_at(10.0, 0.0), 1.0);
+    }
+}